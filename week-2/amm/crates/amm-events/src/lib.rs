@@ -0,0 +1,169 @@
+//! Decodes the events emitted by the AMM program from transaction log messages
+//!
+//! Anchor's `emit!` writes each event as a `Program data: <base64>` log line,
+//! where the payload is the event's 8-byte discriminator followed by its Borsh
+//! encoding. Only lines logged while the AMM program itself is executing are
+//! decoded, so another program cannot forge events by logging the same bytes.
+
+use amm::events::{
+    ClCollectFeesEvent, ClLiquidityEvent, ClSwapEvent, ClaimEvent, CloseOrderEvent, ClosePoolEvent,
+    CollectProtocolFeesEvent, DepositEvent, ExecuteOrdersEvent, ExtendFarmEvent, FlashLoanEvent,
+    InitializeEvent, PlaceOrderEvent, RefundFarmEvent, RevokePermissionEvent, SetPermissionEvent,
+    StakeEvent, SwapEvent, UnstakeEvent, UpdatePoolEvent, WithdrawEvent, WithdrawOrderProceedsEvent,
+};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Prefix of the log lines `emit!` writes
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// An event emitted by the AMM program
+#[derive(Clone, Debug)]
+pub enum AmmEvent {
+    Initialize(InitializeEvent),
+    Swap(SwapEvent),
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    FlashLoan(FlashLoanEvent),
+    CollectProtocolFees(CollectProtocolFeesEvent),
+    UpdatePool(UpdatePoolEvent),
+    ClosePool(ClosePoolEvent),
+    SetPermission(SetPermissionEvent),
+    RevokePermission(RevokePermissionEvent),
+    PlaceOrder(PlaceOrderEvent),
+    ExecuteOrders(ExecuteOrdersEvent),
+    WithdrawOrderProceeds(WithdrawOrderProceedsEvent),
+    CloseOrder(CloseOrderEvent),
+    Stake(StakeEvent),
+    Unstake(UnstakeEvent),
+    Claim(ClaimEvent),
+    ExtendFarm(ExtendFarmEvent),
+    RefundFarm(RefundFarmEvent),
+    ClSwap(ClSwapEvent),
+    ClLiquidity(ClLiquidityEvent),
+    ClCollectFees(ClCollectFeesEvent),
+}
+
+/// Decodes every AMM event in a transaction's log messages, in emission order
+///
+/// # Arguments
+/// * `logs` - Log messages of a transaction, as returned by the RPC
+///
+/// # Returns
+/// * `Vec<AmmEvent>` - Events emitted by the program with ID `amm::ID`
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<AmmEvent> {
+    parse_logs_for(&amm::ID, logs)
+}
+
+/// Decodes every AMM event emitted by `program_id` in a transaction's log messages
+/// Useful when the AMM is deployed under a different address than `amm::ID`
+///
+/// # Arguments
+/// * `program_id` - Address the AMM program is deployed at
+/// * `logs` - Log messages of a transaction, as returned by the RPC
+///
+/// # Returns
+/// * `Vec<AmmEvent>` - Events emitted by `program_id`, in emission order
+pub fn parse_logs_for<S: AsRef<str>>(program_id: &Pubkey, logs: &[S]) -> Vec<AmmEvent> {
+    let program_id = program_id.to_string();
+    // Programs currently executing, innermost last
+    let mut call_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        let log = log.as_ref();
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if call_stack.last() == Some(&program_id.as_str()) {
+                events.extend(decode_base64(data));
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(program), Some("invoke")) => call_stack.push(program),
+                (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                    call_stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    events
+}
+
+/// Decodes the base64 payload of a `Program data:` log line
+///
+/// # Arguments
+/// * `data` - Base64 text following `Program data: `
+///
+/// # Returns
+/// * `Option<AmmEvent>` - The event, or `None` if the payload is not an AMM event
+pub fn decode_base64(data: &str) -> Option<AmmEvent> {
+    let bytes = STANDARD.decode(data.trim()).ok()?;
+    decode_event(&bytes)
+}
+
+/// Decodes a raw event payload: an 8-byte discriminator followed by the Borsh-encoded event
+///
+/// # Arguments
+/// * `bytes` - Event payload
+///
+/// # Returns
+/// * `Option<AmmEvent>` - The event, or `None` if the payload is not an AMM event
+pub fn decode_event(bytes: &[u8]) -> Option<AmmEvent> {
+    if bytes.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut data) = bytes.split_at(8);
+
+    let event = if discriminator == InitializeEvent::DISCRIMINATOR {
+        AmmEvent::Initialize(InitializeEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == SwapEvent::DISCRIMINATOR {
+        AmmEvent::Swap(SwapEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == DepositEvent::DISCRIMINATOR {
+        AmmEvent::Deposit(DepositEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == WithdrawEvent::DISCRIMINATOR {
+        AmmEvent::Withdraw(WithdrawEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == FlashLoanEvent::DISCRIMINATOR {
+        AmmEvent::FlashLoan(FlashLoanEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == CollectProtocolFeesEvent::DISCRIMINATOR {
+        AmmEvent::CollectProtocolFees(CollectProtocolFeesEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == UpdatePoolEvent::DISCRIMINATOR {
+        AmmEvent::UpdatePool(UpdatePoolEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ClosePoolEvent::DISCRIMINATOR {
+        AmmEvent::ClosePool(ClosePoolEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == SetPermissionEvent::DISCRIMINATOR {
+        AmmEvent::SetPermission(SetPermissionEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == RevokePermissionEvent::DISCRIMINATOR {
+        AmmEvent::RevokePermission(RevokePermissionEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == PlaceOrderEvent::DISCRIMINATOR {
+        AmmEvent::PlaceOrder(PlaceOrderEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ExecuteOrdersEvent::DISCRIMINATOR {
+        AmmEvent::ExecuteOrders(ExecuteOrdersEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == WithdrawOrderProceedsEvent::DISCRIMINATOR {
+        AmmEvent::WithdrawOrderProceeds(WithdrawOrderProceedsEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == CloseOrderEvent::DISCRIMINATOR {
+        AmmEvent::CloseOrder(CloseOrderEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == StakeEvent::DISCRIMINATOR {
+        AmmEvent::Stake(StakeEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == UnstakeEvent::DISCRIMINATOR {
+        AmmEvent::Unstake(UnstakeEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ClaimEvent::DISCRIMINATOR {
+        AmmEvent::Claim(ClaimEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ExtendFarmEvent::DISCRIMINATOR {
+        AmmEvent::ExtendFarm(ExtendFarmEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == RefundFarmEvent::DISCRIMINATOR {
+        AmmEvent::RefundFarm(RefundFarmEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ClSwapEvent::DISCRIMINATOR {
+        AmmEvent::ClSwap(ClSwapEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ClLiquidityEvent::DISCRIMINATOR {
+        AmmEvent::ClLiquidity(ClLiquidityEvent::deserialize(&mut data).ok()?)
+    } else if discriminator == ClCollectFeesEvent::DISCRIMINATOR {
+        AmmEvent::ClCollectFees(ClCollectFeesEvent::deserialize(&mut data).ok()?)
+    } else {
+        return None;
+    };
+
+    Some(event)
+}
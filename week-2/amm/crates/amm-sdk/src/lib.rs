@@ -0,0 +1,355 @@
+//! Off-chain quoting for the AMM program
+//!
+//! [`Pool`] is built from the raw data of a pool's accounts and reproduces the
+//! amounts `swap`, `swap_exact_out`, `deposit` and `withdraw` would produce if
+//! executed at a given [`Clock`]. Every quote runs the same curve, fee and
+//! transfer-fee code as the program, in the same order, so results match the
+//! on-chain execution exactly. [`Pool::position_value`] likewise matches the
+//! program's `lp_position_value` view.
+
+use amm::constants::MINIMUM_LIQUIDITY;
+use amm::curves::{initial_liquidity, swap_exact_in, swap_exact_out};
+use amm::errors::AmmError;
+use amm::oracle::marginal_prices;
+use amm::states::{Config, LpPosition, PositionValue};
+use amm::utils::{mint_amount_with_transfer_fee, mint_transfer_fee};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use constant_product_curve::ConstantProduct;
+
+/// Result of a swap quote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Amount the user sends, transfer fee included
+    pub amount_in: u64,
+    /// Amount the user receives, after any transfer fee
+    pub amount_out: u64,
+    /// Swap fee charged in the input token (protocol share included)
+    pub fee: u64,
+    /// How far the trade moves the pool's marginal price, in basis points
+    pub price_impact_bps: u64,
+}
+
+/// Result of a deposit quote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositQuote {
+    /// Amount of token X the user sends, transfer fee included
+    pub amount_x: u64,
+    /// Amount of token Y the user sends, transfer fee included
+    pub amount_y: u64,
+    /// LP tokens minted to the user
+    pub lp_amount: u64,
+}
+
+/// Result of a withdrawal quote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawQuote {
+    /// Amount of token X the user receives, after any transfer fee
+    pub amount_x: u64,
+    /// Amount of token Y the user receives, after any transfer fee
+    pub amount_y: u64,
+    /// LP tokens burned by the user
+    pub lp_amount: u64,
+}
+
+/// Snapshot of a constant product, StableSwap or weighted pool
+#[derive(Clone)]
+pub struct Pool {
+    /// The pool's config account
+    pub config: Config,
+    /// Balance of the token X vault, protocol fees included
+    pub vault_x: u64,
+    /// Balance of the token Y vault, protocol fees included
+    pub vault_y: u64,
+    /// LP token supply
+    pub lp_supply: u64,
+    /// Raw data of the token X mint, for its transfer fee
+    pub mint_x: Vec<u8>,
+    /// Raw data of the token Y mint, for its transfer fee
+    pub mint_y: Vec<u8>,
+}
+
+impl Pool {
+    /// Builds a pool snapshot from the raw data of its accounts
+    ///
+    /// # Arguments
+    /// * `config` - Data of the config account
+    /// * `vault_x` - Data of the token X vault
+    /// * `vault_y` - Data of the token Y vault
+    /// * `mint_x` - Data of the token X mint
+    /// * `mint_y` - Data of the token Y mint
+    /// * `mint_lp` - Data of the LP mint
+    ///
+    /// # Returns
+    /// * `Result<Pool>` - The snapshot, or an error if an account does not deserialize
+    pub fn from_account_data(
+        config: &[u8],
+        vault_x: &[u8],
+        vault_y: &[u8],
+        mint_x: &[u8],
+        mint_y: &[u8],
+        mint_lp: &[u8],
+    ) -> Result<Self> {
+        let config = Config::try_deserialize(&mut &config[..])?;
+        let vault_x = TokenAccount::try_deserialize(&mut &vault_x[..])?;
+        let vault_y = TokenAccount::try_deserialize(&mut &vault_y[..])?;
+        let mint_lp = Mint::try_deserialize(&mut &mint_lp[..])?;
+
+        // Reject mints that are not the pool's pair
+        require_keys_eq!(vault_x.mint, config.mint_x, AmmError::InvalidToken);
+        require_keys_eq!(vault_y.mint, config.mint_y, AmmError::InvalidToken);
+        Mint::try_deserialize(&mut &mint_x[..])?;
+        Mint::try_deserialize(&mut &mint_y[..])?;
+
+        Ok(Self {
+            config,
+            vault_x: vault_x.amount,
+            vault_y: vault_y.amount,
+            lp_supply: mint_lp.supply,
+            mint_x: mint_x.to_vec(),
+            mint_y: mint_y.to_vec(),
+        })
+    }
+
+    /// Returns the LP-owned reserves, excluding accrued protocol fees and the tokens
+    /// of long-term orders, as of the last time the orders were executed
+    pub fn reserves(&self) -> Result<(u64, u64)> {
+        self.config.lp_reserves(self.vault_x, self.vault_y)
+    }
+
+    /// Returns the spot price of token X in units of token Y at `clock` (Q64.64 fixed point)
+    ///
+    /// This is the pool's marginal price, the price its curve trades the next unit at:
+    /// `y / x` for constant product pools, the slope of the invariant for StableSwap
+    /// pools and `(y / w_y) / (x / w_x)` for weighted pools, with long-term orders
+    /// executed and weights moved up to `clock`.
+    pub fn spot_price(&self, clock: &Clock) -> Result<u128> {
+        let (config, reserve_x, reserve_y) = self.executed_at(clock)?;
+        require!(reserve_x != 0 && reserve_y != 0, AmmError::NoLiquidityInPool);
+        let (price_x, _) = marginal_prices(&config, reserve_x, reserve_y)?;
+        Ok(price_x)
+    }
+
+    /// Returns the swap fee in basis points a trade would be charged at `clock`
+    pub fn swap_fee(&self, clock: &Clock) -> u16 {
+        self.config_at(clock).swap_fee()
+    }
+
+    /// Returns how far a trade at `clock` moves the pool price, in basis points
+    ///
+    /// The price is the curve's marginal price before and after the trade, the one
+    /// [`Pool::spot_price`] reports and the dynamic fee measures.
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the trade would execute at
+    /// * `is_x` - true if token X is sold for Y, false if Y is sold for X
+    /// * `amount_in` - Amount the vault receives, after any transfer fee
+    /// * `amount_out` - Amount the vault pays out, before any transfer fee
+    pub fn price_impact_bps(&self, clock: &Clock, is_x: bool, amount_in: u64, amount_out: u64) -> Result<u64> {
+        let (config, reserve_x, reserve_y) = self.executed_at(clock)?;
+        let reserves_after = match is_x {
+            true => (reserve_x.saturating_add(amount_in), reserve_y.saturating_sub(amount_out)),
+            false => (reserve_x.saturating_sub(amount_out), reserve_y.saturating_add(amount_in)),
+        };
+        config.price_move_bps((reserve_x, reserve_y), reserves_after)
+    }
+
+    /// Quotes `swap`: sells exactly `amount_in`
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the swap would execute at
+    /// * `is_x` - true if selling token X for Y, false if selling Y for X
+    /// * `amount_in` - Amount the user sends, transfer fee included
+    ///
+    /// # Returns
+    /// * `Result<SwapQuote>` - What the user pays and receives
+    pub fn quote_swap(&self, clock: &Clock, is_x: bool, amount_in: u64) -> Result<SwapQuote> {
+        self.check_tradeable()?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        let (config, reserve_x, reserve_y) = self.executed_at(clock)?;
+        let (mint_in, mint_out) = self.order_mints(is_x);
+
+        let amount_received = amount_in
+            .checked_sub(mint_transfer_fee(mint_in, clock.epoch, amount_in)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let quote = swap_exact_in(&config, reserve_x, reserve_y, self.lp_supply, is_x, amount_received)?;
+        require!(quote.amount_in != 0, AmmError::InvalidAmount);
+        require!(quote.amount_out != 0, AmmError::InvalidAmount);
+
+        let amount_out = quote
+            .amount_out
+            .checked_sub(mint_transfer_fee(mint_out, clock.epoch, quote.amount_out)?)
+            .ok_or(AmmError::Underflow)?;
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee: quote.fee,
+            price_impact_bps: self.price_impact_bps(clock, is_x, quote.amount_in, quote.amount_out)?,
+        })
+    }
+
+    /// Quotes `swap_exact_out`: buys exactly `amount_out`
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the swap would execute at
+    /// * `is_x` - true if selling token X for Y, false if selling Y for X
+    /// * `amount_out` - Amount the user must receive, after any transfer fee
+    ///
+    /// # Returns
+    /// * `Result<SwapQuote>` - What the user pays and receives
+    pub fn quote_exact_out(&self, clock: &Clock, is_x: bool, amount_out: u64) -> Result<SwapQuote> {
+        self.check_tradeable()?;
+        require!(amount_out > 0, AmmError::InvalidAmount);
+
+        let (config, reserve_x, reserve_y) = self.executed_at(clock)?;
+        let (mint_in, mint_out) = self.order_mints(is_x);
+
+        let amount_withdrawn = mint_amount_with_transfer_fee(mint_out, clock.epoch, amount_out)?;
+        let quote = swap_exact_out(&config, reserve_x, reserve_y, self.lp_supply, is_x, amount_withdrawn)?;
+        let amount_in = mint_amount_with_transfer_fee(mint_in, clock.epoch, quote.amount_in)?;
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee: quote.fee,
+            price_impact_bps: self.price_impact_bps(clock, is_x, quote.amount_in, amount_withdrawn)?,
+        })
+    }
+
+    /// Quotes `deposit` into a pool that already holds liquidity
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the deposit would execute at
+    /// * `lp_amount` - LP tokens to mint
+    ///
+    /// # Returns
+    /// * `Result<DepositQuote>` - Token amounts the user must send
+    pub fn quote_deposit(&self, clock: &Clock, lp_amount: u64) -> Result<DepositQuote> {
+        self.check_tradeable()?;
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+        require!(self.lp_supply != 0, AmmError::NoLiquidityInPool);
+
+        let (_, reserve_x, reserve_y) = self.executed_at(clock)?;
+        let amounts = ConstantProduct::xy_deposit_amounts_from_l(reserve_x, reserve_y, self.lp_supply, lp_amount, 6)
+            .map_err(AmmError::from)?;
+
+        Ok(DepositQuote {
+            amount_x: mint_amount_with_transfer_fee(&self.mint_x, clock.epoch, amounts.x)?,
+            amount_y: mint_amount_with_transfer_fee(&self.mint_y, clock.epoch, amounts.y)?,
+            lp_amount,
+        })
+    }
+
+    /// Quotes the first `deposit` into an empty pool
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the deposit would execute at
+    /// * `amount_x` - Amount of token X the user sends, transfer fee included
+    /// * `amount_y` - Amount of token Y the user sends, transfer fee included
+    ///
+    /// # Returns
+    /// * `Result<DepositQuote>` - LP tokens the user receives, after the locked minimum
+    pub fn quote_initial_deposit(&self, clock: &Clock, amount_x: u64, amount_y: u64) -> Result<DepositQuote> {
+        self.check_tradeable()?;
+
+        let received_x = amount_x
+            .checked_sub(mint_transfer_fee(&self.mint_x, clock.epoch, amount_x)?)
+            .ok_or(AmmError::Underflow)?;
+        let received_y = amount_y
+            .checked_sub(mint_transfer_fee(&self.mint_y, clock.epoch, amount_y)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let liquidity = initial_liquidity(received_x, received_y);
+        require!(liquidity > MINIMUM_LIQUIDITY, AmmError::LiquidityLessThanMinimum);
+
+        Ok(DepositQuote {
+            amount_x,
+            amount_y,
+            lp_amount: liquidity - MINIMUM_LIQUIDITY,
+        })
+    }
+
+    /// Quotes `withdraw`
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the withdrawal would execute at
+    /// * `lp_amount` - LP tokens to burn
+    ///
+    /// # Returns
+    /// * `Result<WithdrawQuote>` - Token amounts the user receives
+    pub fn quote_withdraw(&self, clock: &Clock, lp_amount: u64) -> Result<WithdrawQuote> {
+        self.check_tradeable()?;
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+        require!(self.lp_supply != 0, AmmError::NoLiquidityInPool);
+
+        let (_, reserve_x, reserve_y) = self.executed_at(clock)?;
+        let amounts = ConstantProduct::xy_withdraw_amounts_from_l(reserve_x, reserve_y, self.lp_supply, lp_amount, 6)
+            .map_err(AmmError::from)?;
+
+        let amount_x = amounts
+            .x
+            .checked_sub(mint_transfer_fee(&self.mint_x, clock.epoch, amounts.x)?)
+            .ok_or(AmmError::Underflow)?;
+        let amount_y = amounts
+            .y
+            .checked_sub(mint_transfer_fee(&self.mint_y, clock.epoch, amounts.y)?)
+            .ok_or(AmmError::Underflow)?;
+
+        Ok(WithdrawQuote {
+            amount_x,
+            amount_y,
+            lp_amount,
+        })
+    }
+
+    /// Values an LP position the way `lp_position_value` would
+    ///
+    /// # Arguments
+    /// * `clock` - Clock the view would execute at
+    /// * `position` - The position account, deserialized
+    ///
+    /// # Returns
+    /// * `Result<PositionValue>` - The position's value, fees and impermanent loss
+    pub fn position_value(&self, clock: &Clock, position: &LpPosition) -> Result<PositionValue> {
+        let (config, reserve_x, reserve_y) = self.executed_at(clock)?;
+        position.value(&config, reserve_x, reserve_y, self.lp_supply)
+    }
+
+    /// Fails the same way the program does for pools that cannot be traded right now
+    fn check_tradeable(&self) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        Ok(())
+    }
+
+    /// Returns the config as the program would see it at `clock`, with the
+    /// dynamic fee volatility decayed and the weights moved up to that time
+    fn config_at(&self, clock: &Clock) -> Config {
+        let mut config = self.config.clone();
+        config.decay_volatility_to(clock.unix_timestamp);
+        config.update_weights_to(clock.unix_timestamp);
+        config
+    }
+
+    /// Returns the config and LP reserves as the program would see them at `clock`,
+    /// after executing the pool's long-term orders up to that time
+    fn executed_at(&self, clock: &Clock) -> Result<(Config, u64, u64)> {
+        let mut config = self.config.clone();
+        config.execute_virtual_orders_to(self.vault_x, self.vault_y, self.lp_supply, clock.unix_timestamp)?;
+        config.decay_volatility_to(clock.unix_timestamp);
+        config.update_weights_to(clock.unix_timestamp);
+        let (reserve_x, reserve_y) = config.lp_reserves(self.vault_x, self.vault_y)?;
+        Ok((config, reserve_x, reserve_y))
+    }
+
+    /// Orders the mints as (input, output) for a swap direction
+    fn order_mints(&self, is_x: bool) -> (&[u8], &[u8]) {
+        match is_x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x),
+        }
+    }
+}
@@ -0,0 +1,950 @@
+//! Tests for the SDK's quotes against the program
+//!
+//! Every test deploys the AMM in LiteSVM, creates a pool through the factory and
+//! reads it back into a [`Pool`]. Quotes are then checked against what the real
+//! `swap`, `swap_exact_out`, `deposit` and `withdraw` instructions move between the
+//! user and the vaults, across curves, protocol fees, Token-2022 transfer fees,
+//! dynamic fees, long-term orders and shifting weights. A limit one token tighter
+//! than the quote is refused, so quotes are exact rather than merely safe.
+//!
+//! The tests load `target/deploy/amm.so`, so build the program first:
+//! `anchor build && cargo test -p amm-sdk`
+
+use amm::errors::AmmError;
+use amm::states::{
+    Config, CurveType, Factory, LpPosition, PositionValue, Registry, WeightSchedule, ORDER_INTERVAL,
+};
+use amm_sdk::{Pool, SwapQuote};
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::{instruction::Instruction, program_option::COption, program_pack::Pack};
+use anchor_lang::{
+    system_program, AccountDeserialize, AccountSerialize, AnchorDeserialize, InstructionData, Space, ToAccountMetas,
+};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        immutable_owner::ImmutableOwner,
+        transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions,
+        StateWithExtensionsMut,
+    },
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Lamports every wallet starts with, for fees and account rent
+const LAMPORTS: u64 = 100_000_000_000;
+
+/// Fee tier of every pool, 0.3%
+const FEE: u16 = 30;
+
+/// Tokens of each side the user seeds the pool with
+const LIQUIDITY: u64 = 100_000_000_000;
+
+/// Tokens of each side the user starts with
+const BALANCE: u64 = 1_000_000_000_000;
+
+/// Parameters of the pool a test quotes against
+#[derive(Clone, Copy, Debug)]
+struct Params {
+    curve_type: CurveType,
+    amp: u64,
+    weights: Option<WeightSchedule>,
+    protocol_fee: u16,
+    /// Transfer fee of both mints in basis points, `None` for classic SPL Token mints
+    transfer_fee_bps: Option<u16>,
+}
+
+const CONSTANT_PRODUCT: Params = Params {
+    curve_type: CurveType::ConstantProduct,
+    amp: 0,
+    weights: None,
+    protocol_fee: 0,
+    transfer_fee_bps: None,
+};
+
+const STABLE_SWAP: Params = Params {
+    curve_type: CurveType::StableSwap,
+    amp: 100,
+    ..CONSTANT_PRODUCT
+};
+
+/// A static 80/20 pool
+const WEIGHTED: Params = Params {
+    curve_type: CurveType::Weighted,
+    weights: Some(WeightSchedule {
+        start_weight_x: 8_000,
+        end_weight_x: 8_000,
+        start_ts: 0,
+        end_ts: 0,
+    }),
+    ..CONSTANT_PRODUCT
+};
+
+/// Token balances of the user and the pool's vaults
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Balances {
+    user_x: u64,
+    user_y: u64,
+    user_lp: u64,
+    vault_x: u64,
+    vault_y: u64,
+}
+
+impl Balances {
+    /// Balances of the user and the vault of token X (or Y)
+    fn token(&self, is_x: bool) -> (u64, u64) {
+        match is_x {
+            true => (self.user_x, self.vault_x),
+            false => (self.user_y, self.vault_y),
+        }
+    }
+}
+
+/// The AMM deployed in LiteSVM, with one pool created through the factory
+struct Env {
+    svm: LiteSVM,
+    admin: Keypair,
+    user: Keypair,
+    /// Token program owning both mints
+    token_program: Pubkey,
+    mint_x: Pubkey,
+    mint_y: Pubkey,
+    config: Pubkey,
+    mint_lp: Pubkey,
+    locked_lp: Pubkey,
+    vault_x: Pubkey,
+    vault_y: Pubkey,
+}
+
+impl Env {
+    /// Deploys the program, creates an empty pool described by `params` and funds the user
+    fn new(params: Params) -> Self {
+        let mut svm = LiteSVM::new();
+        let path = format!("{}/../../target/deploy/amm.so", env!("CARGO_MANIFEST_DIR"));
+        svm.add_program_from_file(amm::ID, path)
+            .expect("build the program with `anchor build` before running these tests");
+
+        let admin = Keypair::new();
+        let user = Keypair::new();
+        svm.airdrop(&admin.pubkey(), LAMPORTS).unwrap();
+        svm.airdrop(&user.pubkey(), LAMPORTS).unwrap();
+
+        // `initialize_factory` is restricted to the upgrade authority, which a program
+        // loaded from a file does not have, so the factory is written directly
+        let (factory, factory_bump) = Pubkey::find_program_address(&[b"factory"], &amm::ID);
+        let (registry, registry_bump) = Pubkey::find_program_address(&[b"registry"], &amm::ID);
+        let factory_data = Factory {
+            admin: admin.pubkey(),
+            fee_tiers: vec![FEE],
+            pool_count: 0,
+            bump: factory_bump,
+        };
+        set_program_account(&mut svm, factory, &factory_data, 8 + Factory::INIT_SPACE);
+        let registry_data = Registry {
+            pools: Vec::new(),
+            bump: registry_bump,
+        };
+        set_program_account(&mut svm, registry, &registry_data, Registry::space(0));
+
+        let token_program = match params.transfer_fee_bps {
+            Some(_) => spl_token_2022::ID,
+            None => spl_token::ID,
+        };
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let [mint_x, mint_y] = mints;
+        for mint in mints {
+            set_mint(&mut svm, mint, &admin.pubkey(), BALANCE, params.transfer_fee_bps);
+            set_token_account(&mut svm, mint, &user.pubkey(), BALANCE, token_program);
+        }
+
+        let config = pda(&[b"config", &0u64.to_le_bytes()]);
+        let mut env = Self {
+            svm,
+            admin: admin.insecure_clone(),
+            user,
+            token_program,
+            mint_x,
+            mint_y,
+            config,
+            mint_lp: pda(&[b"lp", config.as_ref()]),
+            locked_lp: pda(&[b"locked_lp", config.as_ref()]),
+            vault_x: ata(&config, &mint_x, &token_program),
+            vault_y: ata(&config, &mint_y, &token_program),
+        };
+
+        let initialize = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Initialize {
+                admin: admin.pubkey(),
+                mint_x,
+                mint_y,
+                factory,
+                registry,
+                pool: pda(&[b"pool", mint_x.as_ref(), mint_y.as_ref(), &FEE.to_le_bytes()]),
+                config,
+                mint_lp: env.mint_lp,
+                locked_lp: env.locked_lp,
+                vault_x: env.vault_x,
+                vault_y: env.vault_y,
+                token_program_x: token_program,
+                token_program_y: token_program,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Initialize {
+                fee: FEE,
+                authority: Some(admin.pubkey()),
+                curve_type: params.curve_type,
+                amp: params.amp,
+                weights: params.weights,
+            }
+            .data(),
+        };
+        assert!(env.send(initialize, &admin), "initialize failed for {params:?}");
+
+        if params.protocol_fee > 0 {
+            let protocol_fee = amm::instruction::UpdateProtocolFee {
+                protocol_fee: params.protocol_fee,
+            };
+            assert!(env.update(protocol_fee.data()), "update_protocol_fee failed for {params:?}");
+        }
+        env
+    }
+
+    /// Creates the pool described by `params` and seeds it with `LIQUIDITY` of each token
+    fn seeded(params: Params) -> Self {
+        let mut env = Self::new(params);
+        assert!(env.deposit(1, LIQUIDITY, LIQUIDITY), "initial deposit failed for {params:?}");
+        env
+    }
+
+    /// Sends `ix` signed and paid for by `signer`
+    ///
+    /// # Returns
+    /// * `bool` - true if the transaction succeeded
+    fn send(&mut self, ix: Instruction, signer: &Keypair) -> bool {
+        // The StableSwap solvers can take more than the default compute budget
+        let budget = ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+        let tx = Transaction::new_signed_with_payer(
+            &[budget, ix],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        let succeeded = self.svm.send_transaction(tx).is_ok();
+        // Identical transactions in a row would otherwise be rejected as duplicates
+        self.svm.expire_blockhash();
+        succeeded
+    }
+
+    /// Sends an authority-gated pool update, signed by the admin
+    fn update(&mut self, data: Vec<u8>) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Update {
+                authority: admin.pubkey(),
+                config: self.config,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Mints `amount` LP tokens to the user, paying at most `max_x` and `max_y`
+    ///
+    /// The user's LP position is tracked, so its value can be compared.
+    fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> bool {
+        let user = self.user.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Deposit {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x, &self.token_program),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y, &self.token_program),
+                user_ata_lp: ata(&user.pubkey(), &self.mint_lp, &spl_token::ID),
+                permission: None,
+                lp_position: Some(self.lp_position()),
+                oracle: None,
+                locked_lp: self.locked_lp,
+                token_program_x: self.token_program,
+                token_program_y: self.token_program,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Deposit {
+                amount,
+                max_x,
+                max_y,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Burns `amount` of the user's LP tokens, expecting at least `min_x` and `min_y`
+    fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> bool {
+        let user = self.user.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Withdraw {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x, &self.token_program),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y, &self.token_program),
+                user_ata_lp: ata(&user.pubkey(), &self.mint_lp, &spl_token::ID),
+                permission: None,
+                lp_position: Some(self.lp_position()),
+                oracle: None,
+                token_program_x: self.token_program,
+                token_program_y: self.token_program,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Withdraw {
+                amount,
+                min_x,
+                min_y,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Sells `amount_in` of the user's token X (or Y), expecting at least `min_amount_out`
+    fn swap(&mut self, is_x: bool, amount_in: u64, min_amount_out: u64) -> bool {
+        let data = amm::instruction::Swap {
+            is_x,
+            amount_in,
+            min_amount_out,
+            expires_at: 0,
+        };
+        self.send_swap(data.data())
+    }
+
+    /// Buys exactly `amount_out` of token Y (or X), spending at most `max_amount_in`
+    fn swap_exact_out(&mut self, is_x: bool, amount_out: u64, max_amount_in: u64) -> bool {
+        let data = amm::instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_amount_in,
+            expires_at: 0,
+        };
+        self.send_swap(data.data())
+    }
+
+    /// Sends the user an instruction taking the `Swap` accounts
+    fn send_swap(&mut self, data: Vec<u8>) -> bool {
+        let user = self.user.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Swap {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x, &self.token_program),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y, &self.token_program),
+                permission: None,
+                oracle: None,
+                token_program_x: self.token_program,
+                token_program_y: self.token_program,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &user)
+    }
+
+    /// Places the user's long-term order `id`, selling `amount_in` over `duration` seconds
+    fn place_order(&mut self, id: u64, is_x: bool, amount_in: u64, duration: u32) -> bool {
+        let user = self.user.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::PlaceOrder {
+                owner: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                owner_ata_x: ata(&user.pubkey(), &self.mint_x, &self.token_program),
+                owner_ata_y: ata(&user.pubkey(), &self.mint_y, &self.token_program),
+                order: pda(&[b"order", self.config.as_ref(), user.pubkey().as_ref(), &id.to_le_bytes()]),
+                permission: None,
+                token_program_x: self.token_program,
+                token_program_y: self.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::PlaceOrder {
+                id,
+                is_x,
+                amount_in,
+                duration,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Values the user's position with `lp_position_value`, decoding its return data
+    fn position_value(&mut self) -> PositionValue {
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::LpPositionValue {
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                lp_position: self.lp_position(),
+                token_program_x: self.token_program,
+                token_program_y: self.token_program,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::LpPositionValue {}.data(),
+        };
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.user.pubkey()),
+            &[&self.user],
+            self.svm.latest_blockhash(),
+        );
+        let meta = self.svm.send_transaction(tx).expect("lp_position_value failed");
+        self.svm.expire_blockhash();
+        PositionValue::deserialize(&mut meta.return_data.data.as_slice()).unwrap()
+    }
+
+    /// Reads the pool back from the SVM, the way a client would from an RPC node
+    fn pool(&self) -> Pool {
+        let data = |address: &Pubkey| self.svm.get_account(address).unwrap().data;
+        Pool::from_account_data(
+            &data(&self.config),
+            &data(&self.vault_x),
+            &data(&self.vault_y),
+            &data(&self.mint_x),
+            &data(&self.mint_y),
+            &data(&self.mint_lp),
+        )
+        .unwrap()
+    }
+
+    /// Reads the pool configuration
+    fn read_config(&self) -> Config {
+        let account = self.svm.get_account(&self.config).unwrap();
+        Config::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Address of the user's LP position
+    fn lp_position(&self) -> Pubkey {
+        pda(&[b"lp_position", self.config.as_ref(), self.user.pubkey().as_ref()])
+    }
+
+    /// Reads the user's LP position
+    fn read_lp_position(&self) -> LpPosition {
+        let account = self.svm.get_account(&self.lp_position()).expect("no LP position");
+        LpPosition::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// The clock the next transaction executes at
+    fn clock(&self) -> Clock {
+        self.svm.get_sysvar::<Clock>()
+    }
+
+    /// Moves the clock to `unix_timestamp`
+    fn warp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    fn balances(&self) -> Balances {
+        let user = self.user.pubkey();
+        Balances {
+            user_x: self.token_balance(&ata(&user, &self.mint_x, &self.token_program)),
+            user_y: self.token_balance(&ata(&user, &self.mint_y, &self.token_program)),
+            user_lp: self.token_balance(&ata(&user, &self.mint_lp, &spl_token::ID)),
+            vault_x: self.token_balance(&self.vault_x),
+            vault_y: self.token_balance(&self.vault_y),
+        }
+    }
+
+    /// Balance of a token account of either token program, 0 if it does not exist yet
+    fn token_balance(&self, address: &Pubkey) -> u64 {
+        self.svm
+            .get_account(address)
+            .map(|account| {
+                StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                    .unwrap()
+                    .base
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Checks that a swap moved exactly the quoted amounts between the user and the vaults
+///
+/// Only holds for mints without a transfer fee, where the vaults receive and pay
+/// what the user sends and receives.
+fn assert_swap_matches(before: &Balances, after: &Balances, is_x: bool, quote: &SwapQuote) {
+    let (user_in, vault_in) = before.token(is_x);
+    let (user_out, vault_out) = before.token(!is_x);
+    let (user_in_after, vault_in_after) = after.token(is_x);
+    let (user_out_after, vault_out_after) = after.token(!is_x);
+    assert_eq!(user_in - user_in_after, quote.amount_in, "{quote:?}");
+    assert_eq!(user_out_after - user_out, quote.amount_out, "{quote:?}");
+    assert_eq!(vault_in_after - vault_in, quote.amount_in, "{quote:?}");
+    assert_eq!(vault_out - vault_out_after, quote.amount_out, "{quote:?}");
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &amm::ID).0
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
+
+/// Writes an Anchor account owned by the AMM, padded to `space` bytes
+fn set_program_account<T: AccountSerialize>(svm: &mut LiteSVM, address: Pubkey, account: &T, space: usize) {
+    let mut data = Vec::with_capacity(space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    set_account(svm, address, amm::ID, data);
+}
+
+/// Creates a mint, a Token-2022 one with a transfer fee of `transfer_fee_bps` if given
+fn set_mint(svm: &mut LiteSVM, address: Pubkey, authority: &Pubkey, supply: u64, transfer_fee_bps: Option<u16>) {
+    let mint = Mint {
+        mint_authority: COption::Some(*authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let Some(basis_points) = transfer_fee_bps else {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+        return set_account(svm, address, spl_token::ID, data);
+    };
+
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+    let mut data = vec![0; space];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    let fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: basis_points.into(),
+    };
+    let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
+    extension.older_transfer_fee = fee;
+    extension.newer_transfer_fee = fee;
+    state.base = mint;
+    state.pack_base();
+    state.init_account_type().unwrap();
+    set_account(svm, address, spl_token_2022::ID, data);
+}
+
+/// Creates `owner`'s associated token account for `mint` holding `amount`
+///
+/// Token-2022 accounts carry the extensions a transfer-fee mint requires.
+fn set_token_account(svm: &mut LiteSVM, mint: Pubkey, owner: &Pubkey, amount: u64, token_program: Pubkey) {
+    let account = TokenAccount {
+        mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    let data = match token_program == spl_token_2022::ID {
+        true => {
+            let extensions = [ExtensionType::TransferFeeAmount, ExtensionType::ImmutableOwner];
+            let space = ExtensionType::try_calculate_account_len::<TokenAccount>(&extensions).unwrap();
+            let mut data = vec![0; space];
+            let mut state = StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+            state.init_extension::<TransferFeeAmount>(true).unwrap();
+            state.init_extension::<ImmutableOwner>(true).unwrap();
+            state.base = account;
+            state.pack_base();
+            state.init_account_type().unwrap();
+            data
+        }
+        false => {
+            let mut data = vec![0; TokenAccount::LEN];
+            TokenAccount::pack(account, &mut data).unwrap();
+            data
+        }
+    };
+    set_account(svm, ata(owner, &mint, &token_program), token_program, data);
+}
+
+fn set_account(svm: &mut LiteSVM, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(address, account).unwrap();
+}
+
+#[test]
+fn swap_quotes_match_the_program() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        // The protocol's share of the fee is not part of the reserves trades are priced on
+        let mut env = Env::seeded(Params {
+            protocol_fee: 1_667,
+            ..params
+        });
+        for amount_in in [1_000, 123_456_789, 10_000_000_000] {
+            for is_x in [true, false] {
+                let quote = env.pool().quote_swap(&env.clock(), is_x, amount_in).unwrap();
+                assert_eq!(quote.amount_in, amount_in);
+
+                let before = env.balances();
+                assert!(!env.swap(is_x, amount_in, quote.amount_out + 1), "{params:?} {quote:?}");
+                assert!(env.swap(is_x, amount_in, quote.amount_out), "{params:?} {quote:?}");
+                assert_swap_matches(&before, &env.balances(), is_x, &quote);
+            }
+        }
+        assert!(env.read_config().protocol_fees_x > 0);
+    }
+}
+
+#[test]
+fn exact_out_quotes_match_the_program() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        let mut env = Env::seeded(Params {
+            protocol_fee: 1_667,
+            ..params
+        });
+        for amount_out in [1_000, 123_456_789, 5_000_000_000] {
+            for is_x in [true, false] {
+                let quote = env.pool().quote_exact_out(&env.clock(), is_x, amount_out).unwrap();
+                assert_eq!(quote.amount_out, amount_out);
+
+                let before = env.balances();
+                assert!(!env.swap_exact_out(is_x, amount_out, quote.amount_in - 1), "{params:?} {quote:?}");
+                assert!(env.swap_exact_out(is_x, amount_out, quote.amount_in), "{params:?} {quote:?}");
+                assert_swap_matches(&before, &env.balances(), is_x, &quote);
+            }
+        }
+    }
+}
+
+#[test]
+fn deposit_and_withdraw_quotes_match_the_program() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        let mut env = Env::new(params);
+
+        // The first deposit sets the price and locks the minimum liquidity
+        let quote = env.pool().quote_initial_deposit(&env.clock(), LIQUIDITY, 3 * LIQUIDITY).unwrap();
+        let before = env.balances();
+        assert!(!env.deposit(quote.lp_amount + 1, LIQUIDITY, 3 * LIQUIDITY));
+        assert!(env.deposit(quote.lp_amount, LIQUIDITY, 3 * LIQUIDITY));
+        let after = env.balances();
+        assert_eq!(after.user_lp - before.user_lp, quote.lp_amount);
+        assert_eq!((after.vault_x, after.vault_y), (LIQUIDITY, 3 * LIQUIDITY));
+
+        // Trading leaves fees in the pool, so later LP tokens are worth more than the first
+        assert!(env.swap(true, 1_000_000_000, 0));
+        assert!(env.swap(false, 3_000_000_000, 0));
+
+        for lp_amount in [7, 333, 123_456, 1_000_000_000] {
+            let quote = env.pool().quote_deposit(&env.clock(), lp_amount).unwrap();
+            let before = env.balances();
+            assert!(!env.deposit(lp_amount, quote.amount_x - 1, quote.amount_y));
+            assert!(!env.deposit(lp_amount, quote.amount_x, quote.amount_y - 1));
+            assert!(env.deposit(lp_amount, quote.amount_x, quote.amount_y), "{params:?} {quote:?}");
+            let after = env.balances();
+            assert_eq!(before.user_x - after.user_x, quote.amount_x);
+            assert_eq!(before.user_y - after.user_y, quote.amount_y);
+            assert_eq!(after.vault_x - before.vault_x, quote.amount_x);
+            assert_eq!(after.vault_y - before.vault_y, quote.amount_y);
+            assert_eq!(after.user_lp - before.user_lp, lp_amount);
+
+            let quote = env.pool().quote_withdraw(&env.clock(), lp_amount).unwrap();
+            let before = env.balances();
+            assert!(!env.withdraw(lp_amount, quote.amount_x + 1, quote.amount_y));
+            assert!(!env.withdraw(lp_amount, quote.amount_x, quote.amount_y + 1));
+            assert!(env.withdraw(lp_amount, quote.amount_x, quote.amount_y), "{params:?} {quote:?}");
+            let after = env.balances();
+            assert_eq!(after.user_x - before.user_x, quote.amount_x);
+            assert_eq!(after.user_y - before.user_y, quote.amount_y);
+            assert_eq!(before.vault_x - after.vault_x, quote.amount_x);
+            assert_eq!(before.vault_y - after.vault_y, quote.amount_y);
+            assert_eq!(before.user_lp - after.user_lp, lp_amount);
+        }
+    }
+}
+
+#[test]
+fn transfer_fees_are_quoted_on_both_legs() {
+    // 1% is withheld on every transfer, into and out of the vaults
+    let mut env = Env::new(Params {
+        transfer_fee_bps: Some(100),
+        ..CONSTANT_PRODUCT
+    });
+    let quote = env.pool().quote_initial_deposit(&env.clock(), LIQUIDITY, LIQUIDITY).unwrap();
+    assert!(!env.deposit(quote.lp_amount + 1, LIQUIDITY, LIQUIDITY));
+    assert!(env.deposit(quote.lp_amount, LIQUIDITY, LIQUIDITY));
+    assert_eq!(env.balances().vault_x, LIQUIDITY - LIQUIDITY / 100);
+
+    for is_x in [true, false] {
+        let quote = env.pool().quote_swap(&env.clock(), is_x, 1_000_000_000).unwrap();
+        let before = env.balances();
+        assert!(!env.swap(is_x, 1_000_000_000, quote.amount_out + 1));
+        assert!(env.swap(is_x, 1_000_000_000, quote.amount_out));
+        let after = env.balances();
+        let ((user_in, vault_in), (user_out, _)) = (before.token(is_x), before.token(!is_x));
+        let ((user_in_after, vault_in_after), (user_out_after, _)) = (after.token(is_x), after.token(!is_x));
+        assert_eq!(user_in - user_in_after, quote.amount_in);
+        assert_eq!(user_out_after - user_out, quote.amount_out);
+        assert_eq!(vault_in_after - vault_in, quote.amount_in - quote.amount_in / 100);
+
+        let quote = env.pool().quote_exact_out(&env.clock(), is_x, 500_000_000).unwrap();
+        let before = env.balances();
+        assert!(!env.swap_exact_out(is_x, 500_000_000, quote.amount_in - 1));
+        assert!(env.swap_exact_out(is_x, 500_000_000, quote.amount_in));
+        let after = env.balances();
+        assert_eq!(before.token(is_x).0 - after.token(is_x).0, quote.amount_in);
+        assert_eq!(after.token(!is_x).0 - before.token(!is_x).0, 500_000_000);
+    }
+
+    // Deposits are grossed up so the vaults receive the proportional amounts
+    let quote = env.pool().quote_deposit(&env.clock(), 1_000_000_000).unwrap();
+    let before = env.balances();
+    assert!(!env.deposit(1_000_000_000, quote.amount_x - 1, quote.amount_y));
+    assert!(env.deposit(1_000_000_000, quote.amount_x, quote.amount_y));
+    let after = env.balances();
+    assert_eq!(before.user_x - after.user_x, quote.amount_x);
+    assert_eq!(before.user_y - after.user_y, quote.amount_y);
+
+    // Withdrawals are quoted after the fee withheld on the way out
+    let quote = env.pool().quote_withdraw(&env.clock(), 1_000_000_000).unwrap();
+    let before = env.balances();
+    assert!(!env.withdraw(1_000_000_000, quote.amount_x + 1, quote.amount_y));
+    assert!(env.withdraw(1_000_000_000, quote.amount_x, quote.amount_y));
+    let after = env.balances();
+    assert_eq!(after.user_x - before.user_x, quote.amount_x);
+    assert_eq!(after.user_y - before.user_y, quote.amount_y);
+    assert!(before.vault_x - after.vault_x > quote.amount_x);
+}
+
+#[test]
+fn dynamic_fees_are_quoted_at_the_execution_clock() {
+    let mut env = Env::seeded(CONSTANT_PRODUCT);
+    let dynamic_fee = amm::instruction::SetDynamicFee {
+        min_fee: 10,
+        max_fee: 300,
+        volatility_factor: 1_000,
+        decay_period: 600,
+    };
+    assert!(env.update(dynamic_fee.data()));
+
+    // Selling 10% of the reserves moves the price by about 17%, raising the fee
+    assert!(env.swap(true, 10_000_000_000, 0));
+    let start = env.clock().unix_timestamp;
+    env.warp(start + 300);
+
+    // Halfway through the decay, the fee is above the base fee but below the cap
+    let pool = env.pool();
+    let clock = env.clock();
+    let fee = pool.swap_fee(&clock);
+    assert!(FEE < fee && fee < 300, "{fee}");
+    let decayed = Clock {
+        unix_timestamp: start + 600,
+        ..clock.clone()
+    };
+    assert_eq!(pool.swap_fee(&decayed), FEE);
+
+    for is_x in [false, true] {
+        let quote = env.pool().quote_swap(&env.clock(), is_x, 1_000_000_000).unwrap();
+        let before = env.balances();
+        assert!(!env.swap(is_x, 1_000_000_000, quote.amount_out + 1));
+        assert!(env.swap(is_x, 1_000_000_000, quote.amount_out));
+        assert_swap_matches(&before, &env.balances(), is_x, &quote);
+    }
+}
+
+#[test]
+fn long_term_orders_execute_before_quotes() {
+    let mut env = Env::seeded(CONSTANT_PRODUCT);
+    // On an interval boundary, so a one-interval order sells for exactly an interval
+    let start = 1_700_000_000 / ORDER_INTERVAL * ORDER_INTERVAL;
+    env.warp(start);
+    assert!(env.place_order(0, true, 100_000 * ORDER_INTERVAL as u64, ORDER_INTERVAL as u32));
+    let reserves = env.pool().reserves().unwrap();
+
+    // Halfway through, half the order has been sold into the pool, which the stored
+    // reserves do not show until the pool is touched
+    env.warp(start + ORDER_INTERVAL / 2);
+    let pool = env.pool();
+    assert_eq!(pool.reserves().unwrap(), reserves);
+    let placed = Clock {
+        unix_timestamp: start,
+        ..env.clock()
+    };
+    let without_order = pool.quote_swap(&placed, true, 1_000_000_000).unwrap();
+    let with_order = pool.quote_swap(&env.clock(), true, 1_000_000_000).unwrap();
+    assert!(with_order.amount_out < without_order.amount_out);
+
+    for is_x in [true, false] {
+        let quote = env.pool().quote_swap(&env.clock(), is_x, 1_000_000_000).unwrap();
+        let before = env.balances();
+        assert!(!env.swap(is_x, 1_000_000_000, quote.amount_out + 1));
+        assert!(env.swap(is_x, 1_000_000_000, quote.amount_out));
+        assert_swap_matches(&before, &env.balances(), is_x, &quote);
+    }
+}
+
+#[test]
+fn bootstrapping_pool_quotes_follow_the_weights() {
+    // Weight of X shifts from 90% down to 10% over a day
+    let mut env = Env::seeded(Params {
+        curve_type: CurveType::Weighted,
+        weights: Some(WeightSchedule {
+            start_weight_x: 9_000,
+            end_weight_x: 1_000,
+            start_ts: 1_000,
+            end_ts: 87_400,
+        }),
+        ..CONSTANT_PRODUCT
+    });
+
+    let mut last = 0;
+    for now in [1_000, 20_000, 44_200, 70_000, 87_400, 100_000] {
+        env.warp(now);
+        let quote = env.pool().quote_swap(&env.clock(), false, 1_000_000).unwrap();
+        let before = env.balances();
+        assert!(!env.swap(false, 1_000_000, quote.amount_out + 1));
+        assert!(env.swap(false, 1_000_000, quote.amount_out));
+        assert_swap_matches(&before, &env.balances(), false, &quote);
+
+        // Y buys more X as the weight of X falls
+        assert!(quote.amount_out >= last, "{now}: {quote:?}");
+        last = quote.amount_out;
+    }
+}
+
+#[test]
+fn price_impact_is_the_move_of_the_spot_price() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        let mut env = Env::seeded(params);
+        let pool = env.pool();
+        let clock = env.clock();
+        let small = pool.quote_swap(&clock, true, 1_000_000).unwrap();
+        let large = pool.quote_swap(&clock, true, 10_000_000_000).unwrap();
+        assert!(small.price_impact_bps < large.price_impact_bps, "{params:?}");
+
+        let price = pool.spot_price(&clock).unwrap();
+        assert!(env.swap(true, 10_000_000_000, large.amount_out));
+        let moved = env.pool().spot_price(&env.clock()).unwrap();
+        let price_move = (price - moved) * 10_000 / price;
+        assert!(price_move.abs_diff(large.price_impact_bps as u128) <= 1, "{params:?}: {price_move} {large:?}");
+    }
+}
+
+#[test]
+fn spot_price_is_what_the_twap_accumulates() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        let mut env = Env::seeded(params);
+        let config = env.read_config();
+        let price = env.pool().spot_price(&env.clock()).unwrap();
+
+        // The next touch accumulates the price the pool held since the last one
+        env.warp(config.last_update_ts + 1_000);
+        assert!(env.swap(true, 1_000_000, 0));
+        let accumulated = env
+            .read_config()
+            .price_x_cumulative
+            .wrapping_sub(config.price_x_cumulative);
+        assert_eq!(accumulated, price.wrapping_mul(1_000), "{params:?}");
+    }
+}
+
+#[test]
+fn position_values_match_the_program() {
+    for params in [CONSTANT_PRODUCT, STABLE_SWAP, WEIGHTED] {
+        let mut env = Env::seeded(params);
+        // Fees from trading back and forth, then a large trade that moves the price
+        for _ in 0..5 {
+            assert!(env.swap(true, 1_000_000_000, 0));
+            assert!(env.swap(false, 1_000_000_000, 0));
+        }
+        assert!(env.swap(true, 20_000_000_000, 0));
+
+        let position = env.read_lp_position();
+        let value = env.pool().position_value(&env.clock(), &position).unwrap();
+        assert_eq!(value, env.position_value(), "{params:?}");
+        assert!(value.fees_x > 0 && value.impermanent_loss > 0, "{params:?}: {value:?}");
+    }
+}
+
+#[test]
+fn refuses_what_the_program_refuses() {
+    let mut env = Env::seeded(CONSTANT_PRODUCT);
+    assert!(env.update(amm::instruction::Lock {}.data()));
+
+    let quote = env.pool().quote_swap(&env.clock(), true, 1_000_000);
+    assert_eq!(quote.unwrap_err(), AmmError::PoolLocked.into());
+    assert!(!env.swap(true, 1_000_000, 0));
+
+    // Vaults must hold the pool's mints
+    let data = |address: &Pubkey| env.svm.get_account(address).unwrap().data;
+    let swapped_vaults = Pool::from_account_data(
+        &data(&env.config),
+        &data(&env.vault_y),
+        &data(&env.vault_x),
+        &data(&env.mint_x),
+        &data(&env.mint_y),
+        &data(&env.mint_lp),
+    );
+    assert!(swapped_vaults.is_err());
+}
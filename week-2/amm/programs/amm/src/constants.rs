@@ -1,4 +1,11 @@
 use anchor_lang::prelude::*;
 
 #[constant]
-pub const SEED: &str = "anchor";
\ No newline at end of file
+pub const SEED: &str = "anchor";
+
+/// LP tokens minted to the locked LP account on the first deposit of every pool
+/// They are only burned when the pool is closed, so the LP supply of a live pool can
+/// never return to zero and the first depositor cannot inflate the value of a single
+/// LP token to grief later ones
+#[constant]
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
\ No newline at end of file
@@ -0,0 +1,404 @@
+use super::U256;
+
+/// Lowest tick index, at which the sqrt price is `MIN_SQRT_PRICE`
+pub const MIN_TICK: i32 = -443_636;
+
+/// Highest tick index, at which the sqrt price is `MAX_SQRT_PRICE`
+pub const MAX_TICK: i32 = 443_636;
+
+/// Sqrt price at `MIN_TICK` (Q64.64)
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_017;
+
+/// Sqrt price at `MAX_TICK` (Q64.64)
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_062;
+
+/// `2^128 / sqrt(1.0001)^(2^i)` for each bit `i` of a tick index, used to build
+/// `sqrt(1.0001)^-|tick|` in Q128 fixed point by multiplying one factor per set bit
+const TICK_RATIOS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x09aa508b5b7a84e1c677de54f3e99bc8,
+    0x005d6af8dedb81196699c329225ee604,
+    0x00002216e584f5fa1ea926041bedfe97,
+];
+
+/// `2^32 / log2(sqrt(1.0001))`, converts a log2 into a tick index
+const LOG_B_2_X32: i128 = 59_543_866_431_248;
+
+/// Fractional bits of log2 computed when converting a sqrt price to a tick
+const LOG2_PRECISION_BITS: u32 = 14;
+
+/// Error margins (Q64.64 ticks) bracketing the tick estimate from the truncated log2
+const LOG_B_P_ERR_MARGIN_LOWER_X64: i128 = 184_467_440_737_095_516;
+const LOG_B_P_ERR_MARGIN_UPPER_X64: i128 = 15_793_534_762_490_258_745;
+
+/// Errors returned by the concentrated liquidity math
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcentratedLiquidityError {
+    /// Tick index outside `MIN_TICK..=MAX_TICK`
+    InvalidTick,
+    /// Sqrt price outside `MIN_SQRT_PRICE..=MAX_SQRT_PRICE`
+    InvalidSqrtPrice,
+    /// Fee of 100% or more
+    InvalidFeeAmount,
+    /// Arithmetic overflow
+    Overflow,
+    /// Arithmetic underflow
+    Underflow,
+}
+
+/// Result of a swap within a single price range of constant liquidity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    /// Sqrt price after the step (Q64.64)
+    pub sqrt_price_next: u128,
+    /// Amount paid in, fee excluded
+    pub amount_in: u64,
+    /// Amount paid out
+    pub amount_out: u64,
+    /// Fee charged on the input amount
+    pub fee: u64,
+}
+
+/// Returns `sqrt(1.0001^tick)` as a Q64.64 fixed-point number, rounded up
+///
+/// # Arguments
+/// * `tick` - Tick index in `MIN_TICK..=MAX_TICK`
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128, ConcentratedLiquidityError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(ConcentratedLiquidityError::InvalidTick);
+    }
+
+    let abs_tick = tick.unsigned_abs();
+    // sqrt(1.0001)^-|tick| in Q128, one factor per set bit of |tick|
+    let mut ratio = match abs_tick & 1 {
+        0 => U256::one() << 128,
+        _ => U256::from(TICK_RATIOS[0]),
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+
+    // Positive ticks use the reciprocal
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128 -> Q64.64, rounded up
+    let rounding = match ratio.low_u64() {
+        0 => U256::zero(),
+        _ => U256::one(),
+    };
+    Ok(((ratio >> 64) + rounding).as_u128())
+}
+
+/// Returns the greatest tick whose sqrt price is at most `sqrt_price`
+///
+/// Estimates `log_sqrt(1.0001)(sqrt_price)` from a truncated binary logarithm, then
+/// settles the one-tick ambiguity with `sqrt_price_at_tick`.
+///
+/// # Arguments
+/// * `sqrt_price` - Q64.64 sqrt price in `MIN_SQRT_PRICE..=MAX_SQRT_PRICE`
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32, ConcentratedLiquidityError> {
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price) {
+        return Err(ConcentratedLiquidityError::InvalidSqrtPrice);
+    }
+
+    // Integer part of log2, relative to the Q64.64 scaling
+    let msb = 127 - sqrt_price.leading_zeros();
+    let log2_integer_x32 = (msb as i128 - 64) << 32;
+
+    // Normalize to Q1.63 in [1, 2) and extract fractional bits by repeated squaring
+    let mut r = match msb >= 63 {
+        true => sqrt_price >> (msb - 63),
+        false => sqrt_price << (63 - msb),
+    };
+    let mut bit: i128 = 1 << 63;
+    let mut log2_fraction_x64: i128 = 0;
+    for _ in 0..LOG2_PRECISION_BITS {
+        r *= r;
+        let is_r_more_than_two = (r >> 127) as u32;
+        r >>= 63 + is_r_more_than_two;
+        log2_fraction_x64 += bit * is_r_more_than_two as i128;
+        bit >>= 1;
+    }
+
+    let log2_x32 = log2_integer_x32 + (log2_fraction_x64 >> 32);
+    let log_b_x64 = log2_x32 * LOG_B_2_X32;
+
+    let tick_low = ((log_b_x64 - LOG_B_P_ERR_MARGIN_LOWER_X64) >> 64) as i32;
+    let tick_high = ((log_b_x64 + LOG_B_P_ERR_MARGIN_UPPER_X64) >> 64) as i32;
+
+    if tick_low == tick_high {
+        return Ok(tick_low);
+    }
+    match sqrt_price_at_tick(tick_high)? <= sqrt_price {
+        true => Ok(tick_high),
+        false => Ok(tick_low),
+    }
+}
+
+/// Amount of token X between two sqrt prices for the given liquidity
+///
+/// `L / sqrt(P_a) - L / sqrt(P_b)`
+///
+/// # Arguments
+/// * `sqrt_price_a` - Lower sqrt price (Q64.64)
+/// * `sqrt_price_b` - Upper sqrt price (Q64.64)
+/// * `liquidity` - Liquidity active between the two prices
+/// * `round_up` - true when the pool receives the amount, false when it pays it out
+pub fn amount_x_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64, ConcentratedLiquidityError> {
+    to_u64(amount_x_delta_u256(sqrt_price_a, sqrt_price_b, liquidity, round_up)?)
+}
+
+/// Amount of token Y between two sqrt prices for the given liquidity
+///
+/// `L * (sqrt(P_b) - sqrt(P_a))`
+///
+/// # Arguments
+/// * `sqrt_price_a` - Lower sqrt price (Q64.64)
+/// * `sqrt_price_b` - Upper sqrt price (Q64.64)
+/// * `liquidity` - Liquidity active between the two prices
+/// * `round_up` - true when the pool receives the amount, false when it pays it out
+pub fn amount_y_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64, ConcentratedLiquidityError> {
+    to_u64(amount_y_delta_u256(sqrt_price_a, sqrt_price_b, liquidity, round_up)?)
+}
+
+/// Token amounts backing `liquidity` over `[sqrt_price_lower, sqrt_price_upper)`
+/// at the current price
+///
+/// Below the range a position holds only token X, above it only token Y, and
+/// inside it a mix of both.
+///
+/// # Arguments
+/// * `sqrt_price` - Current sqrt price (Q64.64)
+/// * `sqrt_price_lower` - Sqrt price at the lower tick of the range
+/// * `sqrt_price_upper` - Sqrt price at the upper tick of the range
+/// * `liquidity` - Liquidity of the position
+/// * `round_up` - true when liquidity is added, false when it is removed
+///
+/// # Returns
+/// * `Result<(u64, u64), ConcentratedLiquidityError>` - Amounts of token X and token Y
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64), ConcentratedLiquidityError> {
+    if sqrt_price < sqrt_price_lower {
+        let x = amount_x_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+        Ok((x, 0))
+    } else if sqrt_price < sqrt_price_upper {
+        let x = amount_x_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?;
+        let y = amount_y_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?;
+        Ok((x, y))
+    } else {
+        let y = amount_y_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+        Ok((0, y))
+    }
+}
+
+/// Sqrt price reached after adding `amount` of input to a range of constant liquidity
+///
+/// The result is rounded so the trader never gets a better price than exact math gives.
+///
+/// # Arguments
+/// * `sqrt_price` - Current sqrt price (Q64.64)
+/// * `liquidity` - Active liquidity (must be non-zero)
+/// * `amount` - Input amount, fee excluded
+/// * `x_in` - true if token X is paid in (price falls), false for token Y (price rises)
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    x_in: bool,
+) -> Result<u128, ConcentratedLiquidityError> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let liquidity_x64 = U256::from(liquidity) << 64;
+    let next = match x_in {
+        // sqrt(P') = L / (L / sqrt(P) + dx), rounded up
+        true => {
+            let denominator = liquidity_x64 / U256::from(sqrt_price) + U256::from(amount);
+            div_ceil(liquidity_x64, denominator)
+        }
+        // sqrt(P') = sqrt(P) + dy / L, rounded down
+        false => U256::from(sqrt_price) + (U256::from(amount) << 64) / U256::from(liquidity),
+    };
+
+    if next < U256::from(MIN_SQRT_PRICE) || next > U256::from(MAX_SQRT_PRICE) {
+        return Err(ConcentratedLiquidityError::InvalidSqrtPrice);
+    }
+    Ok(next.as_u128())
+}
+
+/// Swaps an exact input amount within a single range of constant liquidity
+///
+/// The swap stops either at `sqrt_price_target` (the next initialized tick or the
+/// price limit) or where `amount_remaining` runs out, whichever comes first.
+///
+/// # Arguments
+/// * `sqrt_price` - Current sqrt price (Q64.64)
+/// * `sqrt_price_target` - Price the step may not move past (Q64.64)
+/// * `liquidity` - Liquidity active in the range
+/// * `amount_remaining` - Input left to swap, fee included
+/// * `fee` - Trading fee in basis points, charged on the input amount
+/// * `x_in` - true if token X is paid in (price falls), false for token Y (price rises)
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee: u16,
+    x_in: bool,
+) -> Result<SwapStep, ConcentratedLiquidityError> {
+    if fee >= 10_000 {
+        return Err(ConcentratedLiquidityError::InvalidFeeAmount);
+    }
+
+    // Fee is taken from the input, rounded in the pool's favor
+    let amount_less_fee = (amount_remaining as u128 * (10_000 - fee) as u128 / 10_000) as u64;
+
+    // Input needed to move all the way to the target
+    let amount_to_target = match x_in {
+        true => amount_x_delta_u256(sqrt_price_target, sqrt_price, liquidity, true)?,
+        false => amount_y_delta_u256(sqrt_price, sqrt_price_target, liquidity, true)?,
+    };
+
+    let reaches_target = amount_to_target <= U256::from(amount_less_fee);
+    let sqrt_price_next = match reaches_target {
+        true => sqrt_price_target,
+        false => next_sqrt_price_from_input(sqrt_price, liquidity, amount_less_fee, x_in)?,
+    };
+
+    let (amount_in, amount_out) = match x_in {
+        true => (
+            amount_x_delta(sqrt_price_next, sqrt_price, liquidity, true)?,
+            amount_y_delta(sqrt_price_next, sqrt_price, liquidity, false)?,
+        ),
+        false => (
+            amount_y_delta(sqrt_price, sqrt_price_next, liquidity, true)?,
+            amount_x_delta(sqrt_price, sqrt_price_next, liquidity, false)?,
+        ),
+    };
+
+    let fee = match reaches_target {
+        // ceil(amount_in * fee / (10_000 - fee)), so the fee matches the gross input
+        true => div_ceil(
+            U256::from(amount_in) * U256::from(fee),
+            U256::from(10_000 - fee),
+        )
+        .as_u64(),
+        // The range absorbs the whole remaining input, rounding dust included
+        false => amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(ConcentratedLiquidityError::Underflow)?,
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee,
+    })
+}
+
+/// Applies a signed liquidity change
+///
+/// # Arguments
+/// * `liquidity` - Current liquidity
+/// * `delta` - Liquidity to add (positive) or remove (negative)
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128, ConcentratedLiquidityError> {
+    match delta >= 0 {
+        true => liquidity
+            .checked_add(delta as u128)
+            .ok_or(ConcentratedLiquidityError::Overflow),
+        false => liquidity
+            .checked_sub(delta.unsigned_abs())
+            .ok_or(ConcentratedLiquidityError::Underflow),
+    }
+}
+
+/// `amount_x_delta` without narrowing, so callers can compare against amounts beyond u64
+fn amount_x_delta_u256(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, ConcentratedLiquidityError> {
+    if sqrt_price_a == 0 || sqrt_price_a > sqrt_price_b {
+        return Err(ConcentratedLiquidityError::InvalidSqrtPrice);
+    }
+
+    let liquidity_x64 = U256::from(liquidity) << 64;
+    let (a, b) = (U256::from(sqrt_price_a), U256::from(sqrt_price_b));
+    // Round each term so the difference is rounded in the requested direction
+    let delta = match round_up {
+        true => div_ceil(liquidity_x64, a).checked_sub(liquidity_x64 / b),
+        false => (liquidity_x64 / a).checked_sub(div_ceil(liquidity_x64, b)),
+    };
+
+    Ok(delta.unwrap_or_default())
+}
+
+/// `amount_y_delta` without narrowing, so callers can compare against amounts beyond u64
+fn amount_y_delta_u256(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, ConcentratedLiquidityError> {
+    if sqrt_price_a > sqrt_price_b {
+        return Err(ConcentratedLiquidityError::InvalidSqrtPrice);
+    }
+
+    let product = U256::from(liquidity) * U256::from(sqrt_price_b - sqrt_price_a);
+    let delta = match round_up {
+        true => div_ceil(product, U256::one() << 64),
+        false => product >> 64,
+    };
+
+    Ok(delta)
+}
+
+/// Divides rounding towards positive infinity
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    (numerator + denominator - U256::one()) / denominator
+}
+
+/// Narrows a U256 to u64
+fn to_u64(value: U256) -> Result<u64, ConcentratedLiquidityError> {
+    if value > U256::from(u64::MAX) {
+        return Err(ConcentratedLiquidityError::Overflow);
+    }
+    Ok(value.as_u64())
+}
@@ -0,0 +1,115 @@
+use constant_product_curve::CurveError;
+
+use super::U256;
+
+/// Computes the input required to receive exactly `amount_out` from a constant product pool
+///
+/// Inverts `(x + a * (1 - fee)) * (y - amount_out) = x * y` for `a`. Both divisions
+/// round up, so the trader always pays at least what the forward swap formula needs
+/// and the rounding never works against the pool.
+///
+/// # Arguments
+/// * `reserve_in` - Current reserves of the token being sold to the pool
+/// * `reserve_out` - Current reserves of the token being bought from the pool
+/// * `amount_out` - Exact amount of output tokens requested
+/// * `fee` - Trading fee in basis points
+///
+/// # Returns
+/// * `Result<u64, CurveError>` - Gross input amount, fee included
+pub fn amount_in_for_exact_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee: u16,
+) -> Result<u64, CurveError> {
+    // A fee of 100% would require an infinite input
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+    // Empty pools cannot quote anything
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+    // The pool can never be fully drained of the output token
+    if amount_out >= reserve_out {
+        return Err(CurveError::InsufficientBalance);
+    }
+
+    // Net input that keeps k constant: ceil(x * dy / (y - dy))
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(CurveError::Overflow)?;
+    let denominator = (reserve_out - amount_out) as u128;
+    let net_in = div_ceil(numerator, denominator)?;
+
+    // Gross up for the fee taken on the input: ceil(net * 10_000 / (10_000 - fee))
+    let gross_in = div_ceil(
+        net_in.checked_mul(10_000).ok_or(CurveError::Overflow)?,
+        (10_000 - fee) as u128,
+    )?;
+
+    u64::try_from(gross_in).map_err(|_| CurveError::Overflow)
+}
+
+/// Computes the LP supply minted by the first deposit into a pool
+///
+/// The geometric mean `sqrt(x * y)` makes the initial LP supply independent of
+/// the ratio the pool is seeded at, as in Uniswap v2.
+///
+/// # Arguments
+/// * `amount_x` - Amount of token X deposited
+/// * `amount_y` - Amount of token Y deposited
+///
+/// # Returns
+/// * `u64` - `sqrt(amount_x * amount_y)`, rounded down
+pub fn initial_liquidity(amount_x: u64, amount_y: u64) -> u64 {
+    // sqrt of a product of two u64 always fits in a u64
+    (U256::from(amount_x) * U256::from(amount_y))
+        .integer_sqrt()
+        .as_u64()
+}
+
+/// Computes how much of a single-token deposit to swap so the rest matches the pool ratio
+///
+/// Swapping `s` of `amount_in` leaves `amount_in - s` and the swap output in exactly
+/// the post-swap reserve ratio when
+/// `s = (sqrt(((2 - f) * x)^2 + 4 * (1 - f) * x * amount_in) - (2 - f) * x) / (2 * (1 - f))`
+/// with `x` the input token reserves and `f` the fee. The result is rounded down.
+///
+/// # Arguments
+/// * `reserve_in` - Current reserves of the token being deposited
+/// * `amount_in` - Amount of that token being deposited
+/// * `fee` - Trading fee in basis points
+///
+/// # Returns
+/// * `Result<u64, CurveError>` - Part of `amount_in` to swap into the other token
+pub fn zap_swap_amount(reserve_in: u64, amount_in: u64, fee: u16) -> Result<u64, CurveError> {
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+    if reserve_in == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    // Scale everything by 10_000 so the fee stays in basis points:
+    // s = (sqrt(((10_000 + r) * x)^2 + 4 * r * 10_000 * x * a) - (10_000 + r) * x) / (2 * r)
+    let r = U256::from(10_000 - fee);
+    let b = (U256::from(10_000u64) + r) * U256::from(reserve_in);
+    let discriminant =
+        b * b + U256::from(4u64) * r * U256::from(10_000u64) * U256::from(reserve_in) * U256::from(amount_in);
+    let swap_amount = (discriminant.integer_sqrt() - b) / (U256::from(2u64) * r);
+
+    // Never more than the deposit itself
+    Ok(swap_amount.min(U256::from(amount_in)).as_u64())
+}
+
+/// Divides rounding towards positive infinity
+fn div_ceil(numerator: u128, denominator: u128) -> Result<u128, CurveError> {
+    if denominator == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+    Ok(numerator
+        .checked_add(denominator - 1)
+        .ok_or(CurveError::Overflow)?
+        / denominator)
+}
@@ -0,0 +1,406 @@
+pub mod concentrated_liquidity;
+pub use concentrated_liquidity::*;
+
+pub mod constant_product;
+pub use constant_product::*;
+
+pub mod stable_swap;
+pub use stable_swap::*;
+
+pub mod u256;
+pub use u256::*;
+
+pub mod weighted;
+pub use weighted::*;
+
+use anchor_lang::prelude::*;
+use constant_product_curve::{ConstantProduct, LiquidityPair};
+
+use crate::errors::AmmError;
+use crate::states::{Config, CurveType};
+
+/// Amounts of a swap priced by the pool's curve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Amount the vault receives from the trader, fee included
+    pub amount_in: u64,
+    /// Amount the vault pays out to the trader
+    pub amount_out: u64,
+    /// Part of `amount_in` charged as the swap fee
+    pub fee: u64,
+}
+
+/// Amounts of a single-sided deposit or withdrawal priced by the pool's curve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SingleSidedQuote {
+    /// LP tokens minted by the deposit or burned by the withdrawal
+    pub lp_amount: u64,
+    /// Amount of the single token the vault receives or pays out
+    pub amount: u64,
+    /// Swap fee charged in token X
+    pub fee_x: u64,
+    /// Swap fee charged in token Y
+    pub fee_y: u64,
+}
+
+/// Prices an exact-input swap on the pool's curve
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights, and swap fee)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+/// * `supply` - Current LP token supply
+/// * `is_x` - true if selling token X for Y, false if selling Y for X
+/// * `amount_in` - Amount the vault receives from the trader
+///
+/// # Returns
+/// * `Result<SwapQuote>` - Amounts in, out and fee
+pub fn swap_exact_in(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount_in: u64,
+) -> Result<SwapQuote> {
+    match config.curve_type {
+        CurveType::ConstantProduct => {
+            let mut curve = ConstantProduct::init(reserve_x, reserve_y, supply, config.swap_fee(), None)
+                .map_err(AmmError::from)?;
+            let result = curve
+                .swap(liquidity_pair(is_x), amount_in, 0)
+                .map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                amount_in: result.deposit,
+                amount_out: result.withdraw,
+                fee: result.fee,
+            })
+        }
+        CurveType::StableSwap => {
+            let (reserve_in, reserve_out) = order_reserves(reserve_x, reserve_y, is_x);
+            let result = StableSwap::new(config.amp, config.swap_fee())
+                .and_then(|curve| curve.swap_exact_in(reserve_in, reserve_out, amount_in))
+                .map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                amount_in: result.amount_in,
+                amount_out: result.amount_out,
+                fee: result.fee,
+            })
+        }
+        CurveType::Weighted => {
+            let (reserve_in, reserve_out) = order_reserves(reserve_x, reserve_y, is_x);
+            let result = Weighted::new(config.weight_x, config.swap_fee())
+                .and_then(|curve| curve.swap_exact_in(reserve_in, reserve_out, is_x, amount_in))
+                .map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                amount_in: result.amount_in,
+                amount_out: result.amount_out,
+                fee: result.fee,
+            })
+        }
+    }
+}
+
+/// Prices an exact-output swap on the pool's curve
+///
+/// The required input is rounded up, so the trader always pays at least what
+/// the forward swap formula needs.
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights, and swap fee)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+/// * `supply` - Current LP token supply
+/// * `is_x` - true if selling token X for Y, false if selling Y for X
+/// * `amount_out` - Exact amount the vault pays out to the trader
+///
+/// # Returns
+/// * `Result<SwapQuote>` - Amounts in, out and fee
+pub fn swap_exact_out(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount_out: u64,
+) -> Result<SwapQuote> {
+    let (reserve_in, reserve_out) = order_reserves(reserve_x, reserve_y, is_x);
+    match config.curve_type {
+        CurveType::ConstantProduct => {
+            // Required input, rounded against the trader
+            let amount_in = amount_in_for_exact_out(reserve_in, reserve_out, amount_out, config.swap_fee())
+                .map_err(AmmError::from)?;
+
+            // Run the forward swap on that input to make sure it yields at least `amount_out`
+            let mut curve = ConstantProduct::init(reserve_x, reserve_y, supply, config.swap_fee(), None)
+                .map_err(AmmError::from)?;
+            let result = curve
+                .swap(liquidity_pair(is_x), amount_in, amount_out)
+                .map_err(AmmError::from)?;
+
+            // Any rounding surplus of the forward formula stays in the pool
+            Ok(SwapQuote {
+                amount_in,
+                amount_out,
+                fee: result.fee,
+            })
+        }
+        CurveType::StableSwap => {
+            let result = StableSwap::new(config.amp, config.swap_fee())
+                .and_then(|curve| curve.swap_exact_out(reserve_in, reserve_out, amount_out))
+                .map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                amount_in: result.amount_in,
+                amount_out: result.amount_out,
+                fee: result.fee,
+            })
+        }
+        CurveType::Weighted => {
+            let result = Weighted::new(config.weight_x, config.swap_fee())
+                .and_then(|curve| curve.swap_exact_out(reserve_in, reserve_out, is_x, amount_out))
+                .map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                amount_in: result.amount_in,
+                amount_out: result.amount_out,
+                fee: result.fee,
+            })
+        }
+    }
+}
+
+/// Maps a swap direction to the constant product curve's pair selector
+fn liquidity_pair(is_x: bool) -> LiquidityPair {
+    match is_x {
+        true => LiquidityPair::X,   // Selling X for Y
+        false => LiquidityPair::Y,  // Selling Y for X
+    }
+}
+
+/// Orders reserves as (input, output) for a swap direction
+fn order_reserves(reserve_x: u64, reserve_y: u64, is_x: bool) -> (u64, u64) {
+    match is_x {
+        true => (reserve_x, reserve_y),
+        false => (reserve_y, reserve_x),
+    }
+}
+
+/// Prices a deposit of a single token
+///
+/// Constant product pools swap the optimal part of the deposit into the other
+/// token and deposit both legs in the post-swap ratio. StableSwap pools mint LP
+/// tokens from the growth of the invariant, charging the imbalance fee. Weighted
+/// pools do the same, charging the swap fee on the share they would have to swap.
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights, and fees)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+/// * `supply` - Current LP token supply
+/// * `is_x` - true if depositing token X, false for token Y
+/// * `amount_in` - Amount the vault receives from the depositor
+///
+/// # Returns
+/// * `Result<SingleSidedQuote>` - LP tokens minted and fees charged
+pub fn single_sided_deposit(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount_in: u64,
+) -> Result<SingleSidedQuote> {
+    require!(supply != 0 && reserve_x != 0 && reserve_y != 0, AmmError::ZeroBalance);
+
+    let (lp_amount, fee_x, fee_y) = match config.curve_type {
+        CurveType::ConstantProduct => {
+            let (reserve_in, reserve_out) = order_reserves(reserve_x, reserve_y, is_x);
+            let swap_in = zap_swap_amount(reserve_in, amount_in, config.swap_fee()).map_err(AmmError::from)?;
+            require!(swap_in != 0, AmmError::InvalidAmount);
+
+            let swap = swap_exact_in(config, reserve_x, reserve_y, supply, is_x, swap_in)?;
+
+            // LP reserves after the internal swap, less the protocol's cut of its fee
+            let reserve_in = reserve_in
+                .checked_add(swap_in - config.protocol_cut(swap.fee)?)
+                .ok_or(AmmError::Overflow)?;
+            let reserve_out = reserve_out
+                .checked_sub(swap.amount_out)
+                .ok_or(AmmError::Underflow)?;
+
+            // Deposit both legs, minting against the scarcer one
+            let lp_in = mul_div(amount_in - swap_in, supply, reserve_in)?;
+            let lp_out = mul_div(swap.amount_out, supply, reserve_out)?;
+
+            match is_x {
+                true => (lp_in.min(lp_out), swap.fee, 0),
+                false => (lp_in.min(lp_out), 0, swap.fee),
+            }
+        }
+        CurveType::StableSwap => {
+            let (amount_x, amount_y) = match is_x {
+                true => (amount_in, 0),
+                false => (0, amount_in),
+            };
+            StableSwap::new(config.amp, config.swap_fee())
+                .and_then(|curve| curve.lp_for_deposit(reserve_x, reserve_y, supply, amount_x, amount_y))
+                .map_err(AmmError::from)?
+        }
+        CurveType::Weighted => {
+            let reserve = order_reserves(reserve_x, reserve_y, is_x).0;
+            let (lp_amount, fee) = Weighted::new(config.weight_x, config.swap_fee())
+                .and_then(|curve| curve.deposit_one(reserve, supply, is_x, amount_in))
+                .map_err(AmmError::from)?;
+            match is_x {
+                true => (lp_amount, fee, 0),
+                false => (lp_amount, 0, fee),
+            }
+        }
+    };
+
+    Ok(SingleSidedQuote {
+        lp_amount,
+        amount: amount_in,
+        fee_x,
+        fee_y,
+    })
+}
+
+/// Prices a withdrawal paid out entirely in a single token
+///
+/// Constant product pools withdraw both tokens proportionally and swap the other
+/// leg into the requested token. StableSwap pools solve the requested token's
+/// reserves for the reduced invariant, charging the imbalance fee. Weighted pools
+/// do the same, charging the swap fee on the share they would have to swap.
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights, and fees)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+/// * `supply` - Current LP token supply
+/// * `is_x` - true to receive token X, false to receive token Y
+/// * `lp_amount` - LP tokens being burned
+///
+/// # Returns
+/// * `Result<SingleSidedQuote>` - Amount paid out and fees charged
+pub fn single_sided_withdraw(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    lp_amount: u64,
+) -> Result<SingleSidedQuote> {
+    // The last LP cannot swap against an empty pool
+    require!(lp_amount < supply, AmmError::InsufficientBalance);
+
+    let (amount, fee_x, fee_y) = match config.curve_type {
+        CurveType::ConstantProduct => {
+            let amounts = ConstantProduct::xy_withdraw_amounts_from_l(reserve_x, reserve_y, supply, lp_amount, 6)
+                .map_err(AmmError::from)?;
+            let (amount_same, amount_other) = match is_x {
+                true => (amounts.x, amounts.y),
+                false => (amounts.y, amounts.x),
+            };
+
+            // Sell the other leg back to the pool as it stands after the withdrawal
+            let swap = match amount_other {
+                0 => SwapQuote { amount_in: 0, amount_out: 0, fee: 0 },
+                _ => swap_exact_in(
+                    config,
+                    reserve_x - amounts.x,
+                    reserve_y - amounts.y,
+                    supply - lp_amount,
+                    !is_x,
+                    amount_other,
+                )?,
+            };
+            let amount = amount_same
+                .checked_add(swap.amount_out)
+                .ok_or(AmmError::Overflow)?;
+
+            match is_x {
+                true => (amount, 0, swap.fee),
+                false => (amount, swap.fee, 0),
+            }
+        }
+        CurveType::StableSwap => {
+            let (amount, fee) = StableSwap::new(config.amp, config.swap_fee())
+                .and_then(|curve| curve.withdraw_one(reserve_x, reserve_y, supply, lp_amount, is_x))
+                .map_err(AmmError::from)?;
+            match is_x {
+                true => (amount, fee, 0),
+                false => (amount, 0, fee),
+            }
+        }
+        CurveType::Weighted => {
+            let reserve = order_reserves(reserve_x, reserve_y, is_x).0;
+            let (amount, fee) = Weighted::new(config.weight_x, config.swap_fee())
+                .and_then(|curve| curve.withdraw_one(reserve, supply, is_x, lp_amount))
+                .map_err(AmmError::from)?;
+            match is_x {
+                true => (amount, fee, 0),
+                false => (amount, 0, fee),
+            }
+        }
+    };
+
+    Ok(SingleSidedQuote {
+        lp_amount,
+        amount,
+        fee_x,
+        fee_y,
+    })
+}
+
+/// Computes the pool's invariant on its curve
+///
+/// Every curve's invariant scales linearly with the reserves: `sqrt(x * y)` for
+/// constant product, `D` for StableSwap and `x^w_x * y^w_y` for weighted pools.
+/// Proportional deposits and withdrawals therefore leave the invariant per LP token
+/// unchanged, and swap fees are what makes it grow.
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+///
+/// # Returns
+/// * `Result<U256>` - The invariant, rounded down
+pub fn invariant(config: &Config, reserve_x: u64, reserve_y: u64) -> Result<U256> {
+    let invariant = match config.curve_type {
+        CurveType::ConstantProduct => U256::from(initial_liquidity(reserve_x, reserve_y)),
+        CurveType::StableSwap => StableSwap::new(config.amp, config.swap_fee())
+            .and_then(|curve| curve.compute_d(reserve_x, reserve_y))
+            .map_err(AmmError::from)?,
+        CurveType::Weighted => Weighted::new(config.weight_x, config.swap_fee())
+            .and_then(|curve| curve.invariant(reserve_x, reserve_y))
+            .map_err(AmmError::from)?,
+    };
+    Ok(invariant)
+}
+
+/// Computes the pool's invariant per LP token, the value one LP token has grown to
+///
+/// Only swap fees (and the weight shifts of a weighted pool) move it, so it serves
+/// as the LP token's virtual price.
+///
+/// # Arguments
+/// * `config` - Pool configuration (curve type, amplification or weights)
+/// * `reserve_x` - LP reserves of token X
+/// * `reserve_y` - LP reserves of token Y
+/// * `supply` - Current LP token supply
+///
+/// # Returns
+/// * `Result<u128>` - `invariant / supply` in Q64.64 fixed point, rounded down
+pub fn invariant_per_lp(config: &Config, reserve_x: u64, reserve_y: u64, supply: u64) -> Result<u128> {
+    require!(supply != 0, AmmError::NoLiquidityInPool);
+    let per_lp = (invariant(config, reserve_x, reserve_y)? << 64) / U256::from(supply);
+    require!(per_lp <= U256::from(u128::MAX), AmmError::Overflow);
+    Ok(per_lp.as_u128())
+}
+
+/// Computes `a * b / c`, rounded down
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    require!(c != 0, AmmError::ZeroBalance);
+    u64::try_from(a as u128 * b as u128 / c as u128).map_err(|_| AmmError::Overflow.into())
+}
\ No newline at end of file
@@ -0,0 +1,410 @@
+use super::U256;
+
+/// Smallest amplification coefficient accepted for a StableSwap pool
+pub const MIN_AMP: u64 = 1;
+
+/// Largest amplification coefficient accepted for a StableSwap pool
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Number of tokens in a pool
+const N_COINS: u64 = 2;
+
+/// Upper bound on Newton iterations before giving up
+const MAX_ITERATIONS: usize = 255;
+
+/// Errors returned by the StableSwap solvers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StableSwapError {
+    /// Amplification coefficient outside `MIN_AMP..=MAX_AMP`
+    InvalidAmp,
+    /// Fee of 100% or more
+    InvalidFeeAmount,
+    /// A reserve or the LP supply is empty
+    ZeroBalance,
+    /// The pool cannot pay out the requested amount
+    InsufficientBalance,
+    /// Arithmetic overflow
+    Overflow,
+    /// Arithmetic underflow
+    Underflow,
+    /// Newton's method did not converge
+    NotConverged,
+}
+
+/// Result of a StableSwap trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableSwapResult {
+    /// Gross amount paid in by the trader, fee included
+    pub amount_in: u64,
+    /// Amount paid out to the trader
+    pub amount_out: u64,
+    /// Fee charged on the input amount
+    pub fee: u64,
+}
+
+/// Curve-style StableSwap invariant for two tokens
+///
+/// `A * n^n * (x + y) + D = A * n^n * D + D^(n+1) / (n^n * x * y)`
+///
+/// Behaves like a constant-sum curve around the balance point and like a
+/// constant product curve far from it, which keeps slippage low for pegged pairs.
+/// The amplification coefficient `A` controls how flat the curve is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableSwap {
+    /// Amplification coefficient
+    amp: u64,
+    /// Trading fee in basis points, charged on the input amount
+    fee: u16,
+}
+
+impl StableSwap {
+    /// Creates a StableSwap curve
+    ///
+    /// # Arguments
+    /// * `amp` - Amplification coefficient (`MIN_AMP..=MAX_AMP`)
+    /// * `fee` - Trading fee in basis points
+    pub fn new(amp: u64, fee: u16) -> Result<Self, StableSwapError> {
+        if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+            return Err(StableSwapError::InvalidAmp);
+        }
+        if fee >= 10_000 {
+            return Err(StableSwapError::InvalidFeeAmount);
+        }
+        Ok(Self { amp, fee })
+    }
+
+    /// Returns `A * n^n`
+    fn ann(&self) -> U256 {
+        U256::from(self.amp) * U256::from(N_COINS * N_COINS)
+    }
+
+    /// Computes the invariant `D` for the given reserves with Newton's method
+    ///
+    /// # Arguments
+    /// * `x` - Reserves of token X
+    /// * `y` - Reserves of token Y
+    pub fn compute_d(&self, x: u64, y: u64) -> Result<U256, StableSwapError> {
+        let sum = U256::from(x) + U256::from(y);
+        if sum.is_zero() {
+            return Ok(U256::zero());
+        }
+        if x == 0 || y == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        let n = U256::from(N_COINS);
+        let ann = self.ann();
+        let mut d = sum;
+
+        for _ in 0..MAX_ITERATIONS {
+            // d_p = D^(n+1) / (n^n * x * y)
+            let d_p = d * d / (U256::from(x) * n) * d / (U256::from(y) * n);
+            let d_prev = d;
+            // D = (Ann * S + n * d_p) * D / ((Ann - 1) * D + (n + 1) * d_p)
+            let numerator = (ann * sum + d_p * n) * d;
+            let denominator = (ann - U256::one()) * d + (n + U256::one()) * d_p;
+            d = numerator / denominator;
+
+            if abs_diff(d, d_prev) <= U256::one() {
+                return Ok(d);
+            }
+        }
+
+        Err(StableSwapError::NotConverged)
+    }
+
+    /// Computes the reserves of one token given the other token's reserves and `D`
+    ///
+    /// Solves `y^2 + (x + D / Ann - D) * y = D^(n+1) / (n^n * x * Ann)` with Newton's method.
+    ///
+    /// # Arguments
+    /// * `x` - New reserves of the other token
+    /// * `d` - Invariant to hold
+    pub fn compute_y(&self, x: u64, d: U256) -> Result<U256, StableSwapError> {
+        if x == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        let n = U256::from(N_COINS);
+        let ann = self.ann();
+        let x = U256::from(x);
+
+        // c = D^(n+1) / (n^n * x * Ann)
+        let c = d * d / (x * n) * d / (ann * n);
+        // b = x + D / Ann
+        let b = x + d / ann;
+        let mut y = d;
+
+        for _ in 0..MAX_ITERATIONS {
+            let y_prev = y;
+            // y = (y^2 + c) / (2y + b - D)
+            let denominator = (y * n + b)
+                .checked_sub(d)
+                .ok_or(StableSwapError::Underflow)?;
+            if denominator.is_zero() {
+                return Err(StableSwapError::NotConverged);
+            }
+            y = (y * y + c) / denominator;
+
+            if abs_diff(y, y_prev) <= U256::one() {
+                return Ok(y);
+            }
+        }
+
+        Err(StableSwapError::NotConverged)
+    }
+
+    /// Computes the marginal price of token X in token Y, `-dy/dx` along the invariant
+    ///
+    /// Differentiating the invariant gives
+    /// `y * (Ann * x^2 * y * n^n + D^(n+1)) / (x * (Ann * x * y^2 * n^n + D^(n+1)))`,
+    /// computed here with both inner terms divided by `D^2` to stay within 256 bits.
+    /// The fraction is then scaled down to at most 128 bits so callers can cross-multiply it.
+    /// Fees are not included.
+    ///
+    /// # Arguments
+    /// * `x` - Reserves of token X
+    /// * `y` - Reserves of token Y
+    ///
+    /// # Returns
+    /// * `Result<(U256, U256), StableSwapError>` - Numerator and denominator of the price
+    pub fn marginal_price(&self, x: u64, y: u64) -> Result<(U256, U256), StableSwapError> {
+        let d = self.compute_d(x, y)?;
+        if d.is_zero() {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        let n_n = U256::from(N_COINS * N_COINS);
+        let ann = self.ann();
+        let (x, y) = (U256::from(x), U256::from(y));
+        let d_squared = d * d;
+
+        // D^2 >= n^n * x * y on the curve, so each term is at most Ann * x (or Ann * y)
+        let term_x = ann * n_n * x * x * y / d_squared;
+        let term_y = ann * n_n * x * y * y / d_squared;
+        let numerator = y * (term_x + d);
+        let denominator = x * (term_y + d);
+
+        // Drop the same low bits from both sides
+        let shift = numerator.max(denominator).bits().saturating_sub(128);
+        Ok((numerator >> shift, denominator >> shift))
+    }
+
+    /// Swaps an exact input amount
+    ///
+    /// The fee is taken from the input amount, as with the constant product curve.
+    /// The output is rounded down in the pool's favor.
+    ///
+    /// # Arguments
+    /// * `reserve_in` - Reserves of the token being sold to the pool
+    /// * `reserve_out` - Reserves of the token being bought from the pool
+    /// * `amount_in` - Gross amount paid in, fee included
+    pub fn swap_exact_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+    ) -> Result<StableSwapResult, StableSwapError> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        let fee = fee_amount(amount_in, self.fee)?;
+        let net_in = amount_in - fee;
+
+        let d = self.compute_d(reserve_in, reserve_out)?;
+        let new_reserve_in = reserve_in
+            .checked_add(net_in)
+            .ok_or(StableSwapError::Overflow)?;
+        let new_reserve_out = self.compute_y(new_reserve_in, d)?;
+
+        // Subtract one extra unit so rounding in the solver never favors the trader
+        let amount_out = U256::from(reserve_out)
+            .checked_sub(new_reserve_out)
+            .and_then(|out| out.checked_sub(U256::one()))
+            .unwrap_or_default();
+
+        Ok(StableSwapResult {
+            amount_in,
+            amount_out: to_u64(amount_out)?,
+            fee,
+        })
+    }
+
+    /// Computes the gross input required to receive exactly `amount_out`
+    ///
+    /// The required input is rounded up in the pool's favor.
+    ///
+    /// # Arguments
+    /// * `reserve_in` - Reserves of the token being sold to the pool
+    /// * `reserve_out` - Reserves of the token being bought from the pool
+    /// * `amount_out` - Exact amount of output tokens requested
+    pub fn swap_exact_out(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_out: u64,
+    ) -> Result<StableSwapResult, StableSwapError> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+        // The pool can never be fully drained of the output token
+        if amount_out >= reserve_out {
+            return Err(StableSwapError::InsufficientBalance);
+        }
+
+        let d = self.compute_d(reserve_in, reserve_out)?;
+        // Add one extra unit so rounding in the solver never favors the trader
+        let new_reserve_in = self.compute_y(reserve_out - amount_out, d)? + U256::one();
+        let net_in = to_u64(
+            new_reserve_in
+                .checked_sub(U256::from(reserve_in))
+                .ok_or(StableSwapError::Underflow)?,
+        )?;
+
+        // Gross up for the fee taken on the input: ceil(net * 10_000 / (10_000 - fee))
+        let amount_in = to_u64(div_ceil(
+            U256::from(net_in) * U256::from(10_000u64),
+            U256::from(10_000 - self.fee),
+        ))?;
+
+        Ok(StableSwapResult {
+            amount_in,
+            amount_out,
+            fee: amount_in - net_in,
+        })
+    }
+
+    /// Computes the LP tokens minted for an arbitrary (possibly one-sided) deposit
+    ///
+    /// LP tokens are minted in proportion to the growth of `D`. Half the swap fee is
+    /// charged on the deposited amounts, like Curve's imbalance fee, so that a
+    /// one-sided deposit followed by a proportional withdrawal is never cheaper
+    /// than a swap.
+    ///
+    /// # Arguments
+    /// * `reserve_x` - Current reserves of token X
+    /// * `reserve_y` - Current reserves of token Y
+    /// * `supply` - Current LP supply
+    /// * `amount_x` - Amount of token X deposited
+    /// * `amount_y` - Amount of token Y deposited
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64, u64), StableSwapError>` - LP tokens minted, and the imbalance
+    ///   fees charged on token X and token Y
+    pub fn lp_for_deposit(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        amount_x: u64,
+        amount_y: u64,
+    ) -> Result<(u64, u64, u64), StableSwapError> {
+        if supply == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        let d0 = self.compute_d(reserve_x, reserve_y)?;
+        if d0.is_zero() {
+            return Err(StableSwapError::ZeroBalance);
+        }
+
+        // Imbalance fee: half the swap fee on what is added
+        let half_fee = self.fee / 2;
+        let fee_x = fee_amount(amount_x, half_fee)?;
+        let fee_y = fee_amount(amount_y, half_fee)?;
+        let (net_x, net_y) = (amount_x - fee_x, amount_y - fee_y);
+
+        let d1 = self.compute_d(
+            reserve_x.checked_add(net_x).ok_or(StableSwapError::Overflow)?,
+            reserve_y.checked_add(net_y).ok_or(StableSwapError::Overflow)?,
+        )?;
+
+        // lp = supply * (D1 - D0) / D0, rounded down
+        let growth = d1.checked_sub(d0).ok_or(StableSwapError::Underflow)?;
+        let lp_amount = to_u64(U256::from(supply) * growth / d0)?;
+
+        Ok((lp_amount, fee_x, fee_y))
+    }
+
+    /// Computes the tokens paid out when `lp_amount` is redeemed entirely for one token
+    ///
+    /// `D` shrinks in proportion to the LP burned and the requested token's reserves
+    /// are solved for the new `D`. Half the swap fee is charged on the payout.
+    ///
+    /// # Arguments
+    /// * `reserve_x` - Current reserves of token X
+    /// * `reserve_y` - Current reserves of token Y
+    /// * `supply` - Current LP supply
+    /// * `lp_amount` - LP tokens being burned
+    /// * `is_x` - true to receive token X, false to receive token Y
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64), StableSwapError>` - Amount paid out, and the imbalance fee
+    ///   withheld from it in the same token
+    pub fn withdraw_one(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        lp_amount: u64,
+        is_x: bool,
+    ) -> Result<(u64, u64), StableSwapError> {
+        if supply == 0 {
+            return Err(StableSwapError::ZeroBalance);
+        }
+        if lp_amount >= supply {
+            return Err(StableSwapError::InsufficientBalance);
+        }
+
+        let d0 = self.compute_d(reserve_x, reserve_y)?;
+        // D1 = D0 - lp_amount * D0 / supply, rounded up so the payout is rounded down
+        let removed = div_ceil(U256::from(lp_amount) * d0, U256::from(supply));
+        let d1 = d0.checked_sub(removed).ok_or(StableSwapError::Underflow)?;
+
+        let (reserve_out, reserve_other) = match is_x {
+            true => (reserve_x, reserve_y),
+            false => (reserve_y, reserve_x),
+        };
+        let new_reserve_out = self.compute_y(reserve_other, d1)?;
+
+        let amount_out = to_u64(
+            U256::from(reserve_out)
+                .checked_sub(new_reserve_out)
+                .and_then(|out| out.checked_sub(U256::one()))
+                .unwrap_or_default(),
+        )?;
+
+        // Imbalance fee: half the swap fee on what is removed
+        let fee = fee_amount(amount_out, self.fee / 2)?;
+
+        Ok((amount_out - fee, fee))
+    }
+}
+
+/// Fee charged on `amount` at `fee` basis points, rounded down
+fn fee_amount(amount: u64, fee: u16) -> Result<u64, StableSwapError> {
+    u64::try_from((amount as u128) * (fee as u128) / 10_000).map_err(|_| StableSwapError::Overflow)
+}
+
+/// Absolute difference of two values
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+/// Divides rounding towards positive infinity
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    (numerator + denominator - U256::one()) / denominator
+}
+
+/// Narrows a U256 to u64
+fn to_u64(value: U256) -> Result<u64, StableSwapError> {
+    if value > U256::from(u64::MAX) {
+        return Err(StableSwapError::Overflow);
+    }
+    Ok(value.as_u64())
+}
@@ -0,0 +1,11 @@
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::manual_div_ceil)]
+#![allow(clippy::manual_range_contains)]
+#![allow(clippy::ptr_offset_with_cast)]
+
+use uint::construct_uint;
+
+construct_uint! {
+    /// 256-bit unsigned integer for curve math whose intermediate products overflow u128
+    pub struct U256(4);
+}
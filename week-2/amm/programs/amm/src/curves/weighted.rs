@@ -0,0 +1,448 @@
+use super::U256;
+
+/// One in Q64.64 fixed point
+pub const WEIGHT_ONE: u128 = 1 << 64;
+
+/// Smallest weight a token of a weighted pool may have, in basis points
+pub const MIN_WEIGHT: u16 = 100;
+
+/// Largest weight a token of a weighted pool may have, in basis points
+pub const MAX_WEIGHT: u16 = 9_900;
+
+/// `ln(2)` in Q64.64 fixed point
+const LN_2: u128 = 0xB172_17F7_D1CF_79AB;
+
+/// Terms of the Taylor series of `e^x` evaluated by `exp2`, enough for `x < ln(2)`
+const EXP_TERMS: u64 = 24;
+
+/// Relative error margin added to every power, as a right shift (`2^-32` ≈ 2.3e-10),
+/// well above the error of the fixed-point `log2` and `exp2`
+const POW_ERROR_SHIFT: u32 = 32;
+
+/// Errors returned by the weighted pool math
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightedError {
+    /// Weight outside `MIN_WEIGHT..=MAX_WEIGHT`
+    InvalidWeight,
+    /// Fee of 100% or more
+    InvalidFeeAmount,
+    /// A reserve or the LP supply is empty
+    ZeroBalance,
+    /// The pool cannot pay out the requested amount
+    InsufficientBalance,
+    /// Arithmetic overflow
+    Overflow,
+    /// Arithmetic underflow
+    Underflow,
+}
+
+/// Result of a weighted pool trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightedResult {
+    /// Gross amount paid in by the trader, fee included
+    pub amount_in: u64,
+    /// Amount paid out to the trader
+    pub amount_out: u64,
+    /// Fee charged on the input amount
+    pub fee: u64,
+}
+
+/// Balancer-style weighted constant mean invariant for two tokens
+///
+/// `x^w_x * y^w_y = k`, with `w_x + w_y = 1`
+///
+/// An 80/20 pool needs only a fifth of its value in the 20% token, and the spot
+/// price of X in Y is `(y / w_y) / (x / w_x)`. With equal weights this is the
+/// constant product curve.
+///
+/// Powers with fractional exponents are computed through `log2` and `exp2` in
+/// Q64.64 fixed point, then widened by `POW_ERROR_SHIFT` so that every result is
+/// rounded in the pool's favor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weighted {
+    /// Weight of token X as a fraction of one (Q64.64)
+    weight_x: u128,
+    /// Trading fee in basis points, charged on the input amount
+    fee: u16,
+}
+
+impl Weighted {
+    /// Creates a weighted curve
+    ///
+    /// # Arguments
+    /// * `weight_x` - Weight of token X as a fraction of one (Q64.64), within
+    ///   `MIN_WEIGHT..=MAX_WEIGHT` basis points
+    /// * `fee` - Trading fee in basis points
+    pub fn new(weight_x: u128, fee: u16) -> Result<Self, WeightedError> {
+        if weight_x < bps_to_weight(MIN_WEIGHT) || weight_x > bps_to_weight(MAX_WEIGHT) {
+            return Err(WeightedError::InvalidWeight);
+        }
+        if fee >= 10_000 {
+            return Err(WeightedError::InvalidFeeAmount);
+        }
+        Ok(Self { weight_x, fee })
+    }
+
+    /// Returns the weights as (sold token, bought token) for a swap direction
+    fn weights(&self, is_x: bool) -> (u128, u128) {
+        let weight_y = WEIGHT_ONE - self.weight_x;
+        match is_x {
+            true => (self.weight_x, weight_y),
+            false => (weight_y, self.weight_x),
+        }
+    }
+
+    /// Computes the output of selling exactly `amount_in`
+    ///
+    /// `amount_out = reserve_out * (1 - (reserve_in / (reserve_in + net_in))^(w_in / w_out))`,
+    /// rounded down.
+    ///
+    /// # Arguments
+    /// * `reserve_in` - Reserves of the token being sold to the pool
+    /// * `reserve_out` - Reserves of the token being bought from the pool
+    /// * `is_x` - true if selling token X for Y, false if selling Y for X
+    /// * `amount_in` - Gross amount paid in, fee included
+    pub fn swap_exact_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        is_x: bool,
+        amount_in: u64,
+    ) -> Result<WeightedResult, WeightedError> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+
+        let fee = fee_amount(amount_in, self.fee)?;
+        let net_in = amount_in - fee;
+        let new_reserve_in = reserve_in
+            .checked_add(net_in)
+            .ok_or(WeightedError::Overflow)?;
+
+        // Share of the output reserves left in the pool, rounded up
+        let (weight_in, weight_out) = self.weights(is_x);
+        let base = div_ceil(U256::from(reserve_in) << 64, U256::from(new_reserve_in));
+        let remaining = pow_up(to_u128(base)?, ratio(weight_in, weight_out)?)?.min(WEIGHT_ONE);
+
+        let amount_out = (U256::from(reserve_out) * U256::from(WEIGHT_ONE - remaining)) >> 64;
+
+        Ok(WeightedResult {
+            amount_in,
+            amount_out: to_u64(amount_out)?,
+            fee,
+        })
+    }
+
+    /// Computes the gross input required to receive exactly `amount_out`
+    ///
+    /// `net_in = reserve_in * ((reserve_out / (reserve_out - amount_out))^(w_out / w_in) - 1)`,
+    /// rounded up, then grossed up for the fee.
+    ///
+    /// # Arguments
+    /// * `reserve_in` - Reserves of the token being sold to the pool
+    /// * `reserve_out` - Reserves of the token being bought from the pool
+    /// * `is_x` - true if selling token X for Y, false if selling Y for X
+    /// * `amount_out` - Exact amount of output tokens requested
+    pub fn swap_exact_out(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        is_x: bool,
+        amount_out: u64,
+    ) -> Result<WeightedResult, WeightedError> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+        // The pool can never be fully drained of the output token
+        if amount_out >= reserve_out {
+            return Err(WeightedError::InsufficientBalance);
+        }
+
+        // Growth of the input reserves needed, rounded up
+        let (weight_in, weight_out) = self.weights(is_x);
+        let base = div_ceil(U256::from(reserve_out) << 64, U256::from(reserve_out - amount_out));
+        let growth = pow_up(to_u128(base)?, ratio(weight_out, weight_in)?)?;
+
+        let net_in = div_ceil(
+            U256::from(reserve_in) * U256::from(growth.saturating_sub(WEIGHT_ONE)),
+            U256::from(WEIGHT_ONE),
+        );
+        // Gross up for the fee taken on the input
+        let amount_in = div_ceil(net_in * U256::from(10_000u64), U256::from(10_000 - self.fee as u64));
+        let amount_in = to_u64(amount_in)?;
+
+        Ok(WeightedResult {
+            amount_in,
+            amount_out,
+            fee: amount_in - to_u64(net_in)?,
+        })
+    }
+
+    /// Computes the LP tokens minted for a deposit of a single token
+    ///
+    /// Only the part of the deposit the pool would otherwise have to swap, a
+    /// `1 - w` share of it, pays the swap fee. The rest mints
+    /// `supply * ((reserve + net_in) / reserve)^w - supply`, rounded down.
+    ///
+    /// # Arguments
+    /// * `reserve` - Current reserves of the token deposited
+    /// * `supply` - Current LP supply
+    /// * `is_x` - true if depositing token X, false for token Y
+    /// * `amount_in` - Amount deposited
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64), WeightedError>` - LP tokens minted and the fee charged
+    pub fn deposit_one(
+        &self,
+        reserve: u64,
+        supply: u64,
+        is_x: bool,
+        amount_in: u64,
+    ) -> Result<(u64, u64), WeightedError> {
+        if reserve == 0 || supply == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+
+        let (weight, weight_other) = self.weights(is_x);
+        let fee = fee_amount(taxable(amount_in, weight_other)?, self.fee)?;
+        let new_reserve = reserve
+            .checked_add(amount_in - fee)
+            .ok_or(WeightedError::Overflow)?;
+
+        // Growth of the invariant, rounded down
+        let base = (U256::from(new_reserve) << 64) / U256::from(reserve);
+        let growth = pow_down(to_u128(base)?, weight)?;
+
+        let lp_amount = (U256::from(supply) * U256::from(growth.saturating_sub(WEIGHT_ONE))) >> 64;
+
+        Ok((to_u64(lp_amount)?, fee))
+    }
+
+    /// Computes the tokens paid out when `lp_amount` is redeemed entirely for one token
+    ///
+    /// Pays `reserve * (1 - ((supply - lp_amount) / supply)^(1 / w))`, rounded down,
+    /// less the swap fee on the `1 - w` share the pool would otherwise have to swap.
+    ///
+    /// # Arguments
+    /// * `reserve` - Current reserves of the token withdrawn
+    /// * `supply` - Current LP supply
+    /// * `is_x` - true to receive token X, false to receive token Y
+    /// * `lp_amount` - LP tokens being burned
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64), WeightedError>` - Amount paid out and the fee charged
+    pub fn withdraw_one(
+        &self,
+        reserve: u64,
+        supply: u64,
+        is_x: bool,
+        lp_amount: u64,
+    ) -> Result<(u64, u64), WeightedError> {
+        if reserve == 0 || supply == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+        if lp_amount >= supply {
+            return Err(WeightedError::InsufficientBalance);
+        }
+
+        // Share of the reserves left in the pool, rounded up
+        let (weight, weight_other) = self.weights(is_x);
+        let base = div_ceil(U256::from(supply - lp_amount) << 64, U256::from(supply));
+        let remaining = pow_up(to_u128(base)?, ratio(WEIGHT_ONE, weight)?)?.min(WEIGHT_ONE);
+
+        let gross = to_u64((U256::from(reserve) * U256::from(WEIGHT_ONE - remaining)) >> 64)?;
+        let fee = div_ceil(
+            U256::from(taxable(gross, weight_other)?) * U256::from(self.fee),
+            U256::from(10_000u64),
+        );
+        let fee = to_u64(fee)?;
+
+        Ok((gross - fee, fee))
+    }
+
+    /// Checks that the weighted invariant did not decrease between two reserve states
+    ///
+    /// Compares `w_x * log2(x_after / x_before) + w_y * log2(y_after / y_before)` with
+    /// zero. Every step rounds down, so the check can only err in the pool's favor.
+    ///
+    /// # Arguments
+    /// * `before` - Reserves of token X and token Y before
+    /// * `after` - Reserves of token X and token Y after
+    pub fn invariant_holds(&self, before: (u64, u64), after: (u64, u64)) -> Result<bool, WeightedError> {
+        if before.0 == 0 || before.1 == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+        // Draining either side loses the whole invariant
+        if after.0 == 0 || after.1 == 0 {
+            return Ok(false);
+        }
+
+        let log_x = log2(to_u128((U256::from(after.0) << 64) / U256::from(before.0))?);
+        let log_y = log2(to_u128((U256::from(after.1) << 64) / U256::from(before.1))?);
+        let growth = mul_signed(log_x, self.weight_x)?
+            .checked_add(mul_signed(log_y, WEIGHT_ONE - self.weight_x)?)
+            .ok_or(WeightedError::Overflow)?;
+
+        Ok(growth >= 0)
+    }
+
+    /// Computes the weighted geometric mean of the reserves, `x^w_x * y^w_y`, rounded down
+    ///
+    /// Evaluated as `big * (small / big)^w_small` so the power never exceeds one.
+    ///
+    /// # Arguments
+    /// * `x` - Reserves of token X
+    /// * `y` - Reserves of token Y
+    pub fn invariant(&self, x: u64, y: u64) -> Result<U256, WeightedError> {
+        if x == 0 || y == 0 {
+            return Err(WeightedError::ZeroBalance);
+        }
+
+        let (big, small, weight_small) = match x >= y {
+            true => (x, y, WEIGHT_ONE - self.weight_x),
+            false => (y, x, self.weight_x),
+        };
+        let power = pow_down(ratio(small as u128, big as u128)?, weight_small)?;
+
+        Ok((U256::from(big) * U256::from(power)) >> 64)
+    }
+}
+
+/// Converts a weight in basis points to a fraction of one (Q64.64)
+pub fn bps_to_weight(weight: u16) -> u128 {
+    (weight as u128) * WEIGHT_ONE / 10_000
+}
+
+/// Computes `base^exponent` in Q64.64, rounded up
+fn pow_up(base: u128, exponent: u128) -> Result<u128, WeightedError> {
+    let power = pow(base, exponent)?;
+    power
+        .checked_add((power >> POW_ERROR_SHIFT) + 1)
+        .ok_or(WeightedError::Overflow)
+}
+
+/// Computes `base^exponent` in Q64.64, rounded down
+fn pow_down(base: u128, exponent: u128) -> Result<u128, WeightedError> {
+    let power = pow(base, exponent)?;
+    Ok(power.saturating_sub((power >> POW_ERROR_SHIFT) + 1))
+}
+
+/// Computes `base^exponent = 2^(exponent * log2(base))` in Q64.64
+fn pow(base: u128, exponent: u128) -> Result<u128, WeightedError> {
+    if base == 0 {
+        return Err(WeightedError::ZeroBalance);
+    }
+    exp2(mul_signed(log2(base), exponent)?)
+}
+
+/// Computes `log2(x)` of a positive Q64.64 number, rounded down
+///
+/// The integer part comes from the position of the highest bit, and each of the
+/// 64 fractional bits from squaring the normalized mantissa.
+fn log2(x: u128) -> i128 {
+    let msb = 127 - x.leading_zeros() as i128;
+    let integer = msb - 64;
+
+    // Normalize to [1, 2) in Q64.64
+    let mut y = match integer >= 0 {
+        true => U256::from(x >> integer),
+        false => U256::from(x) << (-integer) as usize,
+    };
+    let two = U256::from(2 * WEIGHT_ONE);
+
+    let mut result = integer << 64;
+    for bit in (0..64).rev() {
+        y = (y * y) >> 64;
+        if y >= two {
+            y >>= 1;
+            result += 1 << bit;
+        }
+    }
+    result
+}
+
+/// Computes `2^x` of a Q64.64 number, rounded down
+///
+/// `2^x = 2^n * e^(f * ln(2))` with `n` the integer and `f` the fractional part
+/// of `x`; the exponential is summed as a Taylor series.
+fn exp2(x: i128) -> Result<u128, WeightedError> {
+    let integer = x >> 64;
+    let fraction = (x - (integer << 64)) as u128;
+
+    // e^z for z = f * ln(2) < 0.7
+    let z = (U256::from(fraction) * U256::from(LN_2)) >> 64;
+    let mut term = U256::from(WEIGHT_ONE);
+    let mut sum = term;
+    for k in 1..=EXP_TERMS {
+        term = ((term * z) >> 64) / U256::from(k);
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+    }
+    // e^z < 2, so the sum fits in 65 bits
+    let sum = sum.as_u128();
+
+    match integer {
+        // Results at or above 2^64 do not fit in a Q64.64 u128
+        n if n >= 63 => Err(WeightedError::Overflow),
+        n if n >= 0 => Ok(sum << n),
+        n if n > -128 => Ok(sum >> (-n)),
+        _ => Ok(0),
+    }
+}
+
+/// Multiplies a signed Q64.64 number by an unsigned one, rounding towards negative infinity
+fn mul_signed(a: i128, b: u128) -> Result<i128, WeightedError> {
+    let product = U256::from(a.unsigned_abs()) * U256::from(b);
+    let magnitude = product >> 64;
+    // Rounding the magnitude of a negative product up keeps the result rounded down
+    let magnitude = match a < 0 && (product & U256::from(u64::MAX)) != U256::zero() {
+        true => magnitude + U256::one(),
+        false => magnitude,
+    };
+    let magnitude = i128::try_from(to_u128(magnitude)?).map_err(|_| WeightedError::Overflow)?;
+    Ok(match a < 0 {
+        true => -magnitude,
+        false => magnitude,
+    })
+}
+
+/// Returns `numerator / denominator` in Q64.64
+fn ratio(numerator: u128, denominator: u128) -> Result<u128, WeightedError> {
+    if denominator == 0 {
+        return Err(WeightedError::ZeroBalance);
+    }
+    to_u128((U256::from(numerator) << 64) / U256::from(denominator))
+}
+
+/// Returns the `weight` share of `amount`, rounded up
+fn taxable(amount: u64, weight: u128) -> Result<u64, WeightedError> {
+    to_u64(div_ceil(U256::from(amount) * U256::from(weight), U256::from(WEIGHT_ONE)))
+}
+
+/// Computes the fee charged on `amount`, rounded down
+fn fee_amount(amount: u64, fee: u16) -> Result<u64, WeightedError> {
+    u64::try_from((amount as u128) * (fee as u128) / 10_000).map_err(|_| WeightedError::Overflow)
+}
+
+/// Divides rounding towards positive infinity
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    (numerator + denominator - U256::one()) / denominator
+}
+
+/// Narrows a U256 to u128, failing on overflow
+fn to_u128(value: U256) -> Result<u128, WeightedError> {
+    if value > U256::from(u128::MAX) {
+        return Err(WeightedError::Overflow);
+    }
+    Ok(value.as_u128())
+}
+
+/// Narrows a U256 to u64, failing on overflow
+fn to_u64(value: U256) -> Result<u64, WeightedError> {
+    if value > U256::from(u64::MAX) {
+        return Err(WeightedError::Overflow);
+    }
+    Ok(value.as_u64())
+}
@@ -1,6 +1,8 @@
 use anchor_lang::error_code;
 use constant_product_curve::CurveError;
 
+use crate::curves::{ConcentratedLiquidityError, StableSwapError, WeightedError};
+
 #[error_code]
 pub enum AmmError {
     #[msg("DefaultError")]
@@ -39,6 +41,78 @@ pub enum AmmError {
     InsufficientBalance,
     #[msg("Zero balance.")]
     ZeroBalance,
+    #[msg("Mint uses an unsupported Token-2022 extension.")]
+    UnsupportedMint,
+    #[msg("A flash loan is already in progress.")]
+    FlashLoanInProgress,
+    #[msg("No flash loan in progress.")]
+    NoFlashLoan,
+    #[msg("Flash loan is not repaid later in the transaction.")]
+    MissingFlashRepay,
+    #[msg("Pool invariant violated.")]
+    InvariantViolated,
+    #[msg("Invalid amplification coefficient.")]
+    InvalidAmp,
+    #[msg("Curve solver did not converge.")]
+    NotConverged,
+    #[msg("Invalid tick.")]
+    InvalidTick,
+    #[msg("Invalid tick spacing.")]
+    InvalidTickSpacing,
+    #[msg("Invalid sqrt price.")]
+    InvalidSqrtPrice,
+    #[msg("Too many initialized ticks.")]
+    TickBookFull,
+    #[msg("Position still holds liquidity or fees.")]
+    PositionNotEmpty,
+    #[msg("Fee tiers must be non-empty, ascending and unique.")]
+    InvalidFeeTiers,
+    #[msg("Fee is not one of the allowed fee tiers.")]
+    FeeTierNotAllowed,
+    #[msg("Pool mints must be sorted, mint_x before mint_y.")]
+    MintsNotSorted,
+    #[msg("Invalid factory admin.")]
+    InvalidFactoryAdmin,
+    #[msg("Dynamic fee caps must bracket the base fee and decay period must be positive.")]
+    InvalidDynamicFee,
+    #[msg("Reward rate must be positive and the schedule must end after it starts.")]
+    InvalidRewardSchedule,
+    #[msg("Farm has ended.")]
+    FarmEnded,
+    #[msg("Farm has not ended yet.")]
+    FarmNotEnded,
+    #[msg("Long-term order does not belong to the pool's order book.")]
+    InvalidOrder,
+    #[msg("Too many distinct long-term order expiries.")]
+    TooManyOrderExpiries,
+    #[msg("Pool still holds liquidity, tokens or open orders.")]
+    PoolNotEmpty,
+    #[msg("Invalid token weights or weight schedule.")]
+    InvalidWeights,
+    #[msg("Price guard band and maximum oracle age must be positive.")]
+    InvalidPriceGuard,
+    #[msg("Oracle account does not match the pool's price guard.")]
+    OracleMismatch,
+    #[msg("Oracle account is not a fully verified price update with a positive price.")]
+    InvalidOracle,
+    #[msg("Oracle price is too old.")]
+    StaleOraclePrice,
+    #[msg("Swap moves the pool price too far from the oracle price.")]
+    PriceDeviationExceeded,
+    #[msg("User lacks the permission this pool requires.")]
+    PermissionDenied,
+    #[msg("Return data is missing, not set by the AMM, or malformed.")]
+    InvalidReturnData,
+    #[msg("Long-term order is smaller than the pool's minimum order size.")]
+    OrderTooSmall,
+    #[msg("Long-term order duration exceeds the maximum.")]
+    OrderTooLong,
+    #[msg("Long-term order has not expired yet.")]
+    OrderNotExpired,
+    #[msg("Position liquidity is below the minimum.")]
+    PositionTooSmall,
+    #[msg("Protocol fees must be collected and switched off first.")]
+    ProtocolFeesOutstanding,
 }
 
 impl From<CurveError> for AmmError {
@@ -53,4 +127,43 @@ impl From<CurveError> for AmmError {
             CurveError::SlippageLimitExceeded => AmmError::SlippageExceeded,
         }
     }
+}
+
+impl From<StableSwapError> for AmmError {
+    fn from(error: StableSwapError) -> AmmError {
+        match error {
+            StableSwapError::InvalidAmp => AmmError::InvalidAmp,
+            StableSwapError::InvalidFeeAmount => AmmError::InvalidFee,
+            StableSwapError::ZeroBalance => AmmError::ZeroBalance,
+            StableSwapError::InsufficientBalance => AmmError::InsufficientBalance,
+            StableSwapError::Overflow => AmmError::Overflow,
+            StableSwapError::Underflow => AmmError::Underflow,
+            StableSwapError::NotConverged => AmmError::NotConverged,
+        }
+    }
+}
+
+impl From<ConcentratedLiquidityError> for AmmError {
+    fn from(error: ConcentratedLiquidityError) -> AmmError {
+        match error {
+            ConcentratedLiquidityError::InvalidTick => AmmError::InvalidTick,
+            ConcentratedLiquidityError::InvalidSqrtPrice => AmmError::InvalidSqrtPrice,
+            ConcentratedLiquidityError::InvalidFeeAmount => AmmError::InvalidFee,
+            ConcentratedLiquidityError::Overflow => AmmError::Overflow,
+            ConcentratedLiquidityError::Underflow => AmmError::Underflow,
+        }
+    }
+}
+
+impl From<WeightedError> for AmmError {
+    fn from(error: WeightedError) -> AmmError {
+        match error {
+            WeightedError::InvalidWeight => AmmError::InvalidWeights,
+            WeightedError::InvalidFeeAmount => AmmError::InvalidFee,
+            WeightedError::ZeroBalance => AmmError::ZeroBalance,
+            WeightedError::InsufficientBalance => AmmError::InsufficientBalance,
+            WeightedError::Overflow => AmmError::Overflow,
+            WeightedError::Underflow => AmmError::Underflow,
+        }
+    }
 }
\ No newline at end of file
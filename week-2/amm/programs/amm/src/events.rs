@@ -0,0 +1,443 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{DynamicFee, PriceGuard};
+
+/// Emitted when a pool is created
+#[event]
+#[derive(Clone, Debug)]
+pub struct InitializeEvent {
+    /// Config account of the new pool
+    pub pool: Pubkey,
+    /// Account that created the pool
+    pub admin: Pubkey,
+    /// Token X mint
+    pub mint_x: Pubkey,
+    /// Token Y mint
+    pub mint_y: Pubkey,
+    /// Base trading fee in basis points
+    pub fee: u16,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted on every swap, exact-input or exact-output
+#[event]
+#[derive(Clone, Debug)]
+pub struct SwapEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Account that performed the swap
+    pub user: Pubkey,
+    /// true if token X was sold for Y, false if Y was sold for X
+    pub is_x: bool,
+    /// Amount of the input token sent by the user, transfer fee included
+    pub amount_in: u64,
+    /// Amount of the output token sent by the pool, before any transfer fee
+    pub amount_out: u64,
+    /// Swap fee charged, in the input token (protocol share included)
+    pub fee: u64,
+    /// LP reserves of token X after the swap
+    pub reserve_x: u64,
+    /// LP reserves of token Y after the swap
+    pub reserve_y: u64,
+    /// LP token supply after the swap
+    pub lp_supply: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted on every deposit, balanced or single-sided
+#[event]
+#[derive(Clone, Debug)]
+pub struct DepositEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Account that provided the liquidity
+    pub user: Pubkey,
+    /// Amount of token X sent by the user, transfer fee included
+    pub amount_x: u64,
+    /// Amount of token Y sent by the user, transfer fee included
+    pub amount_y: u64,
+    /// LP tokens minted to the user
+    pub lp_amount: u64,
+    /// Swap fee charged in token X by a single-sided deposit
+    pub fee_x: u64,
+    /// Swap fee charged in token Y by a single-sided deposit
+    pub fee_y: u64,
+    /// LP reserves of token X after the deposit
+    pub reserve_x: u64,
+    /// LP reserves of token Y after the deposit
+    pub reserve_y: u64,
+    /// LP token supply after the deposit
+    pub lp_supply: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted on every withdrawal, balanced or single-sided
+#[event]
+#[derive(Clone, Debug)]
+pub struct WithdrawEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Account that removed the liquidity
+    pub user: Pubkey,
+    /// Amount of token X sent by the pool, before any transfer fee
+    pub amount_x: u64,
+    /// Amount of token Y sent by the pool, before any transfer fee
+    pub amount_y: u64,
+    /// LP tokens burned by the user
+    pub lp_amount: u64,
+    /// Swap fee charged in token X by a single-sided withdrawal
+    pub fee_x: u64,
+    /// Swap fee charged in token Y by a single-sided withdrawal
+    pub fee_y: u64,
+    /// LP reserves of token X after the withdrawal
+    pub reserve_x: u64,
+    /// LP reserves of token Y after the withdrawal
+    pub reserve_y: u64,
+    /// LP token supply after the withdrawal
+    pub lp_supply: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a flash loan or flash swap is repaid
+#[event]
+#[derive(Clone, Debug)]
+pub struct FlashLoanEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Account that borrowed and repaid
+    pub user: Pubkey,
+    /// Amount of token X lent out
+    pub borrowed_x: u64,
+    /// Amount of token Y lent out
+    pub borrowed_y: u64,
+    /// Amount of token X sent back by the user, transfer fee included
+    pub repaid_x: u64,
+    /// Amount of token Y sent back by the user, transfer fee included
+    pub repaid_y: u64,
+    /// LP reserves of token X after the repayment
+    pub reserve_x: u64,
+    /// LP reserves of token Y after the repayment
+    pub reserve_y: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when the pool authority sweeps the accrued protocol fees
+#[event]
+#[derive(Clone, Debug)]
+pub struct CollectProtocolFeesEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Account the fees were sent to
+    pub treasury: Pubkey,
+    /// Protocol fees swept in token X
+    pub amount_x: u64,
+    /// Protocol fees swept in token Y
+    pub amount_y: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// A change the pool authority made to a pool, carried by `UpdatePoolEvent`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum PoolUpdate {
+    /// New trading fee, in basis points
+    Fee(u16),
+    /// New protocol share of the swap fee, in basis points
+    ProtocolFee(u16),
+    /// New dynamic fee settings, `None` once disabled
+    DynamicFee(Option<DynamicFee>),
+    /// New price guard, `None` once disabled
+    PriceGuard(Option<PriceGuard>),
+    /// Whether the pool now requires permissions
+    Permissioned(bool),
+    /// Whether the pool is now locked
+    Locked(bool),
+    /// New pool authority, `None` once renounced
+    Authority(Option<Pubkey>),
+}
+
+/// Emitted whenever the pool authority changes a pool's settings
+#[event]
+#[derive(Clone, Debug)]
+pub struct UpdatePoolEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Authority that made the change
+    pub authority: Pubkey,
+    /// What changed, with the new value
+    pub update: PoolUpdate,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when an empty pool is closed
+#[event]
+#[derive(Clone, Debug)]
+pub struct ClosePoolEvent {
+    /// Config account of the closed pool
+    pub pool: Pubkey,
+    /// Authority that closed the pool and received the rent
+    pub authority: Pubkey,
+    /// Token X the locked liquidity was worth, swept to the authority
+    pub swept_x: u64,
+    /// Token Y the locked liquidity was worth, swept to the authority
+    pub swept_y: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when the pool authority grants a user roles, or changes them
+#[event]
+#[derive(Clone, Debug)]
+pub struct SetPermissionEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// User the permission belongs to
+    pub user: Pubkey,
+    /// Whether the user may now deposit and withdraw liquidity
+    pub lp: bool,
+    /// Whether the user may now swap, take flash loans and place long-term orders
+    pub trader: bool,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when the pool authority revokes every role of a user
+#[event]
+#[derive(Clone, Debug)]
+pub struct RevokePermissionEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// User whose permission was closed
+    pub user: Pubkey,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a long-term order is placed
+#[event]
+#[derive(Clone, Debug)]
+pub struct PlaceOrderEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Owner of the order
+    pub owner: Pubkey,
+    /// The order account
+    pub order: Pubkey,
+    /// true if the order sells token X for Y, false if it sells Y for X
+    pub is_x: bool,
+    /// Tokens sold per second
+    pub sale_rate: u64,
+    /// Tokens the order sells over its lifetime, received by the vault
+    pub amount: u64,
+    /// Unix timestamp the order stops selling at
+    pub expiry_ts: i64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when the long-term orders of a pool are executed up to now
+/// Only emitted when the orders actually sold something
+#[event]
+#[derive(Clone, Debug)]
+pub struct ExecuteOrdersEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Unix timestamp the orders had been executed up to before
+    pub from_ts: i64,
+    /// Token X sold by the orders selling X
+    pub sold_x: u64,
+    /// Token Y sold by the orders selling Y
+    pub sold_y: u64,
+    /// Token X bought by the orders selling Y
+    pub bought_x: u64,
+    /// Token Y bought by the orders selling X
+    pub bought_y: u64,
+    /// Unix timestamp of the block, which the orders are now executed up to
+    pub timestamp: i64,
+}
+
+/// Emitted when an order owner withdraws what their order has bought so far
+#[event]
+#[derive(Clone, Debug)]
+pub struct WithdrawOrderProceedsEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Owner of the order
+    pub owner: Pubkey,
+    /// The order account
+    pub order: Pubkey,
+    /// Tokens bought by the order and paid out, before any transfer fee
+    pub proceeds: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when an order is closed, by its owner cancelling it or by anyone settling it after expiry
+#[event]
+#[derive(Clone, Debug)]
+pub struct CloseOrderEvent {
+    /// Config account of the pool
+    pub pool: Pubkey,
+    /// Owner of the order, who received the tokens
+    pub owner: Pubkey,
+    /// The closed order account
+    pub order: Pubkey,
+    /// Account that closed the order: the owner, or whoever settled it
+    pub closed_by: Pubkey,
+    /// Tokens bought by the order and paid out, before any transfer fee
+    pub proceeds: u64,
+    /// Tokens the order had not sold yet, refunded before any transfer fee
+    pub refund: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when LP tokens are staked in a farm
+#[event]
+#[derive(Clone, Debug)]
+pub struct StakeEvent {
+    /// The farm account
+    pub farm: Pubkey,
+    /// Account that staked
+    pub user: Pubkey,
+    /// LP tokens staked
+    pub amount: u64,
+    /// LP tokens staked in the farm after the stake
+    pub total_staked: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when LP tokens are unstaked from a farm
+#[event]
+#[derive(Clone, Debug)]
+pub struct UnstakeEvent {
+    /// The farm account
+    pub farm: Pubkey,
+    /// Account that unstaked
+    pub user: Pubkey,
+    /// LP tokens unstaked
+    pub amount: u64,
+    /// LP tokens staked in the farm after the unstake
+    pub total_staked: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a staker claims their farm rewards
+#[event]
+#[derive(Clone, Debug)]
+pub struct ClaimEvent {
+    /// The farm account
+    pub farm: Pubkey,
+    /// Account that claimed
+    pub user: Pubkey,
+    /// Rewards paid out, before any transfer fee
+    pub amount: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a farm authority extends a farm
+#[event]
+#[derive(Clone, Debug)]
+pub struct ExtendFarmEvent {
+    /// The farm account
+    pub farm: Pubkey,
+    /// New end of the farm
+    pub end_ts: i64,
+    /// New rewards emitted per second
+    pub reward_rate: u64,
+    /// Extra rewards added to the schedule, funded by the authority
+    pub amount: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a farm authority takes back the rewards no staker can claim
+#[event]
+#[derive(Clone, Debug)]
+pub struct RefundFarmEvent {
+    /// The farm account
+    pub farm: Pubkey,
+    /// Authority the rewards were refunded to
+    pub authority: Pubkey,
+    /// Rewards refunded, before any transfer fee
+    pub amount: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted on every swap against a concentrated liquidity pool
+#[event]
+#[derive(Clone, Debug)]
+pub struct ClSwapEvent {
+    /// The concentrated liquidity pool account
+    pub pool: Pubkey,
+    /// Account that performed the swap
+    pub user: Pubkey,
+    /// true if token X was sold for Y, false if Y was sold for X
+    pub is_x: bool,
+    /// Amount of the input token sent by the user, transfer fee included
+    pub amount_in: u64,
+    /// Amount of the output token sent by the pool, before any transfer fee
+    pub amount_out: u64,
+    /// Swap fee charged, in the input token
+    pub fee: u64,
+    /// Sqrt price after the swap (Q64.64)
+    pub sqrt_price: u128,
+    /// Current tick after the swap
+    pub tick: i32,
+    /// Active liquidity after the swap
+    pub liquidity: u128,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when liquidity is added to or removed from a concentrated liquidity position
+#[event]
+#[derive(Clone, Debug)]
+pub struct ClLiquidityEvent {
+    /// The concentrated liquidity pool account
+    pub pool: Pubkey,
+    /// Owner of the position
+    pub owner: Pubkey,
+    /// The position account
+    pub position: Pubkey,
+    /// Lower tick of the position's range
+    pub tick_lower: i32,
+    /// Upper tick of the position's range
+    pub tick_upper: i32,
+    /// Liquidity added (positive) or removed (negative)
+    pub liquidity_delta: i128,
+    /// Token X sent by the owner when adding, or by the pool when removing (transfer fee included)
+    pub amount_x: u64,
+    /// Token Y sent by the owner when adding, or by the pool when removing (transfer fee included)
+    pub amount_y: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
+
+/// Emitted when a concentrated liquidity position's fees are collected
+#[event]
+#[derive(Clone, Debug)]
+pub struct ClCollectFeesEvent {
+    /// The concentrated liquidity pool account
+    pub pool: Pubkey,
+    /// Owner of the position
+    pub owner: Pubkey,
+    /// The position account
+    pub position: Pubkey,
+    /// Token X fees paid out, before any transfer fee
+    pub amount_x: u64,
+    /// Token Y fees paid out, before any transfer fee
+    pub amount_y: u64,
+    /// Unix timestamp of the block
+    pub timestamp: i64,
+}
@@ -0,0 +1,331 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::{ClCollectFeesEvent, ClLiquidityEvent};
+use crate::states::{ClPool, ClPosition, TickBook};
+use crate::utils::{amount_with_transfer_fee, check_deadline, transfer_fee};
+
+/// Accounts required for managing the liquidity of a concentrated liquidity position
+/// Shared by `increase_cl_liquidity`, `decrease_cl_liquidity` and `collect_cl_fees`
+#[derive(Accounts)]
+pub struct ClLiquidity<'info> {
+    /// The position owner (must sign the transaction)
+    /// Mutable because they may need to pay for ATA creation if accounts don't exist
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The pool the position belongs to
+    /// Mutable because the active liquidity may change
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.seed.to_le_bytes().as_ref()],
+        bump = cl_pool.bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in the pool
+        has_one = mint_y,  // Ensures mint_y matches the one in the pool
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    /// The pool's initialized ticks
+    /// Mutable because the position's boundary ticks are updated
+    #[account(
+        mut,
+        seeds = [b"tick_book", cl_pool.key().as_ref()],
+        bump,
+    )]
+    pub tick_book: Account<'info, TickBook>,
+
+    /// The position being modified
+    #[account(
+        mut,
+        has_one = owner,    // Only the owner may modify the position
+        has_one = cl_pool,  // Position must belong to this pool
+    )]
+    pub position: Account<'info, ClPosition>,
+
+    /// The vault that holds all deposited token X
+    /// Associated token account owned by the pool PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Associated token account owned by the pool PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token X
+    /// Will be created if it doesn't exist, owner pays for creation
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x
+    )]
+    pub owner_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token Y
+    /// Will be created if it doesn't exist, owner pays for creation
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y
+    )]
+    pub owner_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClLiquidity<'info> {
+    /// Adds liquidity to the position's price range
+    ///
+    /// Below the range only token X is needed, above it only token Y, and inside
+    /// it both, in the ratio implied by the current price.
+    ///
+    /// For Token-2022 mints with a transfer fee, the amounts are grossed up so the
+    /// vaults receive what the liquidity requires; `max_x`/`max_y` bound what the owner sends.
+    ///
+    /// # Arguments
+    /// * `liquidity` - Liquidity to add
+    /// * `max_x` - Maximum amount of token X the owner is willing to deposit
+    /// * `max_y` - Maximum amount of token Y the owner is willing to deposit
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn increase_cl_liquidity(&mut self, liquidity: u128, max_x: u64, max_y: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the owner's deadline
+        check_deadline(expires_at)?;
+        require!(liquidity > 0, AmmError::InvalidAmount);
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+
+        let (x, y) = self.cl_pool.modify_position(&mut self.tick_book, &mut self.position, delta)?;
+
+        // Gross up for any transfer fee so the vaults receive the full amounts
+        let x = amount_with_transfer_fee(&self.mint_x, x)?;
+        let y = amount_with_transfer_fee(&self.mint_y, y)?;
+        require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+
+        if x != 0 {
+            self.deposit_token(true, x)?;
+        }
+        if y != 0 {
+            self.deposit_token(false, y)?;
+        }
+
+        emit!(ClLiquidityEvent {
+            pool: self.cl_pool.key(),
+            owner: self.owner.key(),
+            position: self.position.key(),
+            tick_lower: self.position.tick_lower,
+            tick_upper: self.position.tick_upper,
+            liquidity_delta: delta,
+            amount_x: x,
+            amount_y: y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Removes liquidity from the position's price range and pays out the tokens behind it
+    ///
+    /// Fees earned up to now are credited to the position and left for `collect_cl_fees`.
+    ///
+    /// # Arguments
+    /// * `liquidity` - Liquidity to remove
+    /// * `min_x` - Minimum amount of token X the owner must receive
+    /// * `min_y` - Minimum amount of token Y the owner must receive
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn decrease_cl_liquidity(&mut self, liquidity: u128, min_x: u64, min_y: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the owner's deadline
+        check_deadline(expires_at)?;
+        require!(liquidity > 0, AmmError::InvalidAmount);
+        require!(liquidity <= self.position.liquidity, AmmError::InsufficientBalance);
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+
+        let (x, y) = self.cl_pool.modify_position(&mut self.tick_book, &mut self.position, -delta)?;
+
+        // Slippage protection on what the owner actually receives after any transfer fee
+        let received_x = x - transfer_fee(&self.mint_x, x)?;
+        let received_y = y - transfer_fee(&self.mint_y, y)?;
+        require!(received_x >= min_x && received_y >= min_y, AmmError::SlippageExceeded);
+
+        if x != 0 {
+            self.withdraw_token(true, x)?;
+        }
+        if y != 0 {
+            self.withdraw_token(false, y)?;
+        }
+
+        emit!(ClLiquidityEvent {
+            pool: self.cl_pool.key(),
+            owner: self.owner.key(),
+            position: self.position.key(),
+            tick_lower: self.position.tick_lower,
+            tick_upper: self.position.tick_upper,
+            liquidity_delta: -delta,
+            amount_x: x,
+            amount_y: y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out all fees the position has earned so far
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn collect_cl_fees(&mut self) -> Result<()> {
+        // Credit fees earned since the last update without changing the liquidity
+        self.cl_pool.modify_position(&mut self.tick_book, &mut self.position, 0)?;
+
+        let (fees_x, fees_y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        require!(fees_x != 0 || fees_y != 0, AmmError::ZeroBalance);
+
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        if fees_x != 0 {
+            self.withdraw_token(true, fees_x)?;
+        }
+        if fees_y != 0 {
+            self.withdraw_token(false, fees_y)?;
+        }
+
+        emit!(ClCollectFeesEvent {
+            pool: self.cl_pool.key(),
+            owner: self.owner.key(),
+            position: self.position.key(),
+            amount_x: fees_x,
+            amount_y: fees_y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposits tokens from the owner's account to the appropriate vault
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to deposit
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.owner_ata_x.to_account_info(),      // Transfer from owner's X account
+                self.vault_x.to_account_info(),          // Transfer to vault X
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.owner_ata_y.to_account_info(),      // Transfer from owner's Y account
+                self.vault_y.to_account_info(),          // Transfer to vault Y
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.owner.to_account_info(),  // Owner signs the transfer
+        };
+
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+
+    /// Withdraws tokens from the appropriate vault to the owner's account
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to withdraw
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),          // Transfer from vault X
+                self.owner_ata_x.to_account_info(),      // Transfer to owner's X account
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.vault_y.to_account_info(),          // Transfer from vault Y
+                self.owner_ata_y.to_account_info(),      // Transfer to owner's Y account
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.cl_pool.to_account_info(),  // Pool PDA signs the transfer
+        };
+
+        // Create signer seeds for the pool PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"cl_pool",
+            &self.cl_pool.seed.to_le_bytes(),
+            &[self.cl_pool.bump],
+        ]];
+
+        // Create CPI context with PDA signer
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::ClSwapEvent;
+use crate::states::{ClPool, TickBook};
+use crate::utils::{amount_with_transfer_fee, check_deadline, transfer_fee};
+
+/// Accounts required for swapping against a concentrated liquidity pool
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    /// The user who is performing the swap (must sign the transaction)
+    /// Mutable because they may need to pay for ATA creation if accounts don't exist
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The pool being traded against
+    /// Mutable because the price, active liquidity and fee growth change
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.seed.to_le_bytes().as_ref()],
+        bump = cl_pool.bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in the pool
+        has_one = mint_y,  // Ensures mint_y matches the one in the pool
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    /// The pool's initialized ticks
+    /// Mutable because crossed ticks flip their fee growth
+    #[account(
+        mut,
+        seeds = [b"tick_book", cl_pool.key().as_ref()],
+        bump,
+    )]
+    pub tick_book: Account<'info, TickBook>,
+
+    /// The vault that holds all deposited token X
+    /// Associated token account owned by the pool PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Associated token account owned by the pool PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for token X
+    /// Will be created if it doesn't exist, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x
+    )]
+    pub user_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for token Y
+    /// Will be created if it doesn't exist, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y
+    )]
+    pub user_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClSwap<'info> {
+    /// Exact-input swap against a concentrated liquidity pool
+    ///
+    /// The trade walks the price through the pool's ranges, crossing initialized
+    /// ticks as needed, until the input is used up or `sqrt_price_limit` is hit.
+    /// Input that could not be swapped before the limit is never taken from the user.
+    ///
+    /// For Token-2022 mints with a transfer fee, the trade is priced on the amount the
+    /// vault actually receives, and `min_amount_out` is checked against the amount the
+    /// user actually receives.
+    ///
+    /// # Arguments
+    /// * `is_x` - true if swapping token X for Y, false if swapping Y for X
+    /// * `amount_in` - Amount of input tokens to swap
+    /// * `min_amount_out` - Minimum amount of output tokens expected (slippage protection)
+    /// * `sqrt_price_limit` - Sqrt price (Q64.64) the swap may not move past, 0 for no limit
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn cl_swap(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit: u128,
+        expires_at: i64,
+    ) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        // Determine which token is being swapped in
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),   // Swapping X for Y
+            false => (&self.mint_y, &self.mint_x),  // Swapping Y for X
+        };
+
+        // Price the trade on what the vault actually receives after any transfer fee
+        let amount_received = amount_in
+            .checked_sub(transfer_fee(mint_in, amount_in)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let result = self
+            .cl_pool
+            .swap(&mut self.tick_book, is_x, amount_received, sqrt_price_limit)?;
+        require!(result.amount_out != 0, AmmError::InvalidAmount);
+
+        // Slippage protection on what the user actually receives after any transfer fee
+        let amount_out = result.amount_out - transfer_fee(mint_out, result.amount_out)?;
+        require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+
+        // Only charge the user for the input actually swapped when the price limit was hit
+        let amount_in = match result.amount_in == amount_received {
+            true => amount_in,
+            false => amount_with_transfer_fee(mint_in, result.amount_in)?,
+        };
+
+        self.deposit_token(is_x, amount_in)?;
+        self.withdraw_token(!is_x, result.amount_out)?;
+
+        emit!(ClSwapEvent {
+            pool: self.cl_pool.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            sqrt_price: self.cl_pool.sqrt_price,
+            tick: self.cl_pool.tick_current,
+            liquidity: self.cl_pool.liquidity,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposits tokens from user's account to the appropriate vault
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to deposit
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.user_ata_x.to_account_info(),       // Transfer from user's X account
+                self.vault_x.to_account_info(),          // Transfer to vault X
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.user_ata_y.to_account_info(),       // Transfer from user's Y account
+                self.vault_y.to_account_info(),          // Transfer to vault Y
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.user.to_account_info(),  // User signs the transfer
+        };
+
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+
+    /// Withdraws tokens from vault to user's account
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to withdraw
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),          // Transfer from vault X
+                self.user_ata_x.to_account_info(),       // Transfer to user's X account
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.vault_y.to_account_info(),          // Transfer from vault Y
+                self.user_ata_y.to_account_info(),       // Transfer to user's Y account
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.cl_pool.to_account_info(),  // Pool PDA signs the transfer
+        };
+
+        // Create signer seeds for the pool PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"cl_pool",
+            &self.cl_pool.seed.to_le_bytes(),
+            &[self.cl_pool.bump],
+        ]];
+
+        // Create CPI context with PDA signer
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::states::{ClPool, ClPosition};
+
+/// Accounts required for closing an empty concentrated liquidity position
+#[derive(Accounts)]
+pub struct CloseClPosition<'info> {
+    /// The position owner (must sign the transaction)
+    /// Mutable because the position's rent is refunded to them
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The pool the position belongs to
+    #[account(
+        seeds = [b"cl_pool", cl_pool.seed.to_le_bytes().as_ref()],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    /// The position to close, rent goes back to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,    // Only the owner may close the position
+        has_one = cl_pool,  // Position must belong to this pool
+    )]
+    pub position: Account<'info, ClPosition>,
+}
+
+impl<'info> CloseClPosition<'info> {
+    /// Closes a position that holds no liquidity and no uncollected fees
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn close_cl_position(&mut self) -> Result<()> {
+        require!(
+            self.position.liquidity == 0
+                && self.position.fees_owed_x == 0
+                && self.position.fees_owed_y == 0,
+            AmmError::PositionNotEmpty
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::instruction::AuthorityType,
+    token_interface::{
+        burn, close_account, set_authority, transfer_checked, Burn, CloseAccount, Mint,
+        SetAuthority, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use constant_product_curve::ConstantProduct;
+
+use crate::errors::AmmError;
+use crate::events::ClosePoolEvent;
+use crate::states::{Config, PoolEntry, Registry};
+
+/// Accounts required for closing an empty AMM pool and reclaiming its rent
+/// The config, the canonical pool entry, the locked LP account and both vaults are
+/// closed, and the pool is removed from the registry
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// The pool authority (must sign the transaction)
+    /// Mutable because the rent of every closed account is refunded to it
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The global registry listing every pool
+    /// Shrunk by one entry, the freed rent goes to the authority
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+        realloc = Registry::space(registry.pools.len().saturating_sub(1)),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    /// The canonical entry of the pool
+    /// Closed, so a new pool can be created for the same pair and fee tier
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pool", mint_x.key().as_ref(), mint_y.key().as_ref(), pool.fee_tier.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = config,  // Ensures the entry points at this pool
+    )]
+    pub pool: Account<'info, PoolEntry>,
+
+    /// The AMM pool configuration account
+    /// Closed at the end of the instruction, rent refunded to the authority
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The LP (Liquidity Provider) token mint
+    /// Mutable because the locked LP tokens are burned and its mint authority revoked
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// LP token account holding the locked minimum liquidity
+    /// Its tokens are burned and the account closed
+    #[account(
+        mut,
+        seeds = [b"locked_lp", config.key().as_ref()],
+        bump,
+    )]
+    pub locked_lp: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault for token X, swept and closed
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault for token Y, swept and closed
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Authority's token account for token X, receiving the dust left in the vault
+    /// Will be created if it doesn't exist, authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program_x
+    )]
+    pub authority_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// Authority's token account for token Y, receiving the dust left in the vault
+    /// Will be created if it doesn't exist, authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program_y
+    )]
+    pub authority_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for the registry realloc and account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClosePool<'info> {
+    /// Closes an empty pool and refunds the rent of its accounts to the authority
+    ///
+    /// Every LP must have withdrawn: the only LP tokens left may be the locked
+    /// `MINIMUM_LIQUIDITY`. Protocol fees must have been collected and no long-term
+    /// orders may be left to close, so the vaults hold nothing but the reserves
+    /// backing the locked liquidity.
+    ///
+    /// Those reserves belong to nobody, so the authority receives what the locked LP
+    /// tokens would withdraw and the tokens are burned. This is usually dust, but it
+    /// includes the swap fees the locked liquidity earned and any tokens sent to the
+    /// vaults directly. The sweep is capped at that amount: if the vaults hold
+    /// anything else, the pool is not closed.
+    ///
+    /// The LP mint cannot be closed; its mint authority is revoked instead, so no
+    /// LP tokens of the pool can ever be minted again.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn close_pool(&mut self) -> Result<()> {
+        // Only the pool authority may close the pool
+        self.config.check_authority(&self.authority.key())?;
+        // Vault balances are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Nothing may be left that belongs to LPs, the protocol or order owners
+        require!(
+            self.mint_lp.supply == self.locked_lp.amount
+                && self.config.protocol_fees_x == 0
+                && self.config.protocol_fees_y == 0
+                && self.config.twamm.expiries.is_empty(),
+            AmmError::PoolNotEmpty
+        );
+
+        // Sweep no more than the locked LP tokens are worth; in a pool that was never
+        // seeded, whatever was sent to the vaults backs no LP tokens at all
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let (swept_x, swept_y) = match self.mint_lp.supply {
+            0 => (reserve_x, reserve_y),
+            supply => {
+                let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
+                    reserve_x,
+                    reserve_y,
+                    supply,
+                    self.locked_lp.amount,
+                    6,
+                )
+                .map_err(AmmError::from)?;
+                (amounts.x, amounts.y)
+            }
+        };
+        require!(
+            swept_x == self.vault_x.amount && swept_y == self.vault_y.amount,
+            AmmError::PoolNotEmpty
+        );
+
+        // Drop the pool from the registry; its space was already released above
+        let config = self.config.key();
+        self.registry.pools.retain(|pool| *pool != config);
+
+        // Create signer seeds for config PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"config",
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ]];
+
+        // Burn the locked minimum liquidity and reclaim its account
+        if self.locked_lp.amount != 0 {
+            let cpi_accounts = Burn {
+                mint: self.mint_lp.to_account_info(),
+                from: self.locked_lp.to_account_info(),
+                authority: self.config.to_account_info(),  // Config PDA owns the locked LP
+            };
+            let cpi_context = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            burn(cpi_context, self.locked_lp.amount)?;
+        }
+        let cpi_accounts = CloseAccount {
+            account: self.locked_lp.to_account_info(),
+            destination: self.authority.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_context)?;
+
+        // Freeze the LP supply at zero for good
+        let cpi_accounts = SetAuthority {
+            current_authority: self.config.to_account_info(),
+            account_or_mint: self.mint_lp.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        set_authority(cpi_context, AuthorityType::MintTokens, None)?;
+
+        emit!(ClosePoolEvent {
+            pool: config,
+            authority: self.authority.key(),
+            swept_x,
+            swept_y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        self.close_vault(true, swept_x, signer_seeds)?;
+        self.close_vault(false, swept_y, signer_seeds)
+    }
+
+    /// Sweeps what is left in a pool vault to the authority, then closes the vault,
+    /// refunding its rent to the authority
+    ///
+    /// # Arguments
+    /// * `is_x` - true for the token X vault, false for the token Y vault
+    /// * `amount` - Balance of the vault, the reserves behind the locked liquidity
+    /// * `signer_seeds` - Seeds of the config PDA, the vault's owner
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn close_vault(&mut self, is_x: bool, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let (account, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.authority_ata_x.to_account_info(),
+                self.mint_x.to_account_info(),
+                self.mint_x.decimals,
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.authority_ata_y.to_account_info(),
+                self.mint_y.to_account_info(),
+                self.mint_y.decimals,
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        // Token accounts can only be closed once empty
+        if amount != 0 {
+            let cpi_accounts = TransferChecked {
+                from: account.clone(),
+                to,
+                mint,
+                authority: self.config.to_account_info(),  // Config PDA owns the vault
+            };
+            let cpi_context = CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer_seeds);
+            transfer_checked(cpi_context, amount, decimals)?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account,
+            destination: self.authority.to_account_info(),
+            authority: self.config.to_account_info(),  // Config PDA owns the vault
+        };
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        close_account(cpi_context)
+    }
+}
@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::CollectProtocolFeesEvent;
+use crate::states::Config;
+
+/// Accounts required for sweeping accrued protocol fees out of the pool
+/// This struct defines all the accounts needed to move the protocol's share
+/// of swap fees from the pool vaults to the treasury
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    /// The pool authority (must sign the transaction)
+    /// Mutable because it pays for the treasury ATAs if they don't exist yet
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Owner of the treasury token accounts, only used as an ATA authority
+    pub treasury: UncheckedAccount<'info>,
+
+    /// The mint account for token X in the trading pair
+    /// Immutable as we only need to read mint information for transfers
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    /// Immutable as we only need to read mint information for transfers
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Mutable because the accrued protocol fee balances are reset
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The vault that holds all deposited token X
+    /// Mutable because accrued token X protocol fees are transferred out of it
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Mutable because accrued token Y protocol fees are transferred out of it
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Treasury's token account for token X
+    /// Will be created if it doesn't exist, authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program_x
+    )]
+    pub treasury_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// Treasury's token account for token Y
+    /// Will be created if it doesn't exist, authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program_y
+    )]
+    pub treasury_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    /// Transfers all accrued protocol fees from the vaults to the treasury
+    ///
+    /// LP reserves are untouched: only the amounts tracked in
+    /// `config.protocol_fees_x` and `config.protocol_fees_y` are swept.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn collect_protocol_fees(&mut self) -> Result<()> {
+        // Only the pool authority may sweep protocol fees
+        self.config.check_authority(&self.authority.key())?;
+        // Vault balances are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+
+        let (fees_x, fees_y) = (self.config.protocol_fees_x, self.config.protocol_fees_y);
+        // Nothing to do if no protocol fees have accrued since the last sweep
+        require!(fees_x != 0 || fees_y != 0, AmmError::ZeroBalance);
+
+        // Reset the accrued balances before moving the tokens out
+        self.config.protocol_fees_x = 0;
+        self.config.protocol_fees_y = 0;
+
+        // Transfer whichever sides have accrued fees
+        if fees_x != 0 {
+            self.transfer_to_treasury(true, fees_x)?;
+        }
+        if fees_y != 0 {
+            self.transfer_to_treasury(false, fees_y)?;
+        }
+
+        emit!(CollectProtocolFeesEvent {
+            pool: self.config.key(),
+            treasury: self.treasury.key(),
+            amount_x: fees_x,
+            amount_y: fees_y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Transfers tokens from a pool vault to the matching treasury account
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to transfer
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn transfer_to_treasury(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),          // Transfer from vault X
+                self.treasury_ata_x.to_account_info(),   // Transfer to treasury's X account
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.vault_y.to_account_info(),          // Transfer from vault Y
+                self.treasury_ata_y.to_account_info(),   // Transfer to treasury's Y account
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.config.to_account_info(),  // Config PDA signs the transfer
+        };
+
+        // Create signer seeds for config PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"config",
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ]];
+
+        // Create CPI context with PDA signer
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::states::{Config, Farm};
+use crate::utils::{amount_with_transfer_fee, check_mint_extensions};
+
+/// Accounts required for creating a farm that rewards a pool's staked LP tokens
+/// Only the pool authority may create farms, and funds the whole schedule up front
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateFarm<'info> {
+    /// The pool authority (must sign the transaction)
+    /// Mutable because they pay for account creation and fund the rewards
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The AMM pool configuration account
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The pool's LP token mint, the token being staked
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::token_program = token_program,
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token paid out as rewards
+    /// May belong to either SPL Token or Token-2022
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// The new farm account
+    /// PDA derived from the pool and a seed chosen by the authority
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"farm", config.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Farm::INIT_SPACE
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// The vault holding the staked LP tokens
+    /// Associated token account owned by the farm PDA
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault holding the rewards still to be paid out
+    /// Associated token account owned by the farm PDA
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Authority's token account for the reward token, the rewards are funded from it
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub authority_reward_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program owning the reward mint (SPL Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateFarm<'info> {
+    /// Creates a farm paying `reward_rate` reward tokens per second to the pool's stakers
+    /// over `[start_ts, end_ts)`, and funds the whole schedule from the authority
+    ///
+    /// For Token-2022 reward mints with a transfer fee, the authority is charged the gross
+    /// amount needed for the reward vault to actually receive the whole schedule.
+    ///
+    /// Farms are restricted to the pool authority so that only the pool's owner can run
+    /// incentives in its name. Pools without an authority, because it was never set or
+    /// was renounced, cannot get new farms; farms they already have keep running.
+    ///
+    /// # Arguments
+    /// * `seed` - Unique identifier of the farm among the pool's farms
+    /// * `reward_rate` - Rewards emitted per second
+    /// * `start_ts` - Unix timestamp rewards start accruing at, not in the past
+    /// * `end_ts` - Unix timestamp rewards stop accruing at
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn create_farm(
+        &mut self,
+        seed: u64,
+        reward_rate: u64,
+        start_ts: i64,
+        end_ts: i64,
+        bumps: &CreateFarmBumps,
+    ) -> Result<()> {
+        // Only the pool authority may attach incentives to its LP token
+        self.config.check_authority(&self.authority.key())?;
+        // Reject Token-2022 mints whose extensions could move or freeze the rewards
+        check_mint_extensions(&self.reward_mint)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            reward_rate > 0 && start_ts >= now && end_ts > start_ts,
+            AmmError::InvalidRewardSchedule
+        );
+
+        self.farm.set_inner(Farm {
+            seed,
+            config: self.config.key(),
+            authority: self.authority.key(),
+            mint_lp: self.mint_lp.key(),
+            reward_mint: self.reward_mint.key(),
+            reward_rate,
+            start_ts,
+            end_ts,
+            total_staked: 0,
+            reward_per_share: 0,
+            // Rewards accrue from the start, not from creation
+            last_update_ts: start_ts,
+            rewards_accrued: 0,
+            rewards_claimed: 0,
+            bump: bumps.farm,
+        });
+
+        // Fund the whole schedule up front, so claims can never run dry
+        let rewards = self.farm.rewards_between(start_ts, end_ts)?;
+        let amount = amount_with_transfer_fee(&self.reward_mint, rewards)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.authority_reward_ata.to_account_info(),
+            to: self.reward_vault.to_account_info(),
+            mint: self.reward_mint.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.reward_token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_context, amount, self.reward_mint.decimals)
+    }
+}
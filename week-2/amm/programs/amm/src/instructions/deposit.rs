@@ -1,12 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{mint_to, transfer_checked, Mint, MintTo, Token, TokenAccount, TransferChecked},
+    token_interface::{mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked},
 };
 use constant_product_curve::ConstantProduct;
 
-use crate::states::Config;
+use crate::constants::MINIMUM_LIQUIDITY;
+use crate::curves::{initial_liquidity, single_sided_deposit};
+use crate::states::{Config, LpPosition, Permission, Role};
 use crate::errors::AmmError;
+use crate::events::DepositEvent;
+use crate::utils::{amount_with_transfer_fee, check_deadline, transfer_fee};
 
 /// Accounts required for depositing liquidity into the AMM pool
 /// This struct defines all the accounts needed to perform a liquidity deposit operation
@@ -18,16 +22,20 @@ pub struct Deposit<'info> {
 
     /// The mint account for token X in the trading pair
     /// This is immutable as we only need to read mint information
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
 
     /// The mint account for token Y in the trading pair
     /// This is immutable as we only need to read mint information
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
 
     /// The AMM pool configuration account
     /// Contains pool settings, fees, and references to the token mints
+    /// Mutable because the TWAP price accumulators are updated on every deposit
     /// Uses PDA derived from "config" seed and config.seed
     #[account(
+        mut,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,     
         has_one = mint_x,  // Ensures mint_x matches the one in config
@@ -43,7 +51,7 @@ pub struct Deposit<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: InterfaceAccount<'info, Mint>,
 
     /// The vault that holds all deposited token X
     /// Mutable because we're depositing tokens into it
@@ -52,9 +60,9 @@ pub struct Deposit<'info> {
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
 
     /// The vault that holds all deposited token Y
     /// Mutable because we're depositing tokens into it
@@ -63,29 +71,33 @@ pub struct Deposit<'info> {
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
     
     /// User's token account for token X
+    /// Will be created if it doesn't exist, user pays for creation
     /// Mutable because we're transferring tokens from it
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_ata_x: Account<'info, TokenAccount>,
+    pub user_ata_x: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for token Y
+    /// Will be created if it doesn't exist, user pays for creation
     /// Mutable because we're transferring tokens from it
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
     )]
-    pub user_ata_y: Account<'info, TokenAccount>,
+    pub user_ata_y: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for LP tokens
     /// Will be created if it doesn't exist, user pays for creation
@@ -97,10 +109,48 @@ pub struct Deposit<'info> {
         associated_token::authority = user,
         associated_token::token_program = token_program
     )]
-    pub user_ata_lp: Account<'info, TokenAccount>,
+    pub user_ata_lp: InterfaceAccount<'info, TokenAccount>,
+
+    /// LP token account holding the permanently locked minimum liquidity
+    /// Mutable because `MINIMUM_LIQUIDITY` is minted to it on the first deposit
+    /// PDA derived from "locked_lp" seed and config pubkey
+    #[account(
+        mut,
+        seeds = [b"locked_lp", config.key().as_ref()],
+        bump,
+    )]
+    pub locked_lp: InterfaceAccount<'info, TokenAccount>,
 
-    /// SPL Token program for token operations
-    pub token_program: Program<'info, Token>,
+    /// The user's permission, required with the LP role if the pool is permissioned
+    /// Pass `None` for pools open to everyone
+    #[account(
+        seeds = [b"permission", config.key().as_ref(), user.key().as_ref()],
+        bump = permission.bump,
+    )]
+    pub permission: Option<Account<'info, Permission>>,
+
+    /// The user's liquidity position, tracking what the deposit cost for fee accounting
+    /// Created on the first deposit that passes it, user pays for creation
+    /// Pass `None` to deposit without tracking a position
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + LpPosition::INIT_SPACE,
+        seeds = [b"lp_position", config.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub lp_position: Option<Account<'info, LpPosition>>,
+
+    /// CHECK: Oracle price update of a pool with a price guard, `None` otherwise
+    /// Only read by `deposit_single`, whose internal swap moves the pool price
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
     /// Associated Token program for ATA operations
     pub associated_token_program: Program<'info, AssociatedToken>,
     /// System program for account creation
@@ -113,15 +163,23 @@ impl<'info> Deposit<'info> {
     /// For the first deposit (pool initialization):
     /// - Uses exact amounts provided by user (max_x, max_y)
     /// - Establishes the initial price ratio
+    /// - Mints `sqrt(x * y)` LP tokens, of which `MINIMUM_LIQUIDITY` are locked
+    ///   and the rest go to the user, who must receive at least `amount`
     /// 
     /// For subsequent deposits:
     /// - Calculates proportional amounts based on current pool ratio
     /// - Maintains constant product invariant
     /// 
+    /// For Token-2022 mints with a transfer fee, the user is charged the gross amount
+    /// needed for the vault to actually receive the proportional amounts, and
+    /// `max_x`/`max_y` are checked against that gross amount.
+    /// 
     /// # Arguments
-    /// * `amount` - Amount of LP tokens to mint to the user
+    /// * `amount` - Amount of LP tokens to mint to the user (minimum to receive on the first deposit)
     /// * `max_x` - Maximum amount of token X user is willing to deposit
     /// * `max_y` - Maximum amount of token Y user is willing to deposit
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    /// * `bumps` - PDA bumps calculated by Anchor
     /// 
     /// # Returns
     /// * `Result<()>` - Ok if successful, error otherwise
@@ -130,29 +188,50 @@ impl<'info> Deposit<'info> {
         amount: u64, 
         max_x: u64,
         max_y: u64,
+        expires_at: i64,
+        bumps: &DepositBumps,
     ) -> Result<()> { 
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
         // Ensure the pool is not locked for deposits
         require!(self.config.locked == false, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the LP role
+        self.config.check_permission(self.permission.as_deref(), Role::Lp)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
         // Ensure user is requesting to mint some LP tokens
         require!(amount != 0, AmmError::InvalidAmount);
 
-        // Calculate required token amounts based on whether this is first deposit
-        let (x, y) = match self.mint_lp.supply == 0 && self.vault_x.amount == 0 && self.vault_y.amount == 0 {
-            // First deposit: use exact amounts provided by user
-            // This establishes the initial price ratio for the pool
-            true => (max_x, max_y), 
-            // Subsequent deposits: calculate proportional amounts to maintain pool ratio
-            false => {
-                let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount, 
-                    self.vault_y.amount, 
-                    self.mint_lp.supply, 
-                    amount, 
-                    6  // Precision for calculations
-                ).unwrap();
-                (amounts.x, amounts.y)
-            }
-        };
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Only LP-owned reserves count; accrued protocol fees are not claimable by LPs
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-deposit price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+
+        // First deposit: use exact amounts provided by user
+        // This establishes the initial price ratio for the pool
+        if self.mint_lp.supply == 0 {
+            return self.deposit_initial(amount, max_x, max_y, bumps);
+        }
+
+        // Subsequent deposits: calculate proportional amounts to maintain pool ratio
+        let amounts = ConstantProduct::xy_deposit_amounts_from_l(
+            reserve_x, 
+            reserve_y, 
+            self.mint_lp.supply, 
+            amount, 
+            6  // Precision for calculations
+        ).unwrap();
+
+        // Gross up so the vaults receive the full amounts after any transfer fee
+        let x = amount_with_transfer_fee(&self.mint_x, amounts.x)?;
+        let y = amount_with_transfer_fee(&self.mint_y, amounts.y)?;
 
         // Slippage protection: ensure calculated amounts don't exceed user's maximum
         require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded );
@@ -163,7 +242,270 @@ impl<'info> Deposit<'info> {
         self.deposit_tokens(false, y)?;
 
         // Mint LP tokens to user as proof of liquidity provision
-        self.mint_lp_tokens(amount)
+        let user_ata_lp = self.user_ata_lp.to_account_info();
+        self.mint_lp_tokens(user_ata_lp, amount)?;
+
+        self.emit_deposit_event(x, y, amount, 0, 0)?;
+        self.record_position(x, y, amount, bumps)
+    }
+
+    /// First deposit into the pool, Uniswap v2 style
+    ///
+    /// The user sends exactly `max_x` and `max_y`, which sets the initial price.
+    /// The LP supply is seeded with `sqrt(x * y)` of the amounts the vaults receive;
+    /// `MINIMUM_LIQUIDITY` of it is minted to the locked LP account and the rest to
+    /// the user. Because the locked tokens are only burned by `close_pool`, the supply
+    /// never returns to zero and a dust first deposit cannot make one LP token so
+    /// expensive that later deposits round down to nothing.
+    ///
+    /// # Arguments
+    /// * `min_lp` - Minimum amount of LP tokens the user expects to receive
+    /// * `amount_x` - Amount of token X to deposit
+    /// * `amount_y` - Amount of token Y to deposit
+    /// * `bumps` - PDA bumps calculated by Anchor
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn deposit_initial(
+        &mut self,
+        min_lp: u64,
+        amount_x: u64,
+        amount_y: u64,
+        bumps: &DepositBumps,
+    ) -> Result<()> {
+        // Seed the LP supply from what the vaults actually receive after any transfer fee
+        let received_x = amount_x
+            .checked_sub(transfer_fee(&self.mint_x, amount_x)?)
+            .ok_or(AmmError::Underflow)?;
+        let received_y = amount_y
+            .checked_sub(transfer_fee(&self.mint_y, amount_y)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let liquidity = initial_liquidity(received_x, received_y);
+        require!(liquidity > MINIMUM_LIQUIDITY, AmmError::LiquidityLessThanMinimum);
+
+        // The user gets everything except the permanently locked minimum
+        let lp_amount = liquidity - MINIMUM_LIQUIDITY;
+        require!(lp_amount >= min_lp, AmmError::SlippageExceeded);
+
+        // Transfer both tokens from user to vaults
+        self.deposit_tokens(true, amount_x)?;
+        self.deposit_tokens(false, amount_y)?;
+
+        // Lock the minimum liquidity, then mint the rest to the user
+        let locked_lp = self.locked_lp.to_account_info();
+        self.mint_lp_tokens(locked_lp, MINIMUM_LIQUIDITY)?;
+        let user_ata_lp = self.user_ata_lp.to_account_info();
+        self.mint_lp_tokens(user_ata_lp, lp_amount)?;
+
+        self.emit_deposit_event(amount_x, amount_y, lp_amount, 0, 0)?;
+        self.record_position(amount_x, amount_y, lp_amount, bumps)
+    }
+
+    /// Zap deposit: provides liquidity with a single token
+    ///
+    /// The pool's curve prices the deposit as if the optimal part of `amount_in`
+    /// were swapped into the other token and both legs deposited in the pool ratio.
+    /// No tokens leave the vaults: the swapped part simply stays in the input vault.
+    /// The internal swap pays the regular swap fee, including the protocol's share.
+    ///
+    /// Slippage is checked once, on the LP tokens minted for the whole operation.
+    ///
+    /// # Arguments
+    /// * `is_x` - true if depositing token X, false for token Y
+    /// * `amount_in` - Amount of the token to deposit
+    /// * `min_lp_out` - Minimum amount of LP tokens the user expects to receive
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    /// * `bumps` - PDA bumps calculated by Anchor
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+        expires_at: i64,
+        bumps: &DepositBumps,
+    ) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
+        // Ensure the pool is not locked for deposits
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the LP role
+        self.config.check_permission(self.permission.as_deref(), Role::Lp)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Ensure user is depositing a positive amount
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Only LP-owned reserves count; accrued protocol fees are not claimable by LPs
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-deposit price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+        // The internal swap is charged the current dynamic fee
+        self.config.decay_volatility()?;
+        // Weighted pools price with the weights in effect now
+        self.config.update_weights()?;
+
+        // Price the deposit on what the vault actually receives after any transfer fee
+        let mint_in = match is_x {
+            true => &self.mint_x,
+            false => &self.mint_y,
+        };
+        let amount_received = amount_in
+            .checked_sub(transfer_fee(mint_in, amount_in)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let quote = single_sided_deposit(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            amount_received,
+        )?;
+
+        // Slippage protection for the whole zap
+        require!(quote.lp_amount != 0, AmmError::InvalidAmount);
+        require!(quote.lp_amount >= min_lp_out, AmmError::SlippageExceeded);
+
+        // Set aside the protocol's share of the internal swap fee
+        self.config.accrue_protocol_fee(true, quote.fee_x)?;
+        self.config.accrue_protocol_fee(false, quote.fee_y)?;
+
+        // Reserves once the zap lands, for the dynamic fee and the price guard
+        let (vault_x, vault_y) = match is_x {
+            true => (self.vault_x.amount.checked_add(amount_received), Some(self.vault_y.amount)),
+            false => (Some(self.vault_x.amount), self.vault_y.amount.checked_add(amount_received)),
+        };
+        let reserves_after = self.config.lp_reserves(
+            vault_x.ok_or(AmmError::Overflow)?,
+            vault_y.ok_or(AmmError::Overflow)?,
+        )?;
+
+        // Feed the price move of the internal swap into the dynamic fee
+        self.config.record_price_move((reserve_x, reserve_y), reserves_after)?;
+
+        // Guarded pools must not be pushed away from the oracle price by the internal swap
+        self.config.check_price_guard(
+            self.oracle.as_deref(),
+            self.mint_x.decimals,
+            self.mint_y.decimals,
+            (reserve_x, reserve_y),
+            reserves_after,
+        )?;
+
+        // Transfer the single token from user to vault
+        self.deposit_tokens(is_x, amount_in)?;
+
+        // Mint LP tokens to user as proof of liquidity provision
+        let user_ata_lp = self.user_ata_lp.to_account_info();
+        self.mint_lp_tokens(user_ata_lp, quote.lp_amount)?;
+
+        let (amount_x, amount_y) = match is_x {
+            true => (amount_in, 0),
+            false => (0, amount_in),
+        };
+        self.emit_deposit_event(amount_x, amount_y, quote.lp_amount, quote.fee_x, quote.fee_y)?;
+        self.record_position(amount_x, amount_y, quote.lp_amount, bumps)
+    }
+
+    /// Records the deposit in the user's liquidity position, if one was passed
+    ///
+    /// Fills in a newly created position, then adds the deposited amounts and the
+    /// LP tokens minted, snapshotting the pool's invariant per LP token after the
+    /// deposit. Must run after `emit_deposit_event`, which reloads the vaults and
+    /// the LP mint.
+    ///
+    /// # Arguments
+    /// * `amount_x` - Amount of token X the user sent, transfer fee included
+    /// * `amount_y` - Amount of token Y the user sent, transfer fee included
+    /// * `lp_amount` - LP tokens minted to the user
+    /// * `bumps` - PDA bumps calculated by Anchor
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn record_position(
+        &mut self,
+        amount_x: u64,
+        amount_y: u64,
+        lp_amount: u64,
+        bumps: &DepositBumps,
+    ) -> Result<()> {
+        let (Some(lp_position), Some(bump)) = (self.lp_position.as_mut(), bumps.lp_position) else {
+            return Ok(());
+        };
+
+        // A freshly created position is all zeroes
+        if lp_position.owner == Pubkey::default() {
+            lp_position.config = self.config.key();
+            lp_position.owner = self.user.key();
+            lp_position.bump = bump;
+        }
+
+        let reserves = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        lp_position.record_deposit(
+            &self.config,
+            reserves,
+            self.mint_lp.supply,
+            (amount_x, amount_y),
+            lp_amount,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Emits a `DepositEvent` describing the deposit and the pool state after it
+    ///
+    /// # Arguments
+    /// * `amount_x` - Amount of token X the user sent, transfer fee included
+    /// * `amount_y` - Amount of token Y the user sent, transfer fee included
+    /// * `lp_amount` - LP tokens minted to the user
+    /// * `fee_x` - Swap fee charged in token X
+    /// * `fee_y` - Swap fee charged in token Y
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn emit_deposit_event(
+        &mut self,
+        amount_x: u64,
+        amount_y: u64,
+        lp_amount: u64,
+        fee_x: u64,
+        fee_y: u64,
+    ) -> Result<()> {
+        // Vault balances and LP supply are stale after the transfers and mint
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        emit!(DepositEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            fee_x,
+            fee_y,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
     }
 
     /// Transfers tokens from user's account to the appropriate vault
@@ -180,24 +522,25 @@ impl<'info> Deposit<'info> {
             from,      // User's token account
             to,        // Vault token account
             mint,      // Token mint
-            decimals   // Token decimal places
+            decimals,  // Token decimal places
+            cpi_program, // Token program owning the mint
         ) = match is_x {
             true => (
                 self.user_ata_x.to_account_info(), 
                 self.vault_x.to_account_info(),
                 self.mint_x.to_account_info(),
-                self.mint_x.decimals
+                self.mint_x.decimals,
+                self.token_program_x.to_account_info(),
             ),
             false => (
                 self.user_ata_y.to_account_info(), 
                 self.vault_y.to_account_info(),
                 self.mint_y.to_account_info(),
-                self.mint_y.decimals
+                self.mint_y.decimals,
+                self.token_program_y.to_account_info(),
             ),
         };
 
-        let cpi_program = self.token_program.to_account_info();
-
         // Set up transfer instruction accounts
         let cpi_accounts = TransferChecked {
             from,
@@ -212,20 +555,21 @@ impl<'info> Deposit<'info> {
         transfer_checked(cpi_context, amount, decimals)
     }
 
-    /// Mints LP tokens to the user as receipt for their liquidity provision
+    /// Mints LP tokens as receipt for liquidity provision
     /// 
     /// # Arguments
+    /// * `to` - LP token account receiving the tokens
     /// * `amount` - Amount of LP tokens to mint
     /// 
     /// # Returns
     /// * `Result<()>` - Ok if successful, error otherwise
-    pub fn mint_lp_tokens(&mut self, amount: u64) -> Result<()> {
+    pub fn mint_lp_tokens(&mut self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
         // Set up mint instruction accounts
         let cpi_accounts = MintTo {
             mint: self.mint_lp.to_account_info(),
-            to: self.user_ata_lp.to_account_info(),
+            to,
             authority: self.config.to_account_info(),  // Config PDA is mint authority
         };
 
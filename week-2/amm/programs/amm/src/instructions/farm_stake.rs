@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::{ClaimEvent, StakeEvent, UnstakeEvent};
+use crate::states::{Farm, Stake};
+
+/// Accounts required for staking LP tokens in a farm and claiming its rewards
+/// Shared by `stake`, `unstake` and `claim`
+#[derive(Accounts)]
+pub struct FarmStake<'info> {
+    /// The staker (must sign the transaction)
+    /// Mutable because they may need to pay for the stake account and ATA creation
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool's LP token mint, the token being staked
+    #[account(mint::token_program = token_program)]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token paid out as rewards
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// The farm being staked in
+    /// Mutable because its staked total and reward accumulator are updated
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref(), farm.seed.to_le_bytes().as_ref()],
+        bump = farm.bump,
+        has_one = mint_lp,      // Ensures mint_lp matches the one in the farm
+        has_one = reward_mint,  // Ensures reward_mint matches the one in the farm
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// The user's stake in the farm
+    /// Will be created on the first stake, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = 8 + Stake::INIT_SPACE
+    )]
+    pub stake: Account<'info, Stake>,
+
+    /// The vault holding the staked LP tokens
+    /// Associated token account owned by the farm PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault holding the rewards still to be paid out
+    /// Associated token account owned by the farm PDA
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for LP tokens
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_ata_lp: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for the reward token
+    /// Will be created if it doesn't exist, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub user_ata_reward: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program owning the reward mint (SPL Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FarmStake<'info> {
+    /// Stakes LP tokens in the farm
+    ///
+    /// Rewards earned by the existing stake are credited first and left for `claim`.
+    ///
+    /// # Arguments
+    /// * `amount` - Amount of LP tokens to stake
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn stake(&mut self, amount: u64, bumps: &FarmStakeBumps) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        // Nothing accrues any more, the stake would earn nothing
+        require!(now < self.farm.end_ts, AmmError::FarmEnded);
+
+        // A freshly created stake starts at the current accumulator
+        if self.stake.farm == Pubkey::default() {
+            self.stake.set_inner(Stake {
+                farm: self.farm.key(),
+                owner: self.user.key(),
+                amount: 0,
+                reward_per_share_paid: 0,
+                rewards_owed: 0,
+                bump: bumps.stake,
+            });
+        }
+
+        self.credit_rewards(now)?;
+
+        self.stake.amount = self
+            .stake
+            .amount
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+        self.farm.total_staked = self
+            .farm
+            .total_staked
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.user_ata_lp.to_account_info(),
+            to: self.lp_vault.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            authority: self.user.to_account_info(),  // User signs the transfer
+        };
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_context, amount, self.mint_lp.decimals)?;
+
+        emit!(StakeEvent {
+            farm: self.farm.key(),
+            user: self.user.key(),
+            amount,
+            total_staked: self.farm.total_staked,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Unstakes LP tokens from the farm, possible at any time, also after it ended
+    ///
+    /// Rewards earned so far are credited and left for `claim`.
+    ///
+    /// # Arguments
+    /// * `amount` - Amount of LP tokens to unstake
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn unstake(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(amount <= self.stake.amount, AmmError::InsufficientBalance);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.credit_rewards(now)?;
+
+        self.stake.amount -= amount;
+        self.farm.total_staked = self
+            .farm
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(AmmError::Underflow)?;
+
+        self.transfer_from_farm(true, amount)?;
+
+        emit!(UnstakeEvent {
+            farm: self.farm.key(),
+            user: self.user.key(),
+            amount,
+            total_staked: self.farm.total_staked,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out all rewards the stake has earned so far
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn claim(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.credit_rewards(now)?;
+
+        let amount = self.stake.rewards_owed;
+        require!(amount != 0, AmmError::ZeroBalance);
+
+        self.stake.rewards_owed = 0;
+        self.farm.rewards_claimed = self
+            .farm
+            .rewards_claimed
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+
+        self.transfer_from_farm(false, amount)?;
+
+        emit!(ClaimEvent {
+            farm: self.farm.key(),
+            user: self.user.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Brings the farm up to `now` and credits the stake with what it earned
+    ///
+    /// # Arguments
+    /// * `now` - Current unix timestamp
+    pub fn credit_rewards(&mut self, now: i64) -> Result<()> {
+        self.farm.update(now)?;
+        self.farm.settle(&mut self.stake)
+    }
+
+    /// Transfers LP tokens or rewards from the farm's vaults to the user
+    ///
+    /// # Arguments
+    /// * `is_lp` - true for staked LP tokens, false for rewards
+    /// * `amount` - Amount of tokens to transfer
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn transfer_from_farm(&mut self, is_lp: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_lp {
+            true => (
+                self.lp_vault.to_account_info(),
+                self.user_ata_lp.to_account_info(),
+                self.mint_lp.to_account_info(),
+                self.mint_lp.decimals,
+                self.token_program.to_account_info(),
+            ),
+            false => (
+                self.reward_vault.to_account_info(),
+                self.user_ata_reward.to_account_info(),
+                self.reward_mint.to_account_info(),
+                self.reward_mint.decimals,
+                self.reward_token_program.to_account_info(),
+            ),
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.farm.to_account_info(),  // Farm PDA signs the transfer
+        };
+
+        // Create signer seeds for the farm PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"farm",
+            self.farm.config.as_ref(),
+            &self.farm.seed.to_le_bytes(),
+            &[self.farm.bump],
+        ]];
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
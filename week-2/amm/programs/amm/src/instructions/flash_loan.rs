@@ -0,0 +1,407 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{
+        self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+    },
+    Discriminator,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::curves::invariant;
+use crate::events::FlashLoanEvent;
+use crate::states::{Config, FlashLoan, Permission, Role};
+
+/// Position of the `config` account in the `Flash` account list
+/// Used to match a `flash_repay` instruction to the pool it repays
+const CONFIG_ACCOUNT_INDEX: usize = 3;
+
+/// Accounts required for borrowing from and repaying to the pool vaults
+/// This struct is shared by `flash_borrow` and `flash_repay`, which must
+/// appear as a pair in the same transaction
+#[derive(Accounts)]
+pub struct Flash<'info> {
+    /// The user who is borrowing or repaying (must sign the transaction)
+    /// Mutable because they may need to pay for ATA creation if accounts don't exist
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The mint account for token X in the trading pair
+    /// Immutable as we only need to read mint information for transfers
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    /// Immutable as we only need to read mint information for transfers
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Mutable because the outstanding loan is recorded here
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The vault that holds all deposited token X
+    /// Mutable because token X is lent from and repaid to it
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Mutable because token Y is lent from and repaid to it
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for token X
+    /// Will be created if it doesn't exist, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x
+    )]
+    pub user_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// User's token account for token Y
+    /// Will be created if it doesn't exist, user pays for creation
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y
+    )]
+    pub user_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// The user's permission, required with the trader role if the pool is permissioned
+    /// Pass `None` for pools open to everyone
+    #[account(
+        seeds = [b"permission", config.key().as_ref(), user.key().as_ref()],
+        bump = permission.bump,
+    )]
+    pub permission: Option<Account<'info, Permission>>,
+
+    /// CHECK: Oracle price update of a pool with a price guard, `None` otherwise
+    /// Only read by `flash_repay`, since a flash swap moves the pool price
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Instructions sysvar, used to find the matching `flash_repay`
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Flash<'info> {
+    /// Lends tokens out of the pool vaults for the rest of the transaction
+    ///
+    /// The loan process follows these steps:
+    /// 1. Validate pool is not locked and no other loan is outstanding
+    /// 2. Verify a `flash_repay` for this pool appears later in the transaction
+    /// 3. Record the loan and the pre-loan reserves in the config
+    /// 4. Transfer the borrowed tokens to the user
+    ///
+    /// Borrowing one token and repaying in the other is a flash swap; borrowing
+    /// and repaying the same token is a plain flash loan.
+    ///
+    /// # Arguments
+    /// * `amount_x` - Amount of token X to borrow
+    /// * `amount_y` - Amount of token Y to borrow
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn flash_borrow(&mut self, amount_x: u64, amount_y: u64) -> Result<()> {
+        // Ensure the pool is not locked for loans
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the trader role
+        self.config.check_permission(self.permission.as_deref(), Role::Trader)?;
+        // Nested loans against the same pool are refused
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Ensure user is borrowing something
+        require!(amount_x != 0 || amount_y != 0, AmmError::InvalidAmount);
+
+        // Only LP-owned reserves can be lent out
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        // The vaults can never be drained completely
+        require!(
+            amount_x < reserve_x && amount_y < reserve_y,
+            AmmError::InsufficientBalance
+        );
+
+        // Record the pre-loan price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+
+        // The loan is only safe if the transaction is guaranteed to repay it
+        self.check_repay_instruction()?;
+
+        // Remember what was lent and the reserves the repayment is checked against
+        self.config.flash_loan = Some(FlashLoan {
+            amount_x,
+            amount_y,
+            reserve_x,
+            reserve_y,
+        });
+
+        // Send the borrowed tokens to the user
+        if amount_x != 0 {
+            self.withdraw_token(true, amount_x)?;
+        }
+        if amount_y != 0 {
+            self.withdraw_token(false, amount_y)?;
+        }
+
+        Ok(())
+    }
+
+    /// Repays the outstanding flash loan and checks the pool invariant
+    ///
+    /// Uses the same fee-adjusted invariant check as Uniswap v2 flash swaps: the
+    /// swap fee, dynamic surcharge included, is charged on every token paid back in,
+    /// and the pool's invariant on the fee-adjusted balances must not be lower than
+    /// before the loan. The fee stays in the vaults and goes to LPs. Flash swaps feed
+    /// the dynamic fee and are held to the price guard like regular swaps.
+    ///
+    /// # Arguments
+    /// * `amount_x` - Amount of token X the user pays back
+    /// * `amount_y` - Amount of token Y the user pays back
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn flash_repay(&mut self, amount_x: u64, amount_y: u64) -> Result<()> {
+        // There must be a loan to repay
+        let loan = self.config.flash_loan.ok_or(AmmError::NoFlashLoan)?;
+
+        // Transfer the repayment from the user to the vaults
+        if amount_x != 0 {
+            self.deposit_token(true, amount_x)?;
+        }
+        if amount_y != 0 {
+            self.deposit_token(false, amount_y)?;
+        }
+
+        // Read back what the vaults actually hold, after any transfer fee
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (balance_x, balance_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Flash swaps are charged the current dynamic fee, like regular swaps
+        self.config.decay_volatility()?;
+        // Weighted pools check against the weights in effect now
+        self.config.update_weights()?;
+
+        // Fee-adjusted balances must keep k from decreasing
+        let adjusted_x = self.fee_adjusted_balance(balance_x, loan.reserve_x, loan.amount_x)?;
+        let adjusted_y = self.fee_adjusted_balance(balance_y, loan.reserve_y, loan.amount_y)?;
+        // Each curve is checked against its own invariant: a StableSwap pool can keep
+        // x * y while losing D, and weighted pools do not value the tokens 50/50
+        require!(
+            invariant(&self.config, adjusted_x, adjusted_y)?
+                >= invariant(&self.config, loan.reserve_x, loan.reserve_y)?,
+            AmmError::InvariantViolated
+        );
+
+        // Feed the price move of a flash swap into the dynamic fee
+        self.config
+            .record_price_move((loan.reserve_x, loan.reserve_y), (balance_x, balance_y))?;
+
+        // Guarded pools must not be pushed away from the oracle price by a flash swap
+        self.config.check_price_guard(
+            self.oracle.as_deref(),
+            self.mint_x.decimals,
+            self.mint_y.decimals,
+            (loan.reserve_x, loan.reserve_y),
+            (balance_x, balance_y),
+        )?;
+
+        // The loan is settled
+        self.config.flash_loan = None;
+
+        emit!(FlashLoanEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            borrowed_x: loan.amount_x,
+            borrowed_y: loan.amount_y,
+            repaid_x: amount_x,
+            repaid_y: amount_y,
+            reserve_x: balance_x,
+            reserve_y: balance_y,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns a post-repayment balance minus the swap fee owed on the tokens paid in
+    ///
+    /// # Arguments
+    /// * `balance` - LP reserves after the repayment
+    /// * `reserve` - LP reserves before the loan
+    /// * `borrowed` - Amount lent out of this vault
+    ///
+    /// # Returns
+    /// * `Result<u64>` - Balance with the fee removed, rounded in the pool's favor
+    fn fee_adjusted_balance(&self, balance: u64, reserve: u64, borrowed: u64) -> Result<u64> {
+        // Tokens paid in = balance - (reserve - borrowed), or zero if less came back
+        let remaining = reserve.checked_sub(borrowed).ok_or(AmmError::Underflow)?;
+        let amount_in = balance.saturating_sub(remaining);
+
+        // Fee on the tokens paid in, rounded up
+        let fee = (amount_in as u128)
+            .checked_mul(self.config.swap_fee() as u128)
+            .ok_or(AmmError::Overflow)?
+            .div_ceil(10_000) as u64;
+
+        balance.checked_sub(fee).ok_or(AmmError::Underflow.into())
+    }
+
+    /// Ensures a `flash_repay` instruction for this pool follows the current instruction
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if a matching repay instruction is found, error otherwise
+    fn check_repay_instruction(&self) -> Result<()> {
+        let instructions = self.instructions.to_account_info();
+        let current_index = load_current_index_checked(&instructions)? as usize;
+
+        // Walk every instruction after the current one until the end of the transaction
+        let mut index = current_index + 1;
+        while let Ok(instruction) = load_instruction_at_checked(index, &instructions) {
+            let is_repay = instruction.program_id == crate::ID
+                && instruction
+                    .data
+                    .starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+                && instruction
+                    .accounts
+                    .get(CONFIG_ACCOUNT_INDEX)
+                    .is_some_and(|meta| meta.pubkey == self.config.key());
+            if is_repay {
+                return Ok(());
+            }
+            index += 1;
+        }
+
+        err!(AmmError::MissingFlashRepay)
+    }
+
+    /// Transfers tokens from user's account to the appropriate vault
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to transfer
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.user_ata_x.to_account_info(),    // Transfer from user's X account
+                self.vault_x.to_account_info(),       // Transfer to vault X
+                self.mint_x.to_account_info(),        // Token X mint
+                self.mint_x.decimals,                 // Token X decimals
+                self.token_program_x.to_account_info(), // Token X program
+            ),
+            false => (
+                self.user_ata_y.to_account_info(),    // Transfer from user's Y account
+                self.vault_y.to_account_info(),       // Transfer to vault Y
+                self.mint_y.to_account_info(),        // Token Y mint
+                self.mint_y.decimals,                 // Token Y decimals
+                self.token_program_y.to_account_info(), // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            authority: self.user.to_account_info(),  // User signs the transfer
+            mint,
+        };
+
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+
+    /// Withdraws tokens from vault to user's account
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to withdraw
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),       // Transfer from vault X
+                self.user_ata_x.to_account_info(),    // Transfer to user's X account
+                self.mint_x.to_account_info(),        // Token X mint
+                self.mint_x.decimals,                 // Token X decimals
+                self.token_program_x.to_account_info(), // Token X program
+            ),
+            false => (
+                self.vault_y.to_account_info(),       // Transfer from vault Y
+                self.user_ata_y.to_account_info(),    // Transfer to user's Y account
+                self.mint_y.to_account_info(),        // Token Y mint
+                self.mint_y.decimals,                 // Token Y decimals
+                self.token_program_y.to_account_info(), // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.config.to_account_info(),  // Config PDA signs the transfer
+        };
+
+        // Create signer seeds for config PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"config",
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ]];
+
+        // Create CPI context with PDA signer
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
@@ -1,16 +1,22 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::states::Config;
+use crate::curves::{MAX_AMP, MIN_AMP};
+use crate::errors::AmmError;
+use crate::events::InitializeEvent;
+use crate::states::{Config, CurveType, Factory, PoolEntry, Registry, Twamm, WeightSchedule};
+use crate::utils::check_mint_extensions;
 
 /// Accounts required for initializing a new AMM pool
 /// This struct defines all the accounts needed to create a new liquidity pool
 /// with two tokens (X and Y) and set up the initial pool configuration
+/// Pools are created through the factory: there is one canonical pool per
+/// sorted mint pair and fee tier, and every pool is listed in the registry
 #[derive(Accounts)]
-#[instruction(seed: u64)]
+#[instruction(fee: u16)]
 pub struct Initialize<'info> {
     /// The admin/deployer who is creating the pool (must sign the transaction)
     /// Mutable because they will pay for account creation costs
@@ -19,21 +25,61 @@ pub struct Initialize<'info> {
 
     /// The mint account for the first token (X) in the trading pair
     /// This is immutable as we only need to read mint information for validation
-    pub mint_x: Account<'info, Mint>,
+    /// May belong to either SPL Token or Token-2022
+    /// Must be the smaller of the two mint addresses
+    #[account(
+        mint::token_program = token_program_x,
+        constraint = mint_x.key() < mint_y.key() @ AmmError::MintsNotSorted,
+    )]
+    pub mint_x: InterfaceAccount<'info, Mint>,
 
     /// The mint account for the second token (Y) in the trading pair
     /// This is immutable as we only need to read mint information for validation
-    pub mint_y: Account<'info, Mint>,
+    /// May belong to either SPL Token or Token-2022
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The global factory account
+    /// Mutable because its pool counter is incremented
+    #[account(
+        mut,
+        seeds = [b"factory"],
+        bump = factory.bump,
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// The global registry listing every pool
+    /// Grown by one entry for the new pool, admin pays the extra rent
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+        realloc = Registry::space(registry.pools.len() + 1),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub registry: Account<'info, Registry>,
+
+    /// The canonical entry for this mint pair and fee tier
+    /// Creation fails if a pool already exists for the same pair and tier
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"pool", mint_x.key().as_ref(), mint_y.key().as_ref(), fee.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + PoolEntry::INIT_SPACE
+    )]
+    pub pool: Account<'info, PoolEntry>,
 
     /// The main configuration account for the AMM pool
     /// This PDA stores all pool settings, token references, and metadata
-    /// Uses a custom seed provided by the admin to allow multiple pools
+    /// Uses the factory's pool counter as seed, so every pool gets a fresh address
     #[account(
         init,                                                    // Create new account
         payer = admin,                                          // Admin pays for creation
-        seeds = [b"config", seed.to_le_bytes().as_ref()],      // PDA derivation
+        seeds = [b"config", factory.pool_count.to_le_bytes().as_ref()], // PDA derivation
         bump,                                                   // Anchor finds canonical bump
-        space = Config::INIT_SPACE                              // Required space for Config struct
+        space = 8 + Config::INIT_SPACE                          // Discriminator + space for Config struct
     )]
     pub config: Account<'info, Config>,
 
@@ -45,10 +91,25 @@ pub struct Initialize<'info> {
         payer = admin,                         // Admin pays for creation
         mint::decimals = 6,                    // Standard 6 decimal places for LP tokens
         mint::authority = config.key(),        // Config PDA controls minting
+        mint::token_program = token_program,   // LP mint lives under the LP token program
         seeds = [b"lp", config.key().as_ref()], // PDA derived from config
         bump,                                  // Anchor finds canonical bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// LP token account holding the `MINIMUM_LIQUIDITY` minted on the first deposit
+    /// Its authority is the config PDA, which only burns them when the pool is closed
+    /// after every other LP token was, so they stay locked for the pool's lifetime
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"locked_lp", config.key().as_ref()],
+        bump,
+        token::mint = mint_lp,
+        token::authority = config,
+        token::token_program = token_program,
+    )]
+    pub locked_lp: InterfaceAccount<'info, TokenAccount>,
 
     /// The vault that will hold all deposited token X
     /// This is an associated token account owned by the config PDA
@@ -58,9 +119,9 @@ pub struct Initialize<'info> {
         payer = admin,                                 // Admin pays for creation
         associated_token::mint = mint_x,               // Associated with mint_x
         associated_token::authority = config,          // Owned by config PDA
-        associated_token::token_program = token_program, // Uses SPL Token program
+        associated_token::token_program = token_program_x, // Uses token X's program
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
 
     /// The vault that will hold all deposited token Y
     /// This is an associated token account owned by the config PDA
@@ -70,12 +131,16 @@ pub struct Initialize<'info> {
         payer = admin,                                 // Admin pays for creation
         associated_token::mint = mint_y,               // Associated with mint_y
         associated_token::authority = config,          // Owned by config PDA
-        associated_token::token_program = token_program, // Uses SPL Token program
+        associated_token::token_program = token_program_y, // Uses token Y's program
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
-    /// SPL Token program for token operations
-    pub token_program: Program<'info, Token>,
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
     /// Associated Token program for ATA operations
     pub associated_token_program: Program<'info, AssociatedToken>,
     /// System program for account creation
@@ -90,33 +155,108 @@ impl<'info> Initialize<'info> {
     /// - Provide liquidity and earn fees
     /// - Remove liquidity by burning LP tokens
     /// 
-    /// The pool uses the constant product formula (x * y = k) to determine
-    /// exchange rates and maintain liquidity depth.
+    /// The pool uses either the constant product formula (x * y = k) or the
+    /// StableSwap invariant to determine exchange rates and maintain liquidity depth.
+    /// 
+    /// The pool's seed is taken from the factory's pool counter, and the pool is
+    /// recorded both under its canonical `(mint_x, mint_y, fee)` entry and in the registry.
     /// 
     /// # Arguments
-    /// * `seed` - Unique identifier for this pool, allows multiple pools with same token pairs
-    /// * `fee` - Trading fee in basis points (e.g., 30 = 0.30%)
-    /// * `authority` - Optional authority that can update pool settings (None = immutable)
+    /// * `fee` - Trading fee in basis points (e.g., 30 = 0.30%), must be an allowed fee tier
+    /// * `authority` - Optional authority that can update pool settings (None = immutable).
+    ///   Must be the factory admin: whoever creates a pair first must not control its
+    ///   only canonical pool
+    /// * `curve_type` - Pricing curve of the pool (constant product, StableSwap or weighted)
+    /// * `amp` - StableSwap amplification coefficient (must be 0 for other curves)
+    /// * `weights` - Token weights of a weighted pool (must be None for other curves)
     /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
     pub fn initialize(
         &mut self, 
-        seed: u64, 
         fee: u16, 
         authority: Option<Pubkey>, 
+        curve_type: CurveType,
+        amp: u64,
+        weights: Option<WeightSchedule>,
         bumps: &InitializeBumps
     ) -> Result<()> {
+        // Only the factory's fee tiers may be used for new pools
+        self.factory.check_fee_tier(fee)?;
+        // Canonical pools are administered by the factory admin or by nobody
+        require!(
+            authority.map_or(true, |authority| authority == self.factory.admin),
+            AmmError::InvalidAuthority
+        );
+
+        // Reject Token-2022 mints whose extensions could move or freeze vault funds
+        check_mint_extensions(&self.mint_x)?;
+        check_mint_extensions(&self.mint_y)?;
+
+        // Only StableSwap pools are amplified, and only weighted pools have weights
+        match curve_type {
+            CurveType::ConstantProduct => require!(amp == 0, AmmError::InvalidAmp),
+            CurveType::StableSwap => require!((MIN_AMP..=MAX_AMP).contains(&amp), AmmError::InvalidAmp),
+            CurveType::Weighted => require!(amp == 0, AmmError::InvalidAmp),
+        }
+        match (curve_type, &weights) {
+            (CurveType::Weighted, Some(weights)) => weights.validate()?,
+            (CurveType::Weighted, None) => return err!(AmmError::InvalidWeights),
+            (_, Some(_)) => return err!(AmmError::InvalidWeights),
+            (_, None) => {}
+        }
+        let now = Clock::get()?.unix_timestamp;
+
+        // Take the next pool number from the factory
+        let seed = self.factory.pool_count;
+        self.factory.pool_count = seed.checked_add(1).ok_or(AmmError::Overflow)?;
+
         // Initialize the config account with all pool parameters
         self.config.set_inner(Config { 
             seed,                              // Unique pool identifier
             authority,                         // Optional update authority
             mint_x: self.mint_x.key(),        // First token in the pair
             mint_y: self.mint_y.key(),        // Second token in the pair
+            curve_type,                       // Pricing curve
+            amp,                              // StableSwap amplification
+            weights,                          // Weighted pool weights
+            weight_x: weights.map_or(0, |weights| weights.weight_x_at(now)),
             fee,                              // Trading fee in basis points
+            protocol_fee: 0,                  // All swap fees go to LPs until the authority opts in
+            protocol_fees_x: 0,               // No protocol fees accrued yet
+            protocol_fees_y: 0,               // No protocol fees accrued yet
+            price_x_cumulative: 0,            // TWAP accumulators start empty
+            price_y_cumulative: 0,
+            last_update_ts: now,
+            flash_loan: None,                 // No flash loan outstanding
+            dynamic_fee: None,                // Static fee until the authority opts in
+            price_guard: None,                // Trades at any price until the authority opts in
+            twamm: Twamm::default(),          // No long-term orders yet
             locked: false,                    // Pool starts unlocked (active)
+            permissioned: false,              // Open to everyone until the authority opts in
             config_bump: bumps.config,        // PDA bump for config account
             lp_bump: bumps.mint_lp            // PDA bump for LP mint
         });
 
+        // Record the pool as canonical for its pair and fee tier
+        self.pool.set_inner(PoolEntry {
+            config: self.config.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee_tier: fee,
+            bump: bumps.pool,
+        });
+
+        // List the pool so it can be enumerated
+        self.registry.pools.push(self.config.key());
+
+        emit!(InitializeEvent {
+            pool: self.config.key(),
+            admin: self.admin.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         // Pool is now ready for liquidity deposits and trading
         Ok(())
     }
@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::curves::{tick_at_sqrt_price, MAX_SQRT_PRICE, MIN_SQRT_PRICE};
+use crate::errors::AmmError;
+use crate::states::{ClPool, TickBook};
+use crate::utils::check_mint_extensions;
+
+/// Accounts required for initializing a new concentrated liquidity pool
+/// This struct defines all the accounts needed to create a pool whose
+/// liquidity is provided over individual price ranges
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct InitializeClPool<'info> {
+    /// The admin/deployer who is creating the pool (must sign the transaction)
+    /// Mutable because they will pay for account creation costs
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The mint account for the first token (X) in the trading pair
+    /// May belong to either SPL Token or Token-2022
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for the second token (Y) in the trading pair
+    /// May belong to either SPL Token or Token-2022
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The pool account storing price, active liquidity and fee growth
+    /// Uses a custom seed provided by the admin to allow multiple pools
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"cl_pool", seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + ClPool::INIT_SPACE
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    /// The pool's initialized ticks
+    /// PDA derived from the pool, one per pool
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"tick_book", cl_pool.key().as_ref()],
+        bump,
+        space = 8 + TickBook::INIT_SPACE
+    )]
+    pub tick_book: Account<'info, TickBook>,
+
+    /// The vault that will hold all deposited token X
+    /// Associated token account owned by the pool PDA
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that will hold all deposited token Y
+    /// Associated token account owned by the pool PDA
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeClPool<'info> {
+    /// Initializes a new concentrated liquidity pool at the given price
+    ///
+    /// The pool starts with no liquidity. LPs open positions over price ranges
+    /// with `open_cl_position` and fund them with `increase_cl_liquidity`.
+    ///
+    /// # Arguments
+    /// * `seed` - Unique identifier for this pool
+    /// * `fee` - Trading fee in basis points (e.g., 30 = 0.30%)
+    /// * `tick_spacing` - Positions may only start and end on multiples of this spacing
+    /// * `sqrt_price` - Initial sqrt price of token X in units of token Y (Q64.64)
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    pub fn initialize_cl_pool(
+        &mut self,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+        bumps: &InitializeClPoolBumps,
+    ) -> Result<()> {
+        // Reject Token-2022 mints whose extensions could move or freeze vault funds
+        check_mint_extensions(&self.mint_x)?;
+        check_mint_extensions(&self.mint_y)?;
+
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(tick_spacing > 0, AmmError::InvalidTickSpacing);
+        // Keep the price strictly inside the supported range so swaps can move both ways
+        require!(
+            sqrt_price > MIN_SQRT_PRICE && sqrt_price < MAX_SQRT_PRICE,
+            AmmError::InvalidSqrtPrice
+        );
+
+        self.cl_pool.set_inner(ClPool {
+            seed,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick_current: tick_at_sqrt_price(sqrt_price).map_err(AmmError::from)?,
+            liquidity: 0,                 // No positions yet
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            bump: bumps.cl_pool,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::program::Amm;
+use crate::states::{Factory, Registry};
+
+/// Accounts required for creating the global pool factory and registry
+/// Only the program's upgrade authority may do this, so nobody can front-run
+/// the deployment and take over the fee tiers
+#[derive(Accounts)]
+pub struct InitializeFactory<'info> {
+    /// The upgrade authority of the program (must sign the transaction)
+    /// Mutable because they pay for account creation and become the factory admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The global factory account
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"factory"],
+        bump,
+        space = 8 + Factory::INIT_SPACE
+    )]
+    pub factory: Account<'info, Factory>,
+
+    /// The global registry listing every pool, starts empty
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"registry"],
+        bump,
+        space = Registry::space(0)
+    )]
+    pub registry: Account<'info, Registry>,
+
+    /// This program, used to locate its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    /// Program data account holding the upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeFactory<'info> {
+    /// Creates the factory with its initial fee tiers and an empty registry
+    ///
+    /// # Arguments
+    /// * `fee_tiers` - Trading fees (in basis points) pools may be created with, ascending
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    pub fn initialize_factory(
+        &mut self,
+        fee_tiers: Vec<u16>,
+        bumps: &InitializeFactoryBumps,
+    ) -> Result<()> {
+        Factory::check_fee_tiers(&fee_tiers)?;
+
+        self.factory.set_inner(Factory {
+            admin: self.admin.key(),
+            fee_tiers,
+            pool_count: 0,
+            bump: bumps.factory,
+        });
+
+        self.registry.set_inner(Registry {
+            pools: Vec::new(),
+            bump: bumps.registry,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::states::{Config, LpPosition, PositionValue};
+
+/// Accounts required for valuing a liquidity position
+/// Nothing is mutated, so the instruction can be simulated as a view
+#[derive(Accounts)]
+pub struct LpPositionValue<'info> {
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The LP token mint, whose supply the position's share is taken of
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The vault holding token X
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault holding token Y
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// The position to value
+    #[account(
+        seeds = [b"lp_position", config.key().as_ref(), lp_position.owner.as_ref()],
+        bump = lp_position.bump,
+        has_one = config,  // Position must belong to this pool
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> LpPositionValue<'info> {
+    /// Values the position at the pool's current state
+    ///
+    /// Long-term orders are executed up to now and weights moved along their
+    /// schedule on a copy of the pool configuration, so the value matches what the
+    /// next instruction touching the pool would see without changing anything.
+    ///
+    /// # Returns
+    /// * `Result<PositionValue>` - The position's value, fees and impermanent loss,
+    ///   also set as the instruction's return data
+    pub fn lp_position_value(&self) -> Result<PositionValue> {
+        let mut config = (*self.config).clone();
+        // Executed on the copy only, so no `ExecuteOrdersEvent` is emitted
+        let now = Clock::get()?.unix_timestamp;
+        config.execute_virtual_orders_to(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply, now)?;
+        config.update_weights()?;
+
+        let (reserve_x, reserve_y) = config.lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.lp_position
+            .value(&config, reserve_x, reserve_y, self.mint_lp.supply)
+    }
+}
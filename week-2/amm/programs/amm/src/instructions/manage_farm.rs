@@ -0,0 +1,171 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::{ExtendFarmEvent, RefundFarmEvent};
+use crate::states::Farm;
+use crate::utils::amount_with_transfer_fee;
+
+/// Accounts required for the farm authority to extend a farm or take back its leftover rewards
+/// Shared by `extend_farm` and `refund_farm`
+#[derive(Accounts)]
+pub struct ManageFarm<'info> {
+    /// The farm authority (must sign the transaction)
+    /// Mutable because they may need to pay for ATA creation if it doesn't exist
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The mint of the token paid out as rewards
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// The farm being managed
+    /// Mutable because its schedule and reward accumulator are updated
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref(), farm.seed.to_le_bytes().as_ref()],
+        bump = farm.bump,
+        has_one = authority,    // Only the farm authority may manage it
+        has_one = reward_mint,  // Ensures reward_mint matches the one in the farm
+    )]
+    pub farm: Account<'info, Farm>,
+
+    /// The vault holding the rewards still to be paid out
+    /// Associated token account owned by the farm PDA
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Authority's token account for the reward token
+    /// Will be created if it doesn't exist, authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub authority_reward_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning the reward mint (SPL Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ManageFarm<'info> {
+    /// Extends a running farm to a later end and/or a higher reward rate
+    ///
+    /// Rewards accrued so far are kept at the old rate; the new rate applies from now
+    /// (or from the start, if the farm has not started yet) until the new end. The
+    /// authority funds the difference between the new and the old remaining schedule.
+    ///
+    /// # Arguments
+    /// * `end_ts` - New end of the farm, not earlier than the current one
+    /// * `reward_rate` - New rewards emitted per second, not lower than the current rate
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn extend_farm(&mut self, end_ts: i64, reward_rate: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // An ended farm may already have been refunded
+        require!(now < self.farm.end_ts, AmmError::FarmEnded);
+        // Stakers joined expecting at least the current schedule
+        require!(
+            end_ts >= self.farm.end_ts && reward_rate >= self.farm.reward_rate,
+            AmmError::InvalidRewardSchedule
+        );
+
+        // Settle the rewards accrued at the old rate
+        self.farm.update(now)?;
+
+        let from = now.max(self.farm.start_ts);
+        let remaining = self.farm.rewards_between(from, self.farm.end_ts)?;
+        self.farm.reward_rate = reward_rate;
+        self.farm.end_ts = end_ts;
+        let extended = self.farm.rewards_between(from, end_ts)?;
+
+        // Fund the extra rewards, grossed up for any transfer fee
+        let top_up = extended
+            .checked_sub(remaining)
+            .ok_or(AmmError::Underflow)?;
+        require!(top_up > 0, AmmError::InvalidAmount);
+        let amount = amount_with_transfer_fee(&self.reward_mint, top_up)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.authority_reward_ata.to_account_info(),
+            to: self.reward_vault.to_account_info(),
+            mint: self.reward_mint.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(self.reward_token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_context, amount, self.reward_mint.decimals)?;
+
+        emit!(ExtendFarmEvent {
+            farm: self.farm.key(),
+            end_ts,
+            reward_rate,
+            amount: top_up,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the rewards no staker can claim to the authority, once the farm has ended
+    ///
+    /// These are the rewards emitted while nothing was staked, plus rounding dust.
+    /// Rewards credited to stakers stay in the vault until they claim them.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn refund_farm(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.farm.end_ts, AmmError::FarmNotEnded);
+
+        // Credit stakers with everything up to the end before working out the leftovers
+        self.farm.update(now)?;
+        let amount = self.farm.unallocated_rewards(self.reward_vault.amount)?;
+        require!(amount > 0, AmmError::ZeroBalance);
+
+        let cpi_accounts = TransferChecked {
+            from: self.reward_vault.to_account_info(),
+            to: self.authority_reward_ata.to_account_info(),
+            mint: self.reward_mint.to_account_info(),
+            authority: self.farm.to_account_info(),  // Farm PDA signs the transfer
+        };
+
+        // Create signer seeds for the farm PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"farm",
+            self.farm.config.as_ref(),
+            &self.farm.seed.to_le_bytes(),
+            &[self.farm.bump],
+        ]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.reward_token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, amount, self.reward_mint.decimals)?;
+
+        emit!(RefundFarmEvent {
+            farm: self.farm.key(),
+            authority: self.authority.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+use crate::events::{RevokePermissionEvent, SetPermissionEvent};
+use crate::states::{Config, Permission};
+
+/// Accounts required for granting a user roles in a permissioned pool
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct SetPermission<'info> {
+    /// The pool authority (must sign the transaction)
+    /// Mutable because it pays for the permission account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The AMM pool configuration account
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The user's permission in the pool
+    /// Created on the first grant, the authority pays for creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"permission", config.key().as_ref(), user.as_ref()],
+        bump,
+        space = 8 + Permission::INIT_SPACE
+    )]
+    pub permission: Account<'info, Permission>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPermission<'info> {
+    /// Grants `user` the given roles, replacing any roles held before
+    ///
+    /// # Arguments
+    /// * `user` - User the permission is granted to
+    /// * `lp` - Whether the user may deposit and withdraw liquidity
+    /// * `trader` - Whether the user may swap, take flash loans and place long-term orders
+    /// * `bumps` - PDA bumps for the accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_permission(
+        &mut self,
+        user: Pubkey,
+        lp: bool,
+        trader: bool,
+        bumps: &SetPermissionBumps,
+    ) -> Result<()> {
+        // Only the pool authority may manage the allowlist
+        self.config.check_authority(&self.authority.key())?;
+
+        self.permission.set_inner(Permission {
+            config: self.config.key(),
+            user,
+            lp,
+            trader,
+            bump: bumps.permission,
+        });
+
+        emit!(SetPermissionEvent {
+            pool: self.config.key(),
+            user,
+            lp,
+            trader,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Accounts required for revoking a user's permission in a pool
+#[derive(Accounts)]
+pub struct RevokePermission<'info> {
+    /// The pool authority (must sign the transaction)
+    /// Mutable because the permission account's rent is refunded to it
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The AMM pool configuration account
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The permission being revoked
+    /// Closed at the end of the instruction, rent refunded to the authority
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"permission", config.key().as_ref(), permission.user.as_ref()],
+        bump = permission.bump,
+        has_one = config,  // Permission must belong to this pool
+    )]
+    pub permission: Account<'info, Permission>,
+}
+
+impl<'info> RevokePermission<'info> {
+    /// Revokes every role of the permission's user by closing the account
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn revoke_permission(&mut self) -> Result<()> {
+        // Only the pool authority may manage the allowlist
+        self.config.check_authority(&self.authority.key())?;
+
+        emit!(RevokePermissionEvent {
+            pool: self.config.key(),
+            user: self.permission.user,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
@@ -1,3 +1,9 @@
+pub mod initialize_factory;
+pub use initialize_factory::*;
+
+pub mod update_factory;
+pub use update_factory::*;
+
 pub mod initialize;
 pub use initialize::*;
 
@@ -8,4 +14,58 @@ pub mod withdraw;
 pub use withdraw::*;
 
 pub mod swap;
-pub use swap::*;
\ No newline at end of file
+pub use swap::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+pub mod flash_loan;
+pub use flash_loan::*;
+
+pub mod initialize_cl_pool;
+pub use initialize_cl_pool::*;
+
+pub mod open_cl_position;
+pub use open_cl_position::*;
+
+pub mod cl_liquidity;
+pub use cl_liquidity::*;
+
+pub mod cl_swap;
+pub use cl_swap::*;
+
+pub mod close_cl_position;
+pub use close_cl_position::*;
+
+pub mod create_farm;
+pub use create_farm::*;
+
+pub mod manage_farm;
+pub use manage_farm::*;
+
+pub mod farm_stake;
+pub use farm_stake::*;
+
+pub mod place_order;
+pub use place_order::*;
+
+pub mod manage_order;
+pub use manage_order::*;
+
+pub mod settle_order;
+pub use settle_order::*;
+
+pub mod close_pool;
+pub use close_pool::*;
+
+pub mod manage_permission;
+pub use manage_permission::*;
+
+pub mod lp_position_value;
+pub use lp_position_value::*;
+
+pub mod quote;
+pub use quote::*;
\ No newline at end of file
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{ClPool, ClPosition};
+
+/// Accounts required for opening a concentrated liquidity position
+/// A position is a PDA unique to its pool, owner and price range
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenClPosition<'info> {
+    /// The liquidity provider opening the position (must sign the transaction)
+    /// Mutable because they pay for the position account
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The pool the position provides liquidity to
+    #[account(
+        seeds = [b"cl_pool", cl_pool.seed.to_le_bytes().as_ref()],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    /// The new position account
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"cl_position",
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = 8 + ClPosition::INIT_SPACE
+    )]
+    pub position: Account<'info, ClPosition>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenClPosition<'info> {
+    /// Opens an empty position over `[tick_lower, tick_upper)`
+    ///
+    /// # Arguments
+    /// * `tick_lower` - Lower tick of the range, a multiple of the pool's tick spacing
+    /// * `tick_upper` - Upper tick of the range, a multiple of the pool's tick spacing
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    pub fn open_cl_position(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        bumps: &OpenClPositionBumps,
+    ) -> Result<()> {
+        self.cl_pool.check_ticks(tick_lower, tick_upper)?;
+
+        self.position.set_inner(ClPosition {
+            cl_pool: self.cl_pool.key(),
+            owner: self.owner.key(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_x_last: 0,
+            fee_growth_inside_y_last: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            bump: bumps.position,
+        });
+
+        Ok(())
+    }
+}
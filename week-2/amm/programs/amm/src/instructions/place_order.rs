@@ -0,0 +1,260 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::PlaceOrderEvent;
+use crate::states::{
+    Config, Order, Permission, Role, MAX_ORDER_DURATION, MIN_ORDER_BPS, ORDER_INTERVAL,
+};
+use crate::utils::{amount_with_transfer_fee, check_deadline};
+
+/// Accounts required for placing a long-term (TWAMM) order
+/// The order's tokens are deposited into the pool vault and sold over time
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct PlaceOrder<'info> {
+    /// The user placing the order (must sign the transaction)
+    /// Mutable because they pay for the order account and ATA creation
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Mutable because the order is added to the pool's long-term order book
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The LP (Liquidity Provider) token mint
+    /// Read for the LP supply when executing earlier orders
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The vault that holds all deposited token X
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token X
+    /// Will be created if it doesn't exist, owner pays for creation
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x
+    )]
+    pub owner_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token Y
+    /// Will be created if it doesn't exist, owner pays for creation
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y
+    )]
+    pub owner_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// The new order account
+    /// PDA unique to the pool, owner and an id chosen by the owner
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"order", config.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Order::INIT_SPACE
+    )]
+    pub order: Account<'info, Order>,
+
+    /// The user's permission, required with the trader role if the pool is permissioned
+    /// Pass `None` for pools open to everyone
+    #[account(
+        seeds = [b"permission", config.key().as_ref(), owner.key().as_ref()],
+        bump = permission.bump,
+    )]
+    pub permission: Option<Account<'info, Permission>>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceOrder<'info> {
+    /// Places a long-term order selling `amount_in` tokens into the pool at a constant
+    /// rate over (at least) `duration` seconds
+    ///
+    /// The order expires at the first multiple of `ORDER_INTERVAL` at or after
+    /// `now + duration`. It sells `amount_in / (expiry - now)` tokens per second, so
+    /// only `sale_rate * (expiry - now)` tokens are deposited; the rounding remainder
+    /// stays with the owner.
+    ///
+    /// The duration is capped at `MAX_ORDER_DURATION`, and the deposit must be at least
+    /// `MIN_ORDER_BPS` of the LP reserves of the token sold.
+    ///
+    /// For Token-2022 mints with a transfer fee, the owner is charged the gross amount
+    /// needed for the vault to actually receive the deposit.
+    ///
+    /// # Arguments
+    /// * `id` - Unique identifier among the owner's orders on the pool
+    /// * `is_x` - true to sell token X for Y, false to sell token Y for X
+    /// * `amount_in` - Maximum amount of tokens to sell over the order's lifetime
+    /// * `duration` - Minimum number of seconds to spread the sale over, at most `MAX_ORDER_DURATION`
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    /// * `bumps` - Canonical bump values for PDAs (provided by Anchor)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn place_order(
+        &mut self,
+        id: u64,
+        is_x: bool,
+        amount_in: u64,
+        duration: u32,
+        expires_at: i64,
+        bumps: &PlaceOrderBumps,
+    ) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
+        // Ensure the pool is not locked for new orders
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the trader role
+        self.config.check_permission(self.permission.as_deref(), Role::Trader)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Orders trade against the LPs, so the pool must have liquidity
+        require!(self.mint_lp.supply > 0, AmmError::ZeroBalance);
+
+        // Earlier orders must be executed up to now before the sale rates change
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Long orders would spread their expiries over more slots than the book has
+        require!(duration <= MAX_ORDER_DURATION, AmmError::OrderTooLong);
+
+        // Align the expiry to the interval grid, so orders share expiries
+        let now = self.config.twamm.last_execution_ts;
+        let end = now.checked_add(duration as i64).ok_or(AmmError::Overflow)?;
+        let expiry_ts = end
+            .checked_add(ORDER_INTERVAL - 1)
+            .ok_or(AmmError::Overflow)?
+            / ORDER_INTERVAL
+            * ORDER_INTERVAL;
+        require!(expiry_ts > now, AmmError::InvalidAmount);
+
+        // Only whole tokens per second can be sold
+        let selling_time = (expiry_ts - now) as u64;
+        let sale_rate = amount_in / selling_time;
+        require!(sale_rate > 0, AmmError::InvalidAmount);
+        let amount = sale_rate * selling_time;
+
+        // Orders must be worth an expiry slot
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let reserve_in = match is_x {
+            true => reserve_x,
+            false => reserve_y,
+        };
+        let min_amount = (reserve_in as u128 * MIN_ORDER_BPS as u128).div_ceil(10_000);
+        require!(amount as u128 >= min_amount, AmmError::OrderTooSmall);
+
+        self.config.add_order(is_x, sale_rate, expiry_ts, amount)?;
+
+        self.order.set_inner(Order {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            id,
+            is_x,
+            sale_rate,
+            start_ts: now,
+            expiry_ts,
+            proceeds_per_rate_last: match is_x {
+                true => self.config.twamm.proceeds_per_rate_x,
+                false => self.config.twamm.proceeds_per_rate_y,
+            },
+            bump: bumps.order,
+        });
+
+        // Deposit the tokens to sell, grossed up for any transfer fee
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.owner_ata_x.to_account_info(),
+                self.vault_x.to_account_info(),
+                &self.mint_x,
+                self.mint_x.decimals,
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.owner_ata_y.to_account_info(),
+                self.vault_y.to_account_info(),
+                &self.mint_y,
+                self.mint_y.decimals,
+                self.token_program_y.to_account_info(),
+            ),
+        };
+        let gross_amount = amount_with_transfer_fee(mint, amount)?;
+
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.owner.to_account_info(),  // Owner signs the transfer
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        transfer_checked(cpi_context, gross_amount, decimals)?;
+
+        emit!(PlaceOrderEvent {
+            pool: self.config.key(),
+            owner: self.owner.key(),
+            order: self.order.key(),
+            is_x,
+            sale_rate,
+            amount,
+            expiry_ts,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
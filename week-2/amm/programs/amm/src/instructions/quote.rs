@@ -0,0 +1,249 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use constant_product_curve::ConstantProduct;
+
+use crate::curves::{invariant_per_lp, swap_exact_in};
+use crate::errors::AmmError;
+use crate::states::Config;
+use crate::utils::{amount_with_transfer_fee, transfer_fee};
+
+/// Amounts a `swap` would trade, returned by `quote_swap`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotedSwap {
+    /// Amount the user sends, transfer fee included
+    pub amount_in: u64,
+    /// Amount the user receives, after any transfer fee
+    pub amount_out: u64,
+    /// Swap fee charged in the input token (protocol share included)
+    pub fee: u64,
+}
+
+/// Amounts a `deposit` would take, returned by `quote_deposit`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotedDeposit {
+    /// Amount of token X the user sends, transfer fee included
+    pub amount_x: u64,
+    /// Amount of token Y the user sends, transfer fee included
+    pub amount_y: u64,
+    /// LP tokens minted to the user
+    pub lp_amount: u64,
+}
+
+/// Amounts a `withdraw` would pay out, returned by `quote_withdraw`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotedWithdraw {
+    /// Amount of token X the user receives, after any transfer fee
+    pub amount_x: u64,
+    /// Amount of token Y the user receives, after any transfer fee
+    pub amount_y: u64,
+    /// LP tokens burned by the user
+    pub lp_amount: u64,
+}
+
+/// Accounts required for quoting against the AMM pool
+/// The pool accounts of `Swap`, read-only, so other programs can price trades
+/// through CPI and clients can simulate the instructions as views
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The LP token mint, for the LP supply
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The vault holding token X
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault holding token Y
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Quote<'info> {
+    /// Quotes `swap` selling exactly `amount_in`
+    ///
+    /// Runs the same checks, curve, fees and transfer fees as `swap`. Permissions
+    /// and the price guard are not checked, since they depend on the trader and the
+    /// oracle account.
+    ///
+    /// # Arguments
+    /// * `is_x` - true if selling token X for Y, false if selling Y for X
+    /// * `amount_in` - Amount the user would send, transfer fee included
+    ///
+    /// # Returns
+    /// * `Result<QuotedSwap>` - Amounts in, out and fee, also set as return data
+    pub fn quote_swap(&self, is_x: bool, amount_in: u64) -> Result<QuotedSwap> {
+        self.check_tradeable()?;
+        // Ensure user is swapping a positive amount
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        let (config, reserve_x, reserve_y) = self.executed_config()?;
+
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x),
+        };
+        let amount_received = amount_in
+            .checked_sub(transfer_fee(mint_in, amount_in)?)
+            .ok_or(AmmError::Underflow)?;
+
+        let quote = swap_exact_in(
+            &config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            amount_received,
+        )?;
+        require!(quote.amount_in != 0, AmmError::InvalidAmount);
+        require!(quote.amount_out != 0, AmmError::InvalidAmount);
+
+        let amount_out = quote
+            .amount_out
+            .checked_sub(transfer_fee(mint_out, quote.amount_out)?)
+            .ok_or(AmmError::Underflow)?;
+
+        Ok(QuotedSwap {
+            amount_in,
+            amount_out,
+            fee: quote.fee,
+        })
+    }
+
+    /// Quotes `deposit` minting `lp_amount` into a pool that already holds liquidity
+    ///
+    /// # Arguments
+    /// * `lp_amount` - LP tokens to mint
+    ///
+    /// # Returns
+    /// * `Result<QuotedDeposit>` - Token amounts the user would send, also set as return data
+    pub fn quote_deposit(&self, lp_amount: u64) -> Result<QuotedDeposit> {
+        self.check_tradeable()?;
+        // Ensure user is requesting to mint some LP tokens
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+        // The first deposit sets the price, so there is nothing to quote
+        require!(self.mint_lp.supply != 0, AmmError::NoLiquidityInPool);
+
+        let (_, reserve_x, reserve_y) = self.executed_config()?;
+        let amounts = ConstantProduct::xy_deposit_amounts_from_l(
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            lp_amount,
+            6,  // Same precision as `deposit`
+        )
+        .map_err(AmmError::from)?;
+
+        Ok(QuotedDeposit {
+            amount_x: amount_with_transfer_fee(&self.mint_x, amounts.x)?,
+            amount_y: amount_with_transfer_fee(&self.mint_y, amounts.y)?,
+            lp_amount,
+        })
+    }
+
+    /// Quotes `withdraw` burning `lp_amount`
+    ///
+    /// # Arguments
+    /// * `lp_amount` - LP tokens to burn
+    ///
+    /// # Returns
+    /// * `Result<QuotedWithdraw>` - Token amounts the user would receive, also set as return data
+    pub fn quote_withdraw(&self, lp_amount: u64) -> Result<QuotedWithdraw> {
+        self.check_tradeable()?;
+        // Ensure user is requesting to burn some LP tokens
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+        require!(self.mint_lp.supply != 0, AmmError::NoLiquidityInPool);
+
+        let (_, reserve_x, reserve_y) = self.executed_config()?;
+        let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            lp_amount,
+            6,  // Same precision as `withdraw`
+        )
+        .map_err(AmmError::from)?;
+
+        Ok(QuotedWithdraw {
+            amount_x: amounts
+                .x
+                .checked_sub(transfer_fee(&self.mint_x, amounts.x)?)
+                .ok_or(AmmError::Underflow)?,
+            amount_y: amounts
+                .y
+                .checked_sub(transfer_fee(&self.mint_y, amounts.y)?)
+                .ok_or(AmmError::Underflow)?,
+            lp_amount,
+        })
+    }
+
+    /// Returns the LP token's virtual price: the pool invariant per LP token
+    ///
+    /// Deposits and withdrawals leave it unchanged and swap fees grow it, so lending
+    /// protocols can price LP collateral from it without trusting the spot reserves.
+    ///
+    /// # Returns
+    /// * `Result<u128>` - Invariant per LP token in Q64.64 fixed point, also set as return data
+    pub fn lp_virtual_price(&self) -> Result<u128> {
+        let (config, reserve_x, reserve_y) = self.executed_config()?;
+        invariant_per_lp(&config, reserve_x, reserve_y, self.mint_lp.supply)
+    }
+
+    /// Fails the same way the quoted instructions do for pools that cannot be traded right now
+    fn check_tradeable(&self) -> Result<()> {
+        // Ensure the pool is not locked
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        Ok(())
+    }
+
+    /// Returns a copy of the config as the quoted instruction would see it, with
+    /// long-term orders executed, volatility decayed and weights moved up to now,
+    /// along with the LP reserves
+    ///
+    /// The config account itself is left untouched, so quoting changes nothing.
+    fn executed_config(&self) -> Result<(Config, u64, u64)> {
+        let mut config = (*self.config).clone();
+        // Executed on the copy only, so no `ExecuteOrdersEvent` is emitted
+        let now = Clock::get()?.unix_timestamp;
+        config.execute_virtual_orders_to(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply, now)?;
+        config.decay_volatility()?;
+        config.update_weights()?;
+        let (reserve_x, reserve_y) = config.lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+        Ok((config, reserve_x, reserve_y))
+    }
+}
@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::errors::AmmError;
+use crate::events::CloseOrderEvent;
+use crate::states::{Config, Order};
+
+/// Accounts required for settling an expired long-term order on its owner's behalf
+/// Anyone may settle, so orders left behind by their owners cannot hold expiry slots
+#[derive(Accounts)]
+pub struct SettleOrder<'info> {
+    /// The user settling the order (must sign the transaction)
+    /// Mutable because they pay for the owner's ATA creation if it doesn't exist
+    #[account(mut)]
+    pub settler: Signer<'info>,
+
+    /// The order owner, who receives the proceeds and the order account's rent
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    /// The mint account for token X in the trading pair
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
+
+    /// The mint account for token Y in the trading pair
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    /// The AMM pool configuration account
+    /// Mutable because long-term orders are executed and the order book updated
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,  // Ensures mint_x matches the one in config
+        has_one = mint_y,  // Ensures mint_y matches the one in config
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The LP (Liquidity Provider) token mint
+    /// Read for the LP supply when executing orders
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: InterfaceAccount<'info, Mint>,
+
+    /// The vault that holds all deposited token X
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// The vault that holds all deposited token Y
+    /// Associated token account owned by the config PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token X
+    /// Will be created if it doesn't exist, settler pays for creation
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x
+    )]
+    pub owner_ata_x: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner's token account for token Y
+    /// Will be created if it doesn't exist, settler pays for creation
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y
+    )]
+    pub owner_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// The expired order, closed with its rent refunded to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"order", config.key().as_ref(), owner.key().as_ref(), order.id.to_le_bytes().as_ref()],
+        bump = order.bump,
+        has_one = owner,   // Proceeds and rent go to the order's owner
+        has_one = config,  // Order must belong to this pool
+    )]
+    pub order: Account<'info, Order>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleOrder<'info> {
+    /// Pays an expired order's final proceeds to its owner and closes it
+    ///
+    /// Does for the owner what `cancel_order` does after expiry, freeing the order's
+    /// expiry slot once every order on it is closed. Running orders cannot be settled,
+    /// since only their owner may stop them.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn settle_order(&mut self) -> Result<()> {
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+        // Only orders that stopped selling are settled
+        require!(
+            self.config.twamm.last_execution_ts >= self.order.expiry_ts,
+            AmmError::OrderNotExpired
+        );
+
+        let proceeds = self.config.take_order_proceeds(&mut self.order)?;
+        // Expired orders have nothing left unsold
+        self.config.remove_order(&self.order)?;
+
+        if proceeds != 0 {
+            self.withdraw_token(!self.order.is_x, proceeds)?;
+        }
+
+        emit!(CloseOrderEvent {
+            pool: self.config.key(),
+            owner: self.owner.key(),
+            order: self.order.key(),
+            closed_by: self.settler.key(),
+            proceeds,
+            refund: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws tokens from the appropriate vault to the owner's account
+    ///
+    /// # Arguments
+    /// * `is_x` - true for token X, false for token Y
+    /// * `amount` - Amount of tokens to withdraw
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        // Select appropriate accounts based on token type
+        let (from, to, mint, decimals, cpi_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),          // Transfer from vault X
+                self.owner_ata_x.to_account_info(),      // Transfer to owner's X account
+                self.mint_x.to_account_info(),           // Token X mint
+                self.mint_x.decimals,                    // Token X decimals
+                self.token_program_x.to_account_info(),  // Token X program
+            ),
+            false => (
+                self.vault_y.to_account_info(),          // Transfer from vault Y
+                self.owner_ata_y.to_account_info(),      // Transfer to owner's Y account
+                self.mint_y.to_account_info(),           // Token Y mint
+                self.mint_y.decimals,                    // Token Y decimals
+                self.token_program_y.to_account_info(),  // Token Y program
+            ),
+        };
+
+        // Set up transfer instruction accounts
+        let cpi_accounts = TransferChecked {
+            from,
+            to,
+            mint,
+            authority: self.config.to_account_info(),  // Config PDA signs the transfer
+        };
+
+        // Create signer seeds for config PDA
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"config",
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ]];
+
+        // Create CPI context with PDA signer
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        // Execute the transfer with amount and decimal validation
+        transfer_checked(cpi_context, amount, decimals)
+    }
+}
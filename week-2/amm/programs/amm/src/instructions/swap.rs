@@ -1,12 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
-use constant_product_curve::{ConstantProduct, LiquidityPair};
 
+use crate::curves::{swap_exact_in, swap_exact_out};
 use crate::errors::AmmError;
-use crate::states::Config;
+use crate::events::SwapEvent;
+use crate::states::{Config, Permission, Role};
+use crate::utils::{amount_with_transfer_fee, check_deadline, transfer_fee};
 
 /// Accounts required for performing token swaps in the AMM pool
 /// This struct defines all the accounts needed to execute a swap operation
@@ -20,18 +22,21 @@ pub struct Swap<'info> {
 
     /// The mint account for token X in the trading pair
     /// Immutable as we only need to read mint information for transfers
-    #[account(mint::token_program = token_program)]
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
 
     /// The mint account for token Y in the trading pair
     /// Immutable as we only need to read mint information for transfers
-    #[account(mint::token_program = token_program)]
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
 
     /// The AMM pool configuration account
     /// Contains pool settings, fees, and references to the token mints
+    /// Mutable because the protocol's share of the swap fee and the TWAP
+    /// price accumulators are updated here
     /// Uses PDA derived from "config" seed and config.seed
     #[account(
+        mut,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         has_one = mint_x,  // Ensures mint_x matches the one in config
@@ -46,7 +51,7 @@ pub struct Swap<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: InterfaceAccount<'info, Mint>,
 
     /// The vault that holds all deposited token X
     /// Mutable because swap operations either deposit to or withdraw from this vault
@@ -55,9 +60,9 @@ pub struct Swap<'info> {
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
 
     /// The vault that holds all deposited token Y
     /// Mutable because swap operations either deposit to or withdraw from this vault
@@ -66,9 +71,9 @@ pub struct Swap<'info> {
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for token X
     /// Will be created if it doesn't exist, user pays for creation
@@ -78,9 +83,9 @@ pub struct Swap<'info> {
         payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_ata_x: Account<'info, TokenAccount>,
+    pub user_ata_x: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for token Y
     /// Will be created if it doesn't exist, user pays for creation
@@ -90,12 +95,28 @@ pub struct Swap<'info> {
         payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
+    )]
+    pub user_ata_y: InterfaceAccount<'info, TokenAccount>,
+
+    /// The user's permission, required with the trader role if the pool is permissioned
+    /// Pass `None` for pools open to everyone
+    #[account(
+        seeds = [b"permission", config.key().as_ref(), user.key().as_ref()],
+        bump = permission.bump,
     )]
-    pub user_ata_y: Account<'info, TokenAccount>,
+    pub permission: Option<Account<'info, Permission>>,
 
-    /// SPL Token program for token operations
-    pub token_program: Program<'info, Token>,
+    /// CHECK: Oracle price update of a pool with a price guard, `None` otherwise
+    /// Matched against the guard and parsed in `Config::check_price_guard`
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
     /// Associated Token program for ATA operations
     pub associated_token_program: Program<'info, AssociatedToken>,
     /// System program for account creation
@@ -110,54 +131,235 @@ impl<'info> Swap<'info> {
     /// 2. Initialize constant product curve with current pool state
     /// 3. Calculate swap amounts using the curve (accounting for fees)
     /// 4. Validate slippage protection (output meets minimum requirement)
-    /// 5. Deposit input tokens to appropriate vault
-    /// 6. Withdraw output tokens from appropriate vault to user
+    /// 5. Check the post-trade price against the oracle if the pool has a price guard
+    /// 6. Deposit input tokens to appropriate vault
+    /// 7. Withdraw output tokens from appropriate vault to user
     /// 
     /// The constant product formula (x * y = k) ensures that the product of
     /// token reserves remains constant after accounting for fees.
     /// 
+    /// For Token-2022 mints with a transfer fee, the curve is priced on the amount the
+    /// vault actually receives, and `min_amount_out` is checked against the amount the
+    /// user actually receives.
+    /// 
     /// # Arguments
     /// * `is_x` - true if swapping token X for Y, false if swapping Y for X
     /// * `amount_in` - Amount of input tokens to swap
     /// * `min_amount_out` - Minimum amount of output tokens expected (slippage protection)
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
     /// 
     /// # Returns
     /// * `Result<()>` - Ok if successful, error otherwise
-    pub fn swap(&mut self, is_x: bool, amount_in: u64, min_amount_out: u64) -> Result<()> {
+    pub fn swap(&mut self, is_x: bool, amount_in: u64, min_amount_out: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
         // Ensure the pool is not locked for swaps
         require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the trader role
+        self.config.check_permission(self.permission.as_deref(), Role::Trader)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
         // Ensure user is swapping a positive amount
         require!(amount_in > 0, AmmError::InvalidAmount);
 
-        // Initialize constant product curve with current pool state
-        let mut curve = ConstantProduct::init(
-            self.vault_x.amount,    // Current token X reserves
-            self.vault_y.amount,    // Current token Y reserves
-            self.mint_lp.supply,    // Current LP token supply
-            self.config.fee,        // Trading fee in basis points
-            None,                   // No additional configuration
-        )
-        .map_err(AmmError::from)?;
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Exclude accrued protocol fees so they never take part in pricing
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-trade price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+        // Let volatility decay up to now before it prices this trade
+        self.config.decay_volatility()?;
+        // Weighted pools price with the weights in effect now
+        self.config.update_weights()?;
 
         // Determine which token is being swapped in
-        let p = match is_x {
-            true => LiquidityPair::X,   // Swapping X for Y
-            false => LiquidityPair::Y,  // Swapping Y for X
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),   // Swapping X for Y
+            false => (&self.mint_y, &self.mint_x),  // Swapping Y for X
         };
 
-        // Calculate swap amounts using constant product formula
+        // Price the trade on what the vault actually receives after any transfer fee
+        let amount_received = amount_in
+            .checked_sub(transfer_fee(mint_in, amount_in)?)
+            .ok_or(AmmError::Underflow)?;
+
+        // Calculate swap amounts using the pool's curve
         // This accounts for fees and maintains the invariant
-        let swap_result = curve
-            .swap(p, amount_in, min_amount_out)
-            .map_err(AmmError::from)?;
+        let quote = swap_exact_in(
+            &self.config,
+            reserve_x,              // Current token X reserves
+            reserve_y,              // Current token Y reserves
+            self.mint_lp.supply,    // Current LP token supply
+            is_x,
+            amount_received,
+        )?;
 
         // Validate that the calculated amounts are valid
-        require!(swap_result.deposit != 0, AmmError::InvalidAmount);
-        require!(swap_result.withdraw != 0, AmmError::InvalidAmount);
+        require!(quote.amount_in != 0, AmmError::InvalidAmount);
+        require!(quote.amount_out != 0, AmmError::InvalidAmount);
+
+        // Slippage protection on what the user actually receives after any transfer fee
+        let amount_out = quote
+            .amount_out
+            .checked_sub(transfer_fee(mint_out, quote.amount_out)?)
+            .ok_or(AmmError::Underflow)?;
+        require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+
+        // Set aside the protocol's share of the fee, which is paid in the input token
+        self.config.accrue_protocol_fee(is_x, quote.fee)?;
+
+        // Feed the price move of this trade into the dynamic fee
+        let reserves_after = post_trade_reserves(reserve_x, reserve_y, is_x, quote.amount_in, quote.amount_out);
+        self.config.record_price_move((reserve_x, reserve_y), reserves_after)?;
+
+        // Guarded pools must not be pushed away from the oracle price
+        self.config.check_price_guard(
+            self.oracle.as_deref(),
+            self.mint_x.decimals,
+            self.mint_y.decimals,
+            (reserve_x, reserve_y),
+            reserves_after,
+        )?;
 
         // Execute the swap by depositing input tokens and withdrawing output tokens
-        self.deposit_token(is_x, swap_result.deposit)?;      // Deposit input tokens
-        self.withdraw_token(!is_x, swap_result.withdraw)?;   // Withdraw output tokens
+        self.deposit_token(is_x, amount_in)?;                // Deposit input tokens
+        self.withdraw_token(!is_x, quote.amount_out)?;       // Withdraw output tokens
+
+        self.emit_swap_event(is_x, amount_in, quote.amount_out, quote.fee)
+    }
+
+    /// Exact-output swap: buys exactly `amount_out` tokens, spending at most `max_amount_in`
+    ///
+    /// The swap process follows these steps:
+    /// 1. Validate pool is not locked and amounts are valid
+    /// 2. Invert the pool's curve (fee included) to find the required input
+    /// 3. Validate slippage protection (input does not exceed the user's maximum)
+    /// 4. Check the post-trade price against the oracle if the pool has a price guard
+    /// 5. Deposit input tokens and withdraw exactly `amount_out` to the user
+    ///
+    /// For Token-2022 mints with a transfer fee, both legs are grossed up so the user
+    /// receives exactly `amount_out` and `max_amount_in` bounds what the user sends.
+    ///
+    /// # Arguments
+    /// * `is_x` - true if swapping token X for Y, false if swapping Y for X
+    /// * `amount_out` - Exact amount of output tokens the user wants to receive
+    /// * `max_amount_in` - Maximum amount of input tokens the user is willing to spend
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn swap_exact_out(&mut self, is_x: bool, amount_out: u64, max_amount_in: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
+        // Ensure the pool is not locked for swaps
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the trader role
+        self.config.check_permission(self.permission.as_deref(), Role::Trader)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Ensure user is buying a positive amount
+        require!(amount_out > 0, AmmError::InvalidAmount);
+
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Exclude accrued protocol fees so they never take part in pricing
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-trade price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+        // Let volatility decay up to now before it prices this trade
+        self.config.decay_volatility()?;
+        // Weighted pools price with the weights in effect now
+        self.config.update_weights()?;
+
+        // Order mints as (input, output) for the chosen direction
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),   // Selling X, buying Y
+            false => (&self.mint_y, &self.mint_x),  // Selling Y, buying X
+        };
+
+        // The vault must send enough for the user to receive `amount_out` after any transfer fee
+        let amount_withdrawn = amount_with_transfer_fee(mint_out, amount_out)?;
+
+        // Input the vault must receive, rounded against the trader
+        let quote = swap_exact_out(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            amount_withdrawn,
+        )?;
+
+        // Input the user must send for the vault to receive that much after any transfer fee
+        let amount_in = amount_with_transfer_fee(mint_in, quote.amount_in)?;
+
+        // Slippage protection: the trade must not cost more than the user's maximum
+        require!(amount_in <= max_amount_in, AmmError::SlippageExceeded);
+
+        // Set aside the protocol's share of the fee, which is paid in the input token
+        self.config.accrue_protocol_fee(is_x, quote.fee)?;
+
+        // Feed the price move of this trade into the dynamic fee
+        let reserves_after = post_trade_reserves(reserve_x, reserve_y, is_x, quote.amount_in, amount_withdrawn);
+        self.config.record_price_move((reserve_x, reserve_y), reserves_after)?;
+
+        // Guarded pools must not be pushed away from the oracle price
+        self.config.check_price_guard(
+            self.oracle.as_deref(),
+            self.mint_x.decimals,
+            self.mint_y.decimals,
+            (reserve_x, reserve_y),
+            reserves_after,
+        )?;
+
+        // Any rounding surplus of the forward formula stays in the pool
+        self.deposit_token(is_x, amount_in)?;              // Deposit input tokens
+        self.withdraw_token(!is_x, amount_withdrawn)?;     // Withdraw exactly the requested output
+
+        self.emit_swap_event(is_x, amount_in, amount_withdrawn, quote.fee)
+    }
+
+    /// Emits a `SwapEvent` describing the trade and the pool state after it
+    ///
+    /// # Arguments
+    /// * `is_x` - true if token X was sold for Y, false if Y was sold for X
+    /// * `amount_in` - Amount the user sent, transfer fee included
+    /// * `amount_out` - Amount the pool sent
+    /// * `fee` - Swap fee charged in the input token
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn emit_swap_event(&mut self, is_x: bool, amount_in: u64, amount_out: u64, fee: u64) -> Result<()> {
+        // Vault balances are stale after the transfers
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        emit!(SwapEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in,
+            amount_out,
+            fee,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -173,23 +375,23 @@ impl<'info> Swap<'info> {
     /// * `Result<()>` - Ok if successful, error otherwise
     pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
         // Select appropriate accounts based on token type
-        let (from, to, mint, decimals) = match is_x {
+        let (from, to, mint, decimals, cpi_program) = match is_x {
             true => (
                 self.user_ata_x.to_account_info(),    // Transfer from user's X account
                 self.vault_x.to_account_info(),       // Transfer to vault X
                 self.mint_x.to_account_info(),        // Token X mint
                 self.mint_x.decimals,                 // Token X decimals
+                self.token_program_x.to_account_info(), // Token X program
             ),
             false => (
                 self.user_ata_y.to_account_info(),    // Transfer from user's Y account
                 self.vault_y.to_account_info(),       // Transfer to vault Y
                 self.mint_y.to_account_info(),        // Token Y mint
                 self.mint_y.decimals,                 // Token Y decimals
+                self.token_program_y.to_account_info(), // Token Y program
             ),
         };
 
-        let cpi_program = self.token_program.to_account_info();
-
         // Set up transfer instruction accounts
         let cpi_accounts = TransferChecked {
             from,
@@ -215,23 +417,23 @@ impl<'info> Swap<'info> {
     /// * `Result<()>` - Ok if successful, error otherwise
     pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
         // Select appropriate accounts based on token type
-        let (from, to, mint, decimals) = match is_x {
+        let (from, to, mint, decimals, cpi_program) = match is_x {
             true => (
                 self.vault_x.to_account_info(),       // Transfer from vault X
                 self.user_ata_x.to_account_info(),    // Transfer to user's X account
                 self.mint_x.to_account_info(),        // Token X mint
                 self.mint_x.decimals,                 // Token X decimals
+                self.token_program_x.to_account_info(), // Token X program
             ),
             false => (
                 self.vault_y.to_account_info(),       // Transfer from vault Y
                 self.user_ata_y.to_account_info(),    // Transfer to user's Y account
                 self.mint_y.to_account_info(),        // Token Y mint
                 self.mint_y.decimals,                 // Token Y decimals
+                self.token_program_y.to_account_info(), // Token Y program
             ),
         };
 
-        let cpi_program = self.token_program.to_account_info();
-
         // Set up transfer instruction accounts
         let cpi_accounts = TransferChecked {
             from,
@@ -253,4 +455,19 @@ impl<'info> Swap<'info> {
         // Execute the transfer with amount and decimal validation
         transfer_checked(cpi_context, amount, decimals)
     }
+}
+
+/// Returns the LP reserves after a trade, ignoring the protocol's cut of the fee
+///
+/// # Arguments
+/// * `reserve_x` - LP reserves of token X before the trade
+/// * `reserve_y` - LP reserves of token Y before the trade
+/// * `is_x` - true if token X was sold for Y, false if Y was sold for X
+/// * `amount_in` - Amount the vault received
+/// * `amount_out` - Amount the vault paid out
+fn post_trade_reserves(reserve_x: u64, reserve_y: u64, is_x: bool, amount_in: u64, amount_out: u64) -> (u64, u64) {
+    match is_x {
+        true => (reserve_x.saturating_add(amount_in), reserve_y.saturating_sub(amount_out)),
+        false => (reserve_x.saturating_sub(amount_out), reserve_y.saturating_add(amount_in)),
+    }
 }
\ No newline at end of file
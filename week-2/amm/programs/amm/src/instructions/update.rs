@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::events::{PoolUpdate, UpdatePoolEvent};
+use crate::states::{Config, DynamicFee, PriceGuard};
+
+/// Accounts required for administering an existing AMM pool
+/// This struct is shared by every authority-gated instruction that mutates
+/// the pool configuration (fees, lock state and the authority itself)
+#[derive(Accounts)]
+pub struct Update<'info> {
+    /// The current pool authority (must sign the transaction)
+    /// Checked against `config.authority` before any change is applied
+    pub authority: Signer<'info>,
+
+    /// The AMM pool configuration account
+    /// Mutable because the admin instructions update its fields
+    /// Uses PDA derived from "config" seed and config.seed
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> Update<'info> {
+    /// Updates the trading fee charged on every swap
+    ///
+    /// The fee tier the pool was created with stays in its `PoolEntry`, which keys the
+    /// canonical PDA, so retuning the fee never frees or takes another tier's slot.
+    ///
+    /// # Arguments
+    /// * `fee` - New trading fee in basis points (e.g., 30 = 0.30%)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        // Only the pool authority may retune fees
+        self.config.check_authority(&self.authority.key())?;
+        // A fee of 100% or more would swallow the whole trade
+        require!(fee < 10_000, AmmError::InvalidFee);
+        // The base fee must stay within the dynamic fee caps
+        if let Some(dynamic_fee) = &self.config.dynamic_fee {
+            require!(
+                (dynamic_fee.min_fee..=dynamic_fee.max_fee).contains(&fee),
+                AmmError::InvalidDynamicFee
+            );
+        }
+
+        self.config.fee = fee;
+
+        self.emit_update(PoolUpdate::Fee(fee))
+    }
+
+    /// Updates the protocol's share of every swap fee
+    ///
+    /// Only fees charged after this call are affected; fees already accrued
+    /// stay with whoever they were credited to.
+    ///
+    /// # Arguments
+    /// * `protocol_fee` - Protocol share of the swap fee in basis points (e.g., 1667 ≈ 1/6)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn update_protocol_fee(&mut self, protocol_fee: u16) -> Result<()> {
+        // Only the pool authority may change the protocol cut
+        self.config.check_authority(&self.authority.key())?;
+        // The protocol cannot take more than the whole fee
+        require!(protocol_fee <= 10_000, AmmError::InvalidFee);
+
+        self.config.protocol_fee = protocol_fee;
+
+        self.emit_update(PoolUpdate::ProtocolFee(protocol_fee))
+    }
+
+    /// Enables dynamic fees, or retunes them if already enabled
+    ///
+    /// Swaps then pay the base `fee` plus a surcharge proportional to recent
+    /// price movement, which decays back to zero over `decay_period`. Volatility
+    /// already recorded is kept when retuning.
+    ///
+    /// # Arguments
+    /// * `min_fee` - Lowest fee swaps can be charged, at most the base fee (in basis points)
+    /// * `max_fee` - Highest fee swaps can be charged, at least the base fee (in basis points)
+    /// * `volatility_factor` - Basis points of fee added per 10_000 basis points of volatility
+    /// * `decay_period` - Seconds it takes for volatility to decay back to zero
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_dynamic_fee(
+        &mut self,
+        min_fee: u16,
+        max_fee: u16,
+        volatility_factor: u16,
+        decay_period: u32,
+    ) -> Result<()> {
+        // Only the pool authority may retune fees
+        self.config.check_authority(&self.authority.key())?;
+        // A fee of 100% or more would swallow the whole trade
+        require!(max_fee < 10_000, AmmError::InvalidFee);
+        // The caps must bracket the base fee, and volatility must be able to decay
+        require!(
+            min_fee <= self.config.fee && self.config.fee <= max_fee && decay_period > 0,
+            AmmError::InvalidDynamicFee
+        );
+
+        let (volatility, last_update_ts) = match &self.config.dynamic_fee {
+            Some(dynamic_fee) => (dynamic_fee.volatility, dynamic_fee.last_update_ts),
+            None => (0, Clock::get()?.unix_timestamp),
+        };
+
+        self.config.dynamic_fee = Some(DynamicFee {
+            min_fee,
+            max_fee,
+            volatility_factor,
+            decay_period,
+            volatility,
+            last_update_ts,
+        });
+
+        self.emit_update(PoolUpdate::DynamicFee(self.config.dynamic_fee))
+    }
+
+    /// Disables dynamic fees, so swaps are charged the base fee again
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn disable_dynamic_fee(&mut self) -> Result<()> {
+        // Only the pool authority may retune fees
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.dynamic_fee = None;
+
+        self.emit_update(PoolUpdate::DynamicFee(None))
+    }
+
+    /// Guards swaps with an oracle price, or retunes the guard if already set
+    ///
+    /// Swaps must then pass the oracle account, whose price must be fresh, and
+    /// may not leave the pool price further than `max_deviation_bps` from it.
+    ///
+    /// # Arguments
+    /// * `oracle` - Pyth price update account quoting the pair
+    /// * `max_deviation_bps` - Band around the oracle price swaps must stay within (in basis points)
+    /// * `max_age` - Oldest oracle price accepted, in seconds
+    /// * `inverted` - true if the feed quotes token Y in token X rather than X in Y
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_price_guard(
+        &mut self,
+        oracle: Pubkey,
+        max_deviation_bps: u16,
+        max_age: u32,
+        inverted: bool,
+    ) -> Result<()> {
+        // Only the pool authority may change what prices the pool trades at
+        self.config.check_authority(&self.authority.key())?;
+
+        let price_guard = PriceGuard {
+            oracle,
+            max_deviation_bps,
+            max_age,
+            inverted,
+        };
+        price_guard.validate()?;
+
+        self.config.price_guard = Some(price_guard);
+
+        self.emit_update(PoolUpdate::PriceGuard(Some(price_guard)))
+    }
+
+    /// Removes the price guard, so swaps may trade at any price again
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn disable_price_guard(&mut self) -> Result<()> {
+        // Only the pool authority may change what prices the pool trades at
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.price_guard = None;
+
+        self.emit_update(PoolUpdate::PriceGuard(None))
+    }
+
+    /// Restricts the pool to allowlisted users, or opens it to everyone again
+    ///
+    /// While permissioned, deposits and withdrawals require the LP role and swaps,
+    /// flash loans and long-term orders the trader role, granted with `set_permission`.
+    ///
+    /// # Arguments
+    /// * `permissioned` - true to require permissions, false to open the pool
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_permissioned(&mut self, permissioned: bool) -> Result<()> {
+        // Only the pool authority may decide who can use the pool
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.permissioned = permissioned;
+
+        self.emit_update(PoolUpdate::Permissioned(permissioned))
+    }
+
+    /// Locks the pool, halting deposits, withdrawals and swaps
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn lock(&mut self) -> Result<()> {
+        // Only the pool authority may halt the pool
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.locked = true;
+
+        self.emit_update(PoolUpdate::Locked(true))
+    }
+
+    /// Unlocks the pool, resuming deposits, withdrawals and swaps
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn unlock(&mut self) -> Result<()> {
+        // Only the pool authority may resume the pool
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.locked = false;
+
+        self.emit_update(PoolUpdate::Locked(false))
+    }
+
+    /// Hands the pool authority over to a new account
+    ///
+    /// # Arguments
+    /// * `new_authority` - Account that will control the pool from now on
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_authority(&mut self, new_authority: Pubkey) -> Result<()> {
+        // Only the current authority may rotate itself
+        self.config.check_authority(&self.authority.key())?;
+
+        self.config.authority = Some(new_authority);
+
+        self.emit_update(PoolUpdate::Authority(Some(new_authority)))
+    }
+
+    /// Permanently removes the pool authority
+    ///
+    /// After this call the pool becomes fully decentralized: fees and the
+    /// lock state can never be changed again.
+    ///
+    /// Nobody can call `collect_protocol_fees` on a pool without an authority, so
+    /// the protocol fees must be collected and the protocol fee set to 0 first.
+    /// Otherwise they would be stranded in the vaults for good.
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn renounce_authority(&mut self) -> Result<()> {
+        // Only the current authority may give up control
+        self.config.check_authority(&self.authority.key())?;
+        // Fees already accrued could never be collected afterwards
+        require!(
+            self.config.protocol_fees_x == 0 && self.config.protocol_fees_y == 0,
+            AmmError::ProtocolFeesOutstanding
+        );
+        // Nor could fees accrued by later swaps
+        require!(self.config.protocol_fee == 0, AmmError::ProtocolFeesOutstanding);
+
+        self.config.authority = None;
+
+        self.emit_update(PoolUpdate::Authority(None))
+    }
+
+    /// Emits an `UpdatePoolEvent` recording a change the authority just made
+    ///
+    /// # Arguments
+    /// * `update` - What changed, with the new value
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    fn emit_update(&self, update: PoolUpdate) -> Result<()> {
+        emit!(UpdatePoolEvent {
+            pool: self.config.key(),
+            authority: self.authority.key(),
+            update,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::states::Factory;
+
+/// Accounts required for administering the global pool factory
+#[derive(Accounts)]
+pub struct UpdateFactory<'info> {
+    /// The factory admin (must sign the transaction)
+    pub admin: Signer<'info>,
+
+    /// The global factory account
+    #[account(
+        mut,
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = admin @ AmmError::InvalidFactoryAdmin,
+    )]
+    pub factory: Account<'info, Factory>,
+}
+
+impl<'info> UpdateFactory<'info> {
+    /// Replaces the set of fee tiers new pools may be created with
+    /// Existing pools keep the fee tier they were created with
+    ///
+    /// # Arguments
+    /// * `fee_tiers` - Trading fees in basis points, ascending
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn update_fee_tiers(&mut self, fee_tiers: Vec<u16>) -> Result<()> {
+        Factory::check_fee_tiers(&fee_tiers)?;
+
+        self.factory.fee_tiers = fee_tiers;
+
+        Ok(())
+    }
+
+    /// Hands the factory over to a new admin
+    ///
+    /// # Arguments
+    /// * `new_admin` - Public key of the new factory admin
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn set_factory_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        self.factory.admin = new_admin;
+
+        Ok(())
+    }
+}
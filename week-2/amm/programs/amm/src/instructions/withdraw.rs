@@ -1,12 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{burn, transfer_checked, Burn, Mint, Token, TokenAccount, TransferChecked},
+    token_interface::{burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 use constant_product_curve::ConstantProduct;
 
+use crate::curves::single_sided_withdraw;
 use crate::errors::AmmError;
-use crate::states::Config;
+use crate::events::WithdrawEvent;
+use crate::states::{Config, LpPosition, Permission, Role};
+use crate::utils::{check_deadline, transfer_fee};
 
 /// Accounts required for withdrawing liquidity from the AMM pool
 /// This struct defines all the accounts needed to perform a liquidity withdrawal operation
@@ -18,18 +21,20 @@ pub struct Withdraw<'info> {
 
     /// The mint account for token X in the trading pair
     /// Immutable as we only need to read mint information for transfers
-    #[account(mint::token_program = token_program)]
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: InterfaceAccount<'info, Mint>,
 
     /// The mint account for token Y in the trading pair
     /// Immutable as we only need to read mint information for transfers
-    #[account(mint::token_program = token_program)]
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: InterfaceAccount<'info, Mint>,
 
     /// The AMM pool configuration account
     /// Contains pool settings and references to the token mints
+    /// Mutable because the TWAP price accumulators are updated on every withdrawal
     /// Uses PDA derived from "config" seed and config.seed
     #[account(
+        mut,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         has_one = mint_x,  // Ensures mint_x matches the one in config
@@ -45,7 +50,7 @@ pub struct Withdraw<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: InterfaceAccount<'info, Mint>,
 
     /// The vault that holds all deposited token X
     /// Mutable because we're withdrawing tokens from it
@@ -54,9 +59,9 @@ pub struct Withdraw<'info> {
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
 
     /// The vault that holds all deposited token Y
     /// Mutable because we're withdrawing tokens from it
@@ -65,29 +70,33 @@ pub struct Withdraw<'info> {
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for token X
+    /// Will be created if it doesn't exist, user pays for creation
     /// Mutable because we're transferring tokens to it
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_ata_x: Account<'info, TokenAccount>,
+    pub user_ata_x: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for token Y
+    /// Will be created if it doesn't exist, user pays for creation
     /// Mutable because we're transferring tokens to it
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
     )]
-    pub user_ata_y: Account<'info, TokenAccount>,
+    pub user_ata_y: InterfaceAccount<'info, TokenAccount>,
 
     /// User's token account for LP tokens
     /// Will be created if it doesn't exist, user pays for creation
@@ -99,10 +108,35 @@ pub struct Withdraw<'info> {
         associated_token::authority = user,
         associated_token::token_program = token_program
     )]
-    pub user_ata_lp: Account<'info, TokenAccount>,
+    pub user_ata_lp: InterfaceAccount<'info, TokenAccount>,
+
+    /// The user's permission, required with the LP role if the pool is permissioned
+    /// Pass `None` for pools open to everyone
+    #[account(
+        seeds = [b"permission", config.key().as_ref(), user.key().as_ref()],
+        bump = permission.bump,
+    )]
+    pub permission: Option<Account<'info, Permission>>,
 
-    /// SPL Token program for token operations
-    pub token_program: Program<'info, Token>,
+    /// The user's liquidity position, shrunk by the LP tokens burned
+    /// Pass `None` to withdraw without updating a position
+    #[account(
+        mut,
+        seeds = [b"lp_position", config.key().as_ref(), user.key().as_ref()],
+        bump = lp_position.bump,
+    )]
+    pub lp_position: Option<Account<'info, LpPosition>>,
+
+    /// CHECK: Oracle price update of a pool with a price guard, `None` otherwise
+    /// Only read by `withdraw_single`, whose internal swap moves the pool price
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// Token program owning mint X (SPL Token or Token-2022)
+    pub token_program_x: Interface<'info, TokenInterface>,
+    /// Token program owning mint Y (SPL Token or Token-2022)
+    pub token_program_y: Interface<'info, TokenInterface>,
+    /// Token program for the LP mint
+    pub token_program: Interface<'info, TokenInterface>,
     /// Associated Token program for ATA operations
     pub associated_token_program: Program<'info, AssociatedToken>,
     /// System program for account creation
@@ -119,23 +153,45 @@ impl<'info> Withdraw<'info> {
     /// 4. Burn the LP tokens from user's account
     /// 5. Transfer proportional amounts of both tokens to user
     /// 
+    /// For Token-2022 mints with a transfer fee, `min_x`/`min_y` are checked against
+    /// the amounts the user actually receives after the fee is withheld.
+    /// 
     /// # Arguments
     /// * `amount` - Amount of LP tokens to burn
     /// * `min_x` - Minimum amount of token X user expects to receive
     /// * `min_y` - Minimum amount of token Y user expects to receive
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
     /// 
     /// # Returns
     /// * `Result<()>` - Ok if successful, error otherwise
-    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
         // Ensure the pool is not locked for withdrawals
         require!(self.config.locked == false, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the LP role
+        self.config.check_permission(self.permission.as_deref(), Role::Lp)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
         // Ensure user is requesting to burn some LP tokens
         require!(amount != 0, AmmError::InvalidAmount);
 
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Only LP-owned reserves count; accrued protocol fees are not claimable by LPs
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-withdrawal price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+
         // Calculate token amounts to withdraw based on current pool state
         let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
+            && reserve_x == 0
+            && reserve_y == 0
         {
             // Edge case: if pool is completely empty, use minimum amounts
             // This shouldn't happen in normal operation but provides safety
@@ -143,8 +199,8 @@ impl<'info> Withdraw<'info> {
             // Normal case: calculate proportional amounts based on LP token share
             false => {
                 let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
-                    self.vault_x.amount,   // Current vault X balance
-                    self.vault_y.amount,   // Current vault Y balance
+                    reserve_x,             // Current LP reserves of token X
+                    reserve_y,             // Current LP reserves of token Y
                     self.mint_lp.supply,   // Current LP token supply
                     amount,                // LP tokens being burned
                     6,                     // Precision for calculations
@@ -154,15 +210,190 @@ impl<'info> Withdraw<'info> {
             }
         };
 
-        // Slippage protection: ensure calculated amounts meet user's minimum requirements
-        require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+        // Amounts that actually reach the user once any transfer fee is withheld
+        let received_x = x
+            .checked_sub(transfer_fee(&self.mint_x, x)?)
+            .ok_or(AmmError::Underflow)?;
+        let received_y = y
+            .checked_sub(transfer_fee(&self.mint_y, y)?)
+            .ok_or(AmmError::Underflow)?;
+
+        // Slippage protection: ensure received amounts meet user's minimum requirements
+        require!(received_x >= min_x && received_y >= min_y, AmmError::SlippageExceeded);
 
         // Burn LP tokens from user's account first
         self.burn_lp_tokens(amount)?;
+        self.record_position(amount)?;
 
         // Transfer calculated amounts of both tokens to user
         self.withdraw_tokens(x, true)?;   // Transfer token X
-        self.withdraw_tokens(y, false)    // Transfer token Y
+        self.withdraw_tokens(y, false)?;  // Transfer token Y
+
+        self.emit_withdraw_event(x, y, amount, 0, 0)
+    }
+
+    /// Single-sided withdrawal: burns LP tokens and pays out only one token
+    ///
+    /// The pool's curve prices the withdrawal as if both tokens were withdrawn
+    /// proportionally and the other leg swapped into the requested token. The
+    /// internal swap pays the regular swap fee, including the protocol's share.
+    ///
+    /// Slippage is checked once, on the amount the user actually receives for the
+    /// whole operation (after any transfer fee).
+    ///
+    /// # Arguments
+    /// * `is_x` - true to receive token X, false to receive token Y
+    /// * `lp_amount` - Amount of LP tokens to burn
+    /// * `min_out` - Minimum amount of the token the user expects to receive
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn withdraw_single(&mut self, is_x: bool, lp_amount: u64, min_out: u64, expires_at: i64) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        check_deadline(expires_at)?;
+        // Ensure the pool is not locked for withdrawals
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Permissioned pools only admit users holding the LP role
+        self.config.check_permission(self.permission.as_deref(), Role::Lp)?;
+        // Reserves are not final while a flash loan is outstanding
+        require!(self.config.flash_loan.is_none(), AmmError::FlashLoanInProgress);
+        // Ensure user is requesting to burn some LP tokens
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+
+        // Execute long-term orders up to now before anything reads the reserves
+        self.config
+            .execute_virtual_orders(self.vault_x.amount, self.vault_y.amount, self.mint_lp.supply)?;
+
+        // Only LP-owned reserves count; accrued protocol fees are not claimable by LPs
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Record the pre-withdrawal price in the TWAP oracle
+        self.config.update_price_accumulators(reserve_x, reserve_y)?;
+        // The internal swap is charged the current dynamic fee
+        self.config.decay_volatility()?;
+        // Weighted pools price with the weights in effect now
+        self.config.update_weights()?;
+
+        let quote = single_sided_withdraw(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            lp_amount,
+        )?;
+        require!(quote.amount != 0, AmmError::InvalidAmount);
+
+        // Slippage protection on what the user actually receives after any transfer fee
+        let mint_out = match is_x {
+            true => &self.mint_x,
+            false => &self.mint_y,
+        };
+        let received = quote
+            .amount
+            .checked_sub(transfer_fee(mint_out, quote.amount)?)
+            .ok_or(AmmError::Underflow)?;
+        require!(received >= min_out, AmmError::SlippageExceeded);
+
+        // Set aside the protocol's share of the internal swap fee
+        self.config.accrue_protocol_fee(true, quote.fee_x)?;
+        self.config.accrue_protocol_fee(false, quote.fee_y)?;
+
+        // Reserves once the zap lands, for the dynamic fee and the price guard
+        let (vault_x, vault_y) = match is_x {
+            true => (self.vault_x.amount.checked_sub(quote.amount), Some(self.vault_y.amount)),
+            false => (Some(self.vault_x.amount), self.vault_y.amount.checked_sub(quote.amount)),
+        };
+        let reserves_after = self.config.lp_reserves(
+            vault_x.ok_or(AmmError::Underflow)?,
+            vault_y.ok_or(AmmError::Underflow)?,
+        )?;
+
+        // Feed the price move of the internal swap into the dynamic fee
+        self.config.record_price_move((reserve_x, reserve_y), reserves_after)?;
+
+        // Guarded pools must not be pushed away from the oracle price by the internal swap
+        self.config.check_price_guard(
+            self.oracle.as_deref(),
+            self.mint_x.decimals,
+            self.mint_y.decimals,
+            (reserve_x, reserve_y),
+            reserves_after,
+        )?;
+
+        // Burn LP tokens from user's account first
+        self.burn_lp_tokens(lp_amount)?;
+        self.record_position(lp_amount)?;
+
+        // Transfer the single token to user
+        self.withdraw_tokens(quote.amount, is_x)?;
+
+        let (amount_x, amount_y) = match is_x {
+            true => (quote.amount, 0),
+            false => (0, quote.amount),
+        };
+        self.emit_withdraw_event(amount_x, amount_y, lp_amount, quote.fee_x, quote.fee_y)
+    }
+
+    /// Records the withdrawal in the user's liquidity position, if one was passed
+    ///
+    /// # Arguments
+    /// * `lp_amount` - LP tokens burned by the user
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn record_position(&mut self, lp_amount: u64) -> Result<()> {
+        if let Some(lp_position) = self.lp_position.as_mut() {
+            lp_position.record_withdrawal(lp_amount, Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
+
+    /// Emits a `WithdrawEvent` describing the withdrawal and the pool state after it
+    ///
+    /// # Arguments
+    /// * `amount_x` - Amount of token X the pool sent
+    /// * `amount_y` - Amount of token Y the pool sent
+    /// * `lp_amount` - LP tokens burned by the user
+    /// * `fee_x` - Swap fee charged in token X
+    /// * `fee_y` - Swap fee charged in token Y
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn emit_withdraw_event(
+        &mut self,
+        amount_x: u64,
+        amount_y: u64,
+        lp_amount: u64,
+        fee_x: u64,
+        fee_y: u64,
+    ) -> Result<()> {
+        // Vault balances and LP supply are stale after the burn and transfers
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .lp_reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        emit!(WithdrawEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            fee_x,
+            fee_y,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
     }
 
     /// Burns LP tokens from the user's account
@@ -207,23 +438,23 @@ impl<'info> Withdraw<'info> {
     /// * `Result<()>` - Ok if successful, error otherwise
     pub fn withdraw_tokens(&mut self, amount: u64, is_x: bool) -> Result<()> {
         // Select appropriate accounts based on token type
-        let (from, to, mint, decimals) = match is_x {
+        let (from, to, mint, decimals, cpi_program) = match is_x {
             true => (
                 self.vault_x.to_account_info(),      // Transfer from vault X
                 self.user_ata_x.to_account_info(),   // Transfer to user's X account
                 self.mint_x.to_account_info(),       // Token X mint
                 self.mint_x.decimals,                // Token X decimals
+                self.token_program_x.to_account_info(), // Token X program
             ),
             false => (
                 self.vault_y.to_account_info(),      // Transfer from vault Y
                 self.user_ata_y.to_account_info(),   // Transfer to user's Y account
                 self.mint_y.to_account_info(),       // Token Y mint
                 self.mint_y.decimals,                // Token Y decimals
+                self.token_program_y.to_account_info(), // Token Y program
             ),
         };
 
-        let cpi_program = self.token_program.to_account_info();
-
         // Set up transfer instruction accounts
         let cpi_accounts = TransferChecked {
             from,
@@ -3,34 +3,331 @@ use anchor_lang::prelude::*;
 declare_id!("J3Y5M9uwFXxBo2bKqqd58v44pz2v7gLi8tupjWQLf6bF");
 
 pub mod constants;
+pub mod curves;
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod oracle;
+#[cfg(feature = "cpi")]
+pub mod return_data;
 pub mod states;
+pub mod utils;
 
 pub use instructions::*;
+pub use states::{CurveType, PositionValue, WeightSchedule};
 
 #[program]
 pub mod amm {
     use super::*;
 
+    pub fn initialize_factory(ctx: Context<InitializeFactory>, fee_tiers: Vec<u16>) -> Result<()> {
+        ctx.accounts.initialize_factory(fee_tiers, &ctx.bumps)
+    }
+
+    pub fn update_fee_tiers(ctx: Context<UpdateFactory>, fee_tiers: Vec<u16>) -> Result<()> {
+        ctx.accounts.update_fee_tiers(fee_tiers)
+    }
+
+    pub fn set_factory_admin(ctx: Context<UpdateFactory>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.set_factory_admin(new_admin)
+    }
+
     pub fn initialize(
         ctx: Context<Initialize>,
-        seed: u64,
         fee: u16,
         authority: Option<Pubkey>,
+        curve_type: CurveType,
+        amp: u64,
+        weights: Option<WeightSchedule>,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize(fee, authority, curve_type, amp, weights, &ctx.bumps)
+    }
+
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.deposit(amount, max_x, max_y, expires_at, &ctx.bumps)
+    }
+
+    pub fn withdraw(
+        ctx: Context<Withdraw>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.withdraw(amount, min_x, min_y, expires_at)
+    }
+
+    pub fn deposit_single(
+        ctx: Context<Deposit>,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .deposit_single(is_x, amount_in, min_lp_out, expires_at, &ctx.bumps)
+    }
+
+    pub fn withdraw_single(
+        ctx: Context<Withdraw>,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw_single(is_x, lp_amount, min_out, expires_at)
+    }
+
+    pub fn swap(
+        ctx: Context<Swap>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.swap(is_x, amount_in, min_amount_out, expires_at)
+    }
+
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap_exact_out(is_x, amount_out, max_amount_in, expires_at)
+    }
+
+    pub fn flash_borrow(ctx: Context<Flash>, amount_x: u64, amount_y: u64) -> Result<()> {
+        ctx.accounts.flash_borrow(amount_x, amount_y)
+    }
+
+    pub fn flash_repay(ctx: Context<Flash>, amount_x: u64, amount_y: u64) -> Result<()> {
+        ctx.accounts.flash_repay(amount_x, amount_y)
+    }
+
+    pub fn update_fee(ctx: Context<Update>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)
+    }
+
+    pub fn update_protocol_fee(ctx: Context<Update>, protocol_fee: u16) -> Result<()> {
+        ctx.accounts.update_protocol_fee(protocol_fee)
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<Update>,
+        min_fee: u16,
+        max_fee: u16,
+        volatility_factor: u16,
+        decay_period: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_dynamic_fee(min_fee, max_fee, volatility_factor, decay_period)
+    }
+
+    pub fn disable_dynamic_fee(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.disable_dynamic_fee()
+    }
+
+    pub fn set_price_guard(
+        ctx: Context<Update>,
+        oracle: Pubkey,
+        max_deviation_bps: u16,
+        max_age: u32,
+        inverted: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_price_guard(oracle, max_deviation_bps, max_age, inverted)
+    }
+
+    pub fn disable_price_guard(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.disable_price_guard()
+    }
+
+    pub fn set_permissioned(ctx: Context<Update>, permissioned: bool) -> Result<()> {
+        ctx.accounts.set_permissioned(permissioned)
+    }
+
+    pub fn set_permission(
+        ctx: Context<SetPermission>,
+        user: Pubkey,
+        lp: bool,
+        trader: bool,
+    ) -> Result<()> {
+        ctx.accounts.set_permission(user, lp, trader, &ctx.bumps)
+    }
+
+    pub fn revoke_permission(ctx: Context<RevokePermission>) -> Result<()> {
+        ctx.accounts.revoke_permission()
+    }
+
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.lock()
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.unlock()
+    }
+
+    pub fn set_authority(ctx: Context<Update>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.set_authority(new_authority)
+    }
+
+    pub fn renounce_authority(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.renounce_authority()
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        ctx.accounts.collect_protocol_fees()
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        ctx.accounts.close_pool()
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_cl_pool(seed, fee, tick_spacing, sqrt_price, &ctx.bumps)
+    }
+
+    pub fn open_cl_position(
+        ctx: Context<OpenClPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .open_cl_position(tick_lower, tick_upper, &ctx.bumps)
+    }
+
+    pub fn increase_cl_liquidity(
+        ctx: Context<ClLiquidity>,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .increase_cl_liquidity(liquidity, max_x, max_y, expires_at)
+    }
+
+    pub fn decrease_cl_liquidity(
+        ctx: Context<ClLiquidity>,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expires_at: i64,
     ) -> Result<()> {
-        ctx.accounts.initialize(seed, fee, authority, &ctx.bumps)
+        ctx.accounts
+            .decrease_cl_liquidity(liquidity, min_x, min_y, expires_at)
+    }
+
+    pub fn collect_cl_fees(ctx: Context<ClLiquidity>) -> Result<()> {
+        ctx.accounts.collect_cl_fees()
+    }
+
+    pub fn cl_swap(
+        ctx: Context<ClSwap>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit: u128,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .cl_swap(is_x, amount_in, min_amount_out, sqrt_price_limit, expires_at)
+    }
+
+    pub fn close_cl_position(ctx: Context<CloseClPosition>) -> Result<()> {
+        ctx.accounts.close_cl_position()
+    }
+
+    pub fn create_farm(
+        ctx: Context<CreateFarm>,
+        seed: u64,
+        reward_rate: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_farm(seed, reward_rate, start_ts, end_ts, &ctx.bumps)
+    }
+
+    pub fn extend_farm(ctx: Context<ManageFarm>, end_ts: i64, reward_rate: u64) -> Result<()> {
+        ctx.accounts.extend_farm(end_ts, reward_rate)
+    }
+
+    pub fn refund_farm(ctx: Context<ManageFarm>) -> Result<()> {
+        ctx.accounts.refund_farm()
+    }
+
+    pub fn stake(ctx: Context<FarmStake>, amount: u64) -> Result<()> {
+        ctx.accounts.stake(amount, &ctx.bumps)
+    }
+
+    pub fn unstake(ctx: Context<FarmStake>, amount: u64) -> Result<()> {
+        ctx.accounts.unstake(amount)
+    }
+
+    pub fn claim(ctx: Context<FarmStake>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        id: u64,
+        is_x: bool,
+        amount_in: u64,
+        duration: u32,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .place_order(id, is_x, amount_in, duration, expires_at, &ctx.bumps)
+    }
+
+    pub fn withdraw_order_proceeds(ctx: Context<ManageOrder>) -> Result<()> {
+        ctx.accounts.withdraw_order_proceeds()
+    }
+
+    pub fn cancel_order(ctx: Context<ManageOrder>) -> Result<()> {
+        ctx.accounts.cancel_order()
+    }
+
+    pub fn settle_order(ctx: Context<SettleOrder>) -> Result<()> {
+        ctx.accounts.settle_order()
+    }
+
+    pub fn lp_position_value(ctx: Context<LpPositionValue>) -> Result<PositionValue> {
+        ctx.accounts.lp_position_value()
+    }
+
+    pub fn quote_swap(ctx: Context<Quote>, is_x: bool, amount_in: u64) -> Result<QuotedSwap> {
+        ctx.accounts.quote_swap(is_x, amount_in)
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y)
+    pub fn quote_deposit(ctx: Context<Quote>, lp_amount: u64) -> Result<QuotedDeposit> {
+        ctx.accounts.quote_deposit(lp_amount)
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
-        ctx.accounts.withdraw(amount, min_x, min_y)
+    pub fn quote_withdraw(ctx: Context<Quote>, lp_amount: u64) -> Result<QuotedWithdraw> {
+        ctx.accounts.quote_withdraw(lp_amount)
     }
 
-    pub fn swap(ctx: Context<Swap>, is_x: bool, amount_in: u64, min_amount_out: u64) -> Result<()> {
-        ctx.accounts.swap(is_x, amount_in, min_amount_out)
+    pub fn lp_virtual_price(ctx: Context<Quote>) -> Result<u128> {
+        ctx.accounts.lp_virtual_price()
     }
 }
@@ -0,0 +1,224 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::U256;
+use crate::errors::AmmError;
+use crate::states::Config;
+
+/// Number of fractional bits used by the fixed-point prices (Q64.64)
+pub const PRICE_FRACTIONAL_BITS: u32 = 64;
+
+/// Snapshot of a pool's cumulative prices at a point in time
+///
+/// Two observations taken at different times give a time-weighted average
+/// price over the interval between them, which cannot be moved by trades
+/// made inside a single transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Observation {
+    /// Cumulative price of token X in units of token Y (Q64.64 * seconds)
+    pub price_x_cumulative: u128,
+    /// Cumulative price of token Y in units of token X (Q64.64 * seconds)
+    pub price_y_cumulative: u128,
+    /// Unix timestamp the cumulative prices were accumulated up to
+    pub timestamp: i64,
+}
+
+impl Observation {
+    /// Reads the observation stored in a pool's config
+    ///
+    /// The accumulators are only brought up to date when the pool is touched, so
+    /// for an observation valid at `now`, use [`Observation::current`] instead.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            price_x_cumulative: config.price_x_cumulative,
+            price_y_cumulative: config.price_y_cumulative,
+            timestamp: config.last_update_ts,
+        }
+    }
+
+    /// Extends the observation stored in a pool's config up to `now`
+    ///
+    /// Prices have not changed since the last update, so the current reserves are
+    /// accumulated over the elapsed time, exactly as the next pool touch would.
+    ///
+    /// # Arguments
+    /// * `config` - Pool configuration holding the accumulators
+    /// * `reserve_x` - Current LP reserves of token X
+    /// * `reserve_y` - Current LP reserves of token Y
+    /// * `now` - Unix timestamp to extend the observation to
+    ///
+    /// # Returns
+    /// * `Result<Observation>` - The observation at `now`, or an error if the curve cannot be priced
+    pub fn current(config: &Config, reserve_x: u64, reserve_y: u64, now: i64) -> Result<Self> {
+        let mut observation = Self::from_config(config);
+        observation.accumulate(config, reserve_x, reserve_y, now)?;
+        Ok(observation)
+    }
+
+    /// Accumulates the pool's marginal prices over the time elapsed since the last
+    /// observation, then moves the observation to `now`
+    ///
+    /// Accumulators wrap on overflow on purpose: averages are computed from the
+    /// wrapping difference of two observations, which stays correct as long as
+    /// the interval is shorter than a full wrap.
+    ///
+    /// # Arguments
+    /// * `config` - Pool configuration, whose curve prices the reserves
+    /// * `reserve_x` - LP reserves of token X during the elapsed interval
+    /// * `reserve_y` - LP reserves of token Y during the elapsed interval
+    /// * `now` - Current unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error if the curve cannot be priced
+    pub fn accumulate(
+        &mut self,
+        config: &Config,
+        reserve_x: u64,
+        reserve_y: u64,
+        now: i64,
+    ) -> Result<()> {
+        let elapsed = now.saturating_sub(self.timestamp);
+        // Empty pools have no price to record
+        if elapsed > 0 && reserve_x != 0 && reserve_y != 0 {
+            let elapsed = elapsed as u128;
+            let (price_x, price_y) = marginal_prices(config, reserve_x, reserve_y)?;
+            self.price_x_cumulative = self
+                .price_x_cumulative
+                .wrapping_add(price_x.wrapping_mul(elapsed));
+            self.price_y_cumulative = self
+                .price_y_cumulative
+                .wrapping_add(price_y.wrapping_mul(elapsed));
+        }
+        self.timestamp = self.timestamp.max(now);
+        Ok(())
+    }
+}
+
+/// Returns the marginal prices a pool's curve trades at, as Q64.64 fixed-point numbers
+///
+/// These are the prices of [`Config::pool_price`]: `y / x` for constant product
+/// pools, the slope of the invariant for StableSwap pools and `(y / w_y) / (x / w_x)`
+/// for weighted pools. Prices beyond the Q64.64 range saturate.
+///
+/// # Arguments
+/// * `config` - Pool configuration, whose curve prices the reserves
+/// * `reserve_x` - LP reserves of token X (must be non-zero)
+/// * `reserve_y` - LP reserves of token Y (must be non-zero)
+///
+/// # Returns
+/// * `Result<(u128, u128)>` - Price of X in Y and price of Y in X
+pub fn marginal_prices(config: &Config, reserve_x: u64, reserve_y: u64) -> Result<(u128, u128)> {
+    let (numerator, denominator) = config.pool_price(reserve_x, reserve_y)?;
+    require!(
+        !numerator.is_zero() && !denominator.is_zero(),
+        AmmError::NoLiquidityInPool
+    );
+    Ok((
+        to_fixed_point(numerator, denominator),
+        to_fixed_point(denominator, numerator),
+    ))
+}
+
+/// Returns `numerator / denominator` as a Q64.64 fixed-point number, saturating at `u128::MAX`
+fn to_fixed_point(numerator: U256, denominator: U256) -> u128 {
+    u128::try_from((numerator << PRICE_FRACTIONAL_BITS) / denominator).unwrap_or(u128::MAX)
+}
+
+/// Computes the time-weighted average prices between two observations
+///
+/// # Arguments
+/// * `start` - Earlier observation
+/// * `end` - Later observation
+///
+/// # Returns
+/// * `Option<(u128, u128)>` - Average price of X in Y and of Y in X (both Q64.64),
+///   or `None` if `end` is not strictly later than `start`
+pub fn time_weighted_average_price(start: &Observation, end: &Observation) -> Option<(u128, u128)> {
+    let elapsed = end.timestamp.checked_sub(start.timestamp)?;
+    if elapsed <= 0 {
+        return None;
+    }
+    let elapsed = elapsed as u128;
+
+    let price_x = end
+        .price_x_cumulative
+        .wrapping_sub(start.price_x_cumulative)
+        / elapsed;
+    let price_y = end
+        .price_y_cumulative
+        .wrapping_sub(start.price_y_cumulative)
+        / elapsed;
+
+    Some((price_x, price_y))
+}
+
+/// Program owning Pyth price update accounts (the Pyth Solana receiver)
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LyFJ");
+
+/// Mirror of the Pyth receiver's `PriceUpdateV2` account
+///
+/// Only the layout is reproduced, so price guards can read Pyth prices without
+/// depending on the Pyth SDK.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceUpdate {
+    /// Account allowed to overwrite the update
+    pub write_authority: Pubkey,
+    /// How many Wormhole guardian signatures the update was verified with
+    pub verification_level: VerificationLevel,
+    /// The price itself
+    pub price_message: PriceFeedMessage,
+    /// Slot the update was posted at
+    pub posted_slot: u64,
+}
+
+/// Verification level of a Pyth price update
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationLevel {
+    /// Verified with fewer signatures than the guardian quorum
+    Partial { num_signatures: u8 },
+    /// Verified with the full guardian quorum
+    Full,
+}
+
+/// Price of a Pyth feed at a point in time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceFeedMessage {
+    /// Identifier of the feed, e.g. SOL/USD
+    pub feed_id: [u8; 32],
+    /// Price, scaled by `10^exponent`
+    pub price: i64,
+    /// Confidence interval around the price, scaled by `10^exponent`
+    pub conf: u64,
+    /// Decimal exponent of `price` and `conf`
+    pub exponent: i32,
+    /// Unix timestamp the price was published at
+    pub publish_time: i64,
+    /// Unix timestamp of the previous price
+    pub prev_publish_time: i64,
+    /// Exponential moving average of the price
+    pub ema_price: i64,
+    /// Exponential moving average of the confidence interval
+    pub ema_conf: u64,
+}
+
+impl PriceUpdate {
+    /// Anchor discriminator of `PriceUpdateV2` accounts
+    pub const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+    /// Reads a price update account
+    ///
+    /// # Arguments
+    /// * `account` - Account owned by the Pyth receiver
+    ///
+    /// # Returns
+    /// * `Result<PriceUpdate>` - The update, or `InvalidOracle` if the account is not one
+    pub fn try_from_account(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account.owner, PYTH_RECEIVER_ID, AmmError::InvalidOracle);
+
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == Self::DISCRIMINATOR,
+            AmmError::InvalidOracle
+        );
+        Self::deserialize(&mut &data[8..]).map_err(|_| error!(AmmError::InvalidOracle))
+    }
+}
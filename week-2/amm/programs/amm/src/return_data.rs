@@ -0,0 +1,75 @@
+//! Quotes for programs calling the AMM through CPI
+//!
+//! The quote instructions return their result as Borsh-encoded return data. The
+//! functions here make the CPI and read that data with Anchor's `Return::get`,
+//! after checking it was set by the AMM program, so callers get a typed result or
+//! an `InvalidReturnData` error. Available with the `cpi` feature.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::get_return_data;
+
+use crate::cpi::{accounts::Quote, Return};
+use crate::errors::AmmError;
+use crate::{QuotedDeposit, QuotedSwap, QuotedWithdraw};
+
+/// Reads the result of an AMM CPI with `Return::get`
+///
+/// `Return::get` takes whatever return data was set last; this first checks that
+/// it exists and was set by the AMM program rather than by a program it called.
+fn checked_get<T: AnchorDeserialize>(result: Return<T>) -> Result<T> {
+    let (program_id, _) = get_return_data().ok_or(AmmError::InvalidReturnData)?;
+    require_keys_eq!(program_id, crate::ID, AmmError::InvalidReturnData);
+    Ok(result.get())
+}
+
+/// Quotes a `swap` selling exactly `amount_in` through CPI
+///
+/// # Arguments
+/// * `ctx` - CPI context with the pool's quote accounts
+/// * `is_x` - true if selling token X for Y, false if selling Y for X
+/// * `amount_in` - Amount the user would send, transfer fee included
+///
+/// # Returns
+/// * `Result<QuotedSwap>` - Amounts in, out and fee
+pub fn quote_swap<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Quote<'info>>,
+    is_x: bool,
+    amount_in: u64,
+) -> Result<QuotedSwap> {
+    checked_get(crate::cpi::quote_swap(ctx, is_x, amount_in)?)
+}
+
+/// Quotes a `deposit` minting `lp_amount` through CPI
+///
+/// # Arguments
+/// * `ctx` - CPI context with the pool's quote accounts
+/// * `lp_amount` - LP tokens to mint
+///
+/// # Returns
+/// * `Result<QuotedDeposit>` - Token amounts the user would send
+pub fn quote_deposit<'info>(ctx: CpiContext<'_, '_, '_, 'info, Quote<'info>>, lp_amount: u64) -> Result<QuotedDeposit> {
+    checked_get(crate::cpi::quote_deposit(ctx, lp_amount)?)
+}
+
+/// Quotes a `withdraw` burning `lp_amount` through CPI
+///
+/// # Arguments
+/// * `ctx` - CPI context with the pool's quote accounts
+/// * `lp_amount` - LP tokens to burn
+///
+/// # Returns
+/// * `Result<QuotedWithdraw>` - Token amounts the user would receive
+pub fn quote_withdraw<'info>(ctx: CpiContext<'_, '_, '_, 'info, Quote<'info>>, lp_amount: u64) -> Result<QuotedWithdraw> {
+    checked_get(crate::cpi::quote_withdraw(ctx, lp_amount)?)
+}
+
+/// Reads the LP token's virtual price through CPI
+///
+/// # Arguments
+/// * `ctx` - CPI context with the pool's quote accounts
+///
+/// # Returns
+/// * `Result<u128>` - Invariant per LP token in Q64.64 fixed point
+pub fn lp_virtual_price<'info>(ctx: CpiContext<'_, '_, '_, 'info, Quote<'info>>) -> Result<u128> {
+    checked_get(crate::cpi::lp_virtual_price(ctx)?)
+}
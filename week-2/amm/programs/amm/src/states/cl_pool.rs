@@ -0,0 +1,524 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::{
+    add_liquidity_delta, amounts_for_liquidity, compute_swap_step, sqrt_price_at_tick,
+    tick_at_sqrt_price, U256, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK,
+};
+use crate::errors::AmmError;
+
+/// Maximum number of initialized ticks a concentrated liquidity pool can hold
+pub const MAX_INITIALIZED_TICKS: usize = 64;
+
+/// Smallest liquidity a position may hold, other than none
+/// Every initialized tick is a boundary of at least one position, so filling the
+/// pool's tick book takes 32 positions of at least this size rather than dust.
+pub const MIN_POSITION_LIQUIDITY: u128 = 1_000_000;
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClPool {
+    /// Unique identifier used to differentiate between multiple concentrated liquidity pools.
+    pub seed: u64,
+
+    /// SPL token mint address for Token X (first asset in the pair).
+    pub mint_x: Pubkey,
+
+    /// SPL token mint address for Token Y (second asset in the pair).
+    pub mint_y: Pubkey,
+
+    /// Swap fee taken on each trade (in basis points, e.g., 30 = 0.3%).
+    /// Paid entirely to the liquidity in range when the fee is charged.
+    pub fee: u16,
+
+    /// Positions may only start and end on multiples of this tick spacing.
+    pub tick_spacing: u16,
+
+    /// Current sqrt price of token X in units of token Y (Q64.64 fixed point).
+    pub sqrt_price: u128,
+
+    /// Greatest tick whose sqrt price is at most `sqrt_price`.
+    pub tick_current: i32,
+
+    /// Liquidity of all positions whose range contains the current tick.
+    pub liquidity: u128,
+
+    /// Token X fees earned per unit of liquidity over the pool's lifetime (Q64.64, wrapping).
+    pub fee_growth_global_x: u128,
+
+    /// Token Y fees earned per unit of liquidity over the pool's lifetime (Q64.64, wrapping).
+    pub fee_growth_global_y: u128,
+
+    /// Bump used to derive the PDA for this pool account.
+    pub bump: u8,
+}
+
+/// Sorted list of the initialized ticks of a concentrated liquidity pool
+#[account]
+#[derive(InitSpace)]
+pub struct TickBook {
+    /// Initialized ticks, sorted by index
+    #[max_len(MAX_INITIALIZED_TICKS)]
+    pub ticks: Vec<Tick>,
+}
+
+/// A price boundary referenced by at least one position
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct Tick {
+    /// Tick index
+    pub index: i32,
+
+    /// Liquidity added to the pool when the price crosses this tick upwards
+    /// (removed when it crosses downwards)
+    pub liquidity_net: i128,
+
+    /// Total liquidity of the positions using this tick as a boundary
+    pub liquidity_gross: u128,
+
+    /// Token X fee growth on the other side of this tick from the current price (Q64.64)
+    pub fee_growth_outside_x: u128,
+
+    /// Token Y fee growth on the other side of this tick from the current price (Q64.64)
+    pub fee_growth_outside_y: u128,
+}
+
+/// Liquidity provided by one owner over one price range
+#[account]
+#[derive(InitSpace)]
+pub struct ClPosition {
+    /// Pool this position belongs to
+    pub cl_pool: Pubkey,
+
+    /// Owner allowed to modify the position and collect its fees
+    pub owner: Pubkey,
+
+    /// Lower tick of the range (inclusive)
+    pub tick_lower: i32,
+
+    /// Upper tick of the range (exclusive)
+    pub tick_upper: i32,
+
+    /// Liquidity provided over the range
+    pub liquidity: u128,
+
+    /// Token X fee growth inside the range at the last update (Q64.64)
+    pub fee_growth_inside_x_last: u128,
+
+    /// Token Y fee growth inside the range at the last update (Q64.64)
+    pub fee_growth_inside_y_last: u128,
+
+    /// Token X fees earned and not yet collected
+    pub fees_owed_x: u64,
+
+    /// Token Y fees earned and not yet collected
+    pub fees_owed_y: u64,
+
+    /// Bump used to derive the PDA for this position account.
+    pub bump: u8,
+}
+
+/// Amounts of a swap executed on a concentrated liquidity pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClSwapResult {
+    /// Amount the vault receives from the trader, fee included
+    pub amount_in: u64,
+    /// Amount the vault pays out to the trader
+    pub amount_out: u64,
+    /// Part of `amount_in` charged as the swap fee
+    pub fee: u64,
+}
+
+impl ClPool {
+    /// Ensures a price range is valid for this pool
+    ///
+    /// # Arguments
+    /// * `tick_lower` - Lower tick of the range
+    /// * `tick_upper` - Upper tick of the range
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if both ticks are in bounds, ordered and aligned to the tick spacing
+    pub fn check_ticks(&self, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        let spacing = self.tick_spacing as i32;
+        require!(tick_lower < tick_upper, AmmError::InvalidTick);
+        require!(tick_lower >= MIN_TICK && tick_upper <= MAX_TICK, AmmError::InvalidTick);
+        require!(
+            tick_lower % spacing == 0 && tick_upper % spacing == 0,
+            AmmError::InvalidTick
+        );
+        Ok(())
+    }
+
+    /// Adds or removes liquidity from a position
+    ///
+    /// Updates the boundary ticks, credits the position with the fees its range
+    /// earned since its last update, and adjusts the active liquidity if the
+    /// range contains the current price. The position must end up empty or with
+    /// at least `MIN_POSITION_LIQUIDITY`.
+    ///
+    /// # Arguments
+    /// * `tick_book` - Initialized ticks of this pool
+    /// * `position` - Position being modified
+    /// * `liquidity_delta` - Liquidity to add (positive) or remove (negative)
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64)>` - Token X and Y amounts owed to the pool (when adding, rounded up)
+    ///   or to the owner (when removing, rounded down)
+    pub fn modify_position(
+        &mut self,
+        tick_book: &mut TickBook,
+        position: &mut ClPosition,
+        liquidity_delta: i128,
+    ) -> Result<(u64, u64)> {
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+        if liquidity_delta != 0 {
+            tick_book.update(tick_lower, liquidity_delta, false, self)?;
+            tick_book.update(tick_upper, liquidity_delta, true, self)?;
+        }
+
+        // Settle the fees earned with the old liquidity before it changes
+        let (inside_x, inside_y) = tick_book.fee_growth_inside(tick_lower, tick_upper, self);
+        position.accrue_fees(inside_x, inside_y)?;
+        position.liquidity = add_liquidity_delta(position.liquidity, liquidity_delta)
+            .map_err(AmmError::from)?;
+        // Dust positions could otherwise hold tick book slots for free
+        require!(
+            position.liquidity == 0 || position.liquidity >= MIN_POSITION_LIQUIDITY,
+            AmmError::PositionTooSmall
+        );
+
+        // Ticks no longer referenced by any position are freed
+        if liquidity_delta < 0 {
+            tick_book.clear_if_empty(tick_lower);
+            tick_book.clear_if_empty(tick_upper);
+        }
+
+        // Only ranges containing the current price contribute active liquidity
+        if (tick_lower..tick_upper).contains(&self.tick_current) {
+            self.liquidity = add_liquidity_delta(self.liquidity, liquidity_delta)
+                .map_err(AmmError::from)?;
+        }
+
+        let amounts = amounts_for_liquidity(
+            self.sqrt_price,
+            sqrt_price_at_tick(tick_lower).map_err(AmmError::from)?,
+            sqrt_price_at_tick(tick_upper).map_err(AmmError::from)?,
+            liquidity_delta.unsigned_abs(),
+            liquidity_delta > 0,
+        )
+        .map_err(AmmError::from)?;
+
+        Ok(amounts)
+    }
+
+    /// Executes an exact-input swap, crossing initialized ticks as the price moves
+    ///
+    /// The swap stops once the input is used up or the price reaches
+    /// `sqrt_price_limit`, whichever comes first.
+    ///
+    /// # Arguments
+    /// * `tick_book` - Initialized ticks of this pool
+    /// * `x_in` - true if selling token X for Y (price falls), false if selling Y for X
+    /// * `amount_in` - Amount the vault receives from the trader
+    /// * `sqrt_price_limit` - Price the swap may not move past (Q64.64), 0 for no limit
+    ///
+    /// # Returns
+    /// * `Result<ClSwapResult>` - Amounts in, out and fee
+    pub fn swap(
+        &mut self,
+        tick_book: &mut TickBook,
+        x_in: bool,
+        amount_in: u64,
+        sqrt_price_limit: u128,
+    ) -> Result<ClSwapResult> {
+        let sqrt_price_limit = match (sqrt_price_limit, x_in) {
+            (0, true) => MIN_SQRT_PRICE + 1,
+            (0, false) => MAX_SQRT_PRICE - 1,
+            (limit, _) => limit,
+        };
+        // The limit must be on the side the price is moving towards, strictly inside the
+        // supported range so the current tick always stays within `MIN_TICK..MAX_TICK`
+        match x_in {
+            true => require!(
+                sqrt_price_limit > MIN_SQRT_PRICE && sqrt_price_limit < self.sqrt_price,
+                AmmError::InvalidSqrtPrice
+            ),
+            false => require!(
+                sqrt_price_limit > self.sqrt_price && sqrt_price_limit < MAX_SQRT_PRICE,
+                AmmError::InvalidSqrtPrice
+            ),
+        }
+
+        let mut amount_remaining = amount_in;
+        let mut amount_out: u64 = 0;
+        let mut fee_total: u64 = 0;
+
+        while amount_remaining > 0 && self.sqrt_price != sqrt_price_limit {
+            // Swap up to the next initialized tick, or to the end of the price range
+            let next_tick = tick_book.next_initialized(self.tick_current, x_in);
+            let tick_target = match x_in {
+                true => next_tick.unwrap_or(MIN_TICK),
+                false => next_tick.unwrap_or(MAX_TICK),
+            };
+            let sqrt_price_tick = sqrt_price_at_tick(tick_target).map_err(AmmError::from)?;
+            let sqrt_price_target = match x_in {
+                true => sqrt_price_tick.max(sqrt_price_limit),
+                false => sqrt_price_tick.min(sqrt_price_limit),
+            };
+
+            let step = compute_swap_step(
+                self.sqrt_price,
+                sqrt_price_target,
+                self.liquidity,
+                amount_remaining,
+                self.fee,
+                x_in,
+            )
+            .map_err(AmmError::from)?;
+
+            amount_remaining = amount_remaining
+                .checked_sub(step.amount_in + step.fee)
+                .ok_or(AmmError::Underflow)?;
+            amount_out = amount_out
+                .checked_add(step.amount_out)
+                .ok_or(AmmError::Overflow)?;
+            fee_total = fee_total.checked_add(step.fee).ok_or(AmmError::Overflow)?;
+
+            // Fees are shared by the liquidity active during the step
+            if let Some(growth) = ((step.fee as u128) << 64).checked_div(self.liquidity) {
+                match x_in {
+                    true => self.fee_growth_global_x = self.fee_growth_global_x.wrapping_add(growth),
+                    false => self.fee_growth_global_y = self.fee_growth_global_y.wrapping_add(growth),
+                }
+            }
+
+            self.sqrt_price = step.sqrt_price_next;
+            if step.sqrt_price_next == sqrt_price_tick {
+                // Reached the tick: positions bounded by it enter or leave the active range
+                if let Some(index) = next_tick {
+                    let liquidity_net = tick_book.cross(index, self);
+                    let liquidity_net = match x_in {
+                        true => -liquidity_net,
+                        false => liquidity_net,
+                    };
+                    self.liquidity = add_liquidity_delta(self.liquidity, liquidity_net)
+                        .map_err(AmmError::from)?;
+                }
+                self.tick_current = match x_in {
+                    true => tick_target - 1,
+                    false => tick_target,
+                };
+            } else {
+                self.tick_current = tick_at_sqrt_price(self.sqrt_price).map_err(AmmError::from)?;
+            }
+        }
+
+        Ok(ClSwapResult {
+            amount_in: amount_in - amount_remaining,
+            amount_out,
+            fee: fee_total,
+        })
+    }
+}
+
+impl TickBook {
+    /// Adds a liquidity change to a boundary tick, initializing the tick if needed
+    ///
+    /// By convention, all fee growth before a tick is initialized happened below it,
+    /// so `fee_growth_outside` starts at the global value for ticks at or below the
+    /// current tick and at zero for ticks above it.
+    ///
+    /// # Arguments
+    /// * `index` - Tick index
+    /// * `liquidity_delta` - Liquidity added (positive) or removed (negative) by the position
+    /// * `upper` - true if the tick is the position's upper boundary
+    /// * `pool` - Pool the tick belongs to
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error if the book is full
+    pub fn update(
+        &mut self,
+        index: i32,
+        liquidity_delta: i128,
+        upper: bool,
+        pool: &ClPool,
+    ) -> Result<()> {
+        let position = match self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            Ok(position) => position,
+            Err(position) => {
+                require!(liquidity_delta > 0, AmmError::InvalidTick);
+                require!(self.ticks.len() < MAX_INITIALIZED_TICKS, AmmError::TickBookFull);
+
+                let (outside_x, outside_y) = match index <= pool.tick_current {
+                    true => (pool.fee_growth_global_x, pool.fee_growth_global_y),
+                    false => (0, 0),
+                };
+                self.ticks.insert(
+                    position,
+                    Tick {
+                        index,
+                        fee_growth_outside_x: outside_x,
+                        fee_growth_outside_y: outside_y,
+                        ..Tick::default()
+                    },
+                );
+                position
+            }
+        };
+
+        let tick = &mut self.ticks[position];
+        tick.liquidity_gross = add_liquidity_delta(tick.liquidity_gross, liquidity_delta)
+            .map_err(AmmError::from)?;
+        // Crossing the lower tick upwards activates the position, crossing the upper one deactivates it
+        let net_delta = match upper {
+            true => liquidity_delta.checked_neg().ok_or(AmmError::Overflow)?,
+            false => liquidity_delta,
+        };
+        tick.liquidity_net = tick
+            .liquidity_net
+            .checked_add(net_delta)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Removes a tick once no position references it anymore
+    ///
+    /// # Arguments
+    /// * `index` - Tick index
+    pub fn clear_if_empty(&mut self, index: i32) {
+        if let Ok(position) = self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            if self.ticks[position].liquidity_gross == 0 {
+                self.ticks.remove(position);
+            }
+        }
+    }
+
+    /// Returns the fees earned per unit of liquidity inside a price range
+    ///
+    /// # Arguments
+    /// * `tick_lower` - Lower tick of the range
+    /// * `tick_upper` - Upper tick of the range
+    /// * `pool` - Pool the ticks belong to
+    ///
+    /// # Returns
+    /// * `(u128, u128)` - Token X and Y fee growth inside the range (Q64.64, wrapping)
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32, pool: &ClPool) -> (u128, u128) {
+        let lower = self.get(tick_lower);
+        let upper = self.get(tick_upper);
+        let (global_x, global_y) = (pool.fee_growth_global_x, pool.fee_growth_global_y);
+
+        // Fee growth below the lower tick
+        let (below_x, below_y) = match pool.tick_current >= tick_lower {
+            true => (lower.fee_growth_outside_x, lower.fee_growth_outside_y),
+            false => (
+                global_x.wrapping_sub(lower.fee_growth_outside_x),
+                global_y.wrapping_sub(lower.fee_growth_outside_y),
+            ),
+        };
+
+        // Fee growth above the upper tick
+        let (above_x, above_y) = match pool.tick_current < tick_upper {
+            true => (upper.fee_growth_outside_x, upper.fee_growth_outside_y),
+            false => (
+                global_x.wrapping_sub(upper.fee_growth_outside_x),
+                global_y.wrapping_sub(upper.fee_growth_outside_y),
+            ),
+        };
+
+        (
+            global_x.wrapping_sub(below_x).wrapping_sub(above_x),
+            global_y.wrapping_sub(below_y).wrapping_sub(above_y),
+        )
+    }
+
+    /// Returns the next initialized tick in the direction the price is moving
+    ///
+    /// # Arguments
+    /// * `tick_current` - Current tick of the pool
+    /// * `x_in` - true if the price is falling, false if it is rising
+    ///
+    /// # Returns
+    /// * `Option<i32>` - The greatest tick at or below the current tick when falling,
+    ///   the smallest tick above it when rising, or `None` if there is none
+    pub fn next_initialized(&self, tick_current: i32, x_in: bool) -> Option<i32> {
+        match x_in {
+            true => self
+                .ticks
+                .iter()
+                .rev()
+                .find(|tick| tick.index <= tick_current)
+                .map(|tick| tick.index),
+            false => self
+                .ticks
+                .iter()
+                .find(|tick| tick.index > tick_current)
+                .map(|tick| tick.index),
+        }
+    }
+
+    /// Crosses a tick: the fee growth outside it now refers to the other side
+    ///
+    /// # Arguments
+    /// * `index` - Tick index
+    /// * `pool` - Pool the tick belongs to
+    ///
+    /// # Returns
+    /// * `i128` - Net liquidity of the tick, to add when crossing upwards
+    pub fn cross(&mut self, index: i32, pool: &ClPool) -> i128 {
+        match self.ticks.iter_mut().find(|tick| tick.index == index) {
+            Some(tick) => {
+                tick.fee_growth_outside_x = pool
+                    .fee_growth_global_x
+                    .wrapping_sub(tick.fee_growth_outside_x);
+                tick.fee_growth_outside_y = pool
+                    .fee_growth_global_y
+                    .wrapping_sub(tick.fee_growth_outside_y);
+                tick.liquidity_net
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns a copy of a tick, or an empty tick if it is not initialized
+    fn get(&self, index: i32) -> Tick {
+        self.ticks
+            .binary_search_by_key(&index, |tick| tick.index)
+            .map(|position| self.ticks[position])
+            .unwrap_or_default()
+    }
+}
+
+impl ClPosition {
+    /// Credits the position with the fees its range earned since its last update
+    ///
+    /// # Arguments
+    /// * `fee_growth_inside_x` - Current token X fee growth inside the range
+    /// * `fee_growth_inside_y` - Current token Y fee growth inside the range
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn accrue_fees(&mut self, fee_growth_inside_x: u128, fee_growth_inside_y: u128) -> Result<()> {
+        let earned_x = fees_earned(
+            fee_growth_inside_x.wrapping_sub(self.fee_growth_inside_x_last),
+            self.liquidity,
+        )?;
+        let earned_y = fees_earned(
+            fee_growth_inside_y.wrapping_sub(self.fee_growth_inside_y_last),
+            self.liquidity,
+        )?;
+
+        self.fees_owed_x = self.fees_owed_x.checked_add(earned_x).ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self.fees_owed_y.checked_add(earned_y).ok_or(AmmError::Overflow)?;
+        self.fee_growth_inside_x_last = fee_growth_inside_x;
+        self.fee_growth_inside_y_last = fee_growth_inside_y;
+
+        Ok(())
+    }
+}
+
+/// Fees earned by `liquidity` over a fee growth delta, rounded down
+fn fees_earned(fee_growth_delta: u128, liquidity: u128) -> Result<u64> {
+    let earned = (U256::from(fee_growth_delta) * U256::from(liquidity)) >> 64;
+    require!(earned <= U256::from(u64::MAX), AmmError::Overflow);
+    Ok(earned.as_u64())
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+/// Volatility-driven fee settings and state of a pool
+///
+/// Every swap and zap adds the relative pool price move it caused to `volatility`,
+/// which then decays linearly back to zero over `decay_period`. While it is non-zero, swaps
+/// pay `volatility * volatility_factor / 10_000` basis points on top of the
+/// pool's base fee, within `[min_fee, max_fee]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct DynamicFee {
+    /// Lowest fee swaps can be charged (in basis points)
+    pub min_fee: u16,
+
+    /// Highest fee swaps can be charged (in basis points)
+    pub max_fee: u16,
+
+    /// Basis points of fee added per 10_000 basis points of volatility
+    /// (e.g., 1_000 adds 10 bps of fee after a 1% price move)
+    pub volatility_factor: u16,
+
+    /// Seconds it takes for volatility to decay back to zero
+    pub decay_period: u32,
+
+    /// Recent price movement in basis points, as of `last_update_ts`
+    pub volatility: u64,
+
+    /// Unix timestamp `volatility` was last decayed to
+    pub last_update_ts: i64,
+}
+
+impl DynamicFee {
+    /// Returns the volatility left at `now` after linear decay
+    ///
+    /// # Arguments
+    /// * `now` - Current unix timestamp
+    pub fn decayed_volatility(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_update_ts).max(0) as u64;
+        let period = self.decay_period as u64;
+        if elapsed >= period {
+            return 0;
+        }
+        // Fits in a u128: volatility is a u64 and the period a u32
+        (self.volatility as u128 * (period - elapsed) as u128 / period as u128) as u64
+    }
+
+    /// Returns the fee charged on swaps given the pool's base fee
+    ///
+    /// # Arguments
+    /// * `base_fee` - The pool's static fee in basis points
+    ///
+    /// # Returns
+    /// * `u16` - `base_fee` plus the volatility surcharge, clamped to `[min_fee, max_fee]`
+    pub fn fee(&self, base_fee: u16) -> u16 {
+        let variable_fee = self.volatility as u128 * self.volatility_factor as u128 / 10_000;
+        let fee = (base_fee as u128).saturating_add(variable_fee);
+        fee.clamp(self.min_fee as u128, self.max_fee as u128) as u16
+    }
+}
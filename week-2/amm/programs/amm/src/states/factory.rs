@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+/// Maximum number of fee tiers the factory can allow
+pub const MAX_FEE_TIERS: usize = 8;
+
+/// Global settings shared by every pool created through the program
+#[account]
+#[derive(InitSpace)]
+pub struct Factory {
+    /// Account allowed to change the fee tiers.
+    pub admin: Pubkey,
+
+    /// Trading fees (in basis points) new pools may be created with, ascending.
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<u16>,
+
+    /// Number of pools created so far, used as the `seed` of the next pool's config.
+    pub pool_count: u64,
+
+    /// Bump used to derive the PDA for this factory account.
+    pub bump: u8,
+}
+
+/// List of every pool created through the factory, in creation order
+/// Grows by one entry per pool, so its size is not fixed
+#[account]
+pub struct Registry {
+    /// Config addresses of all pools
+    pub pools: Vec<Pubkey>,
+
+    /// Bump used to derive the PDA for this registry account.
+    pub bump: u8,
+}
+
+/// Canonical pool for a sorted mint pair and fee tier
+/// Its PDA can only be created once, so there is exactly one pool per pair and tier
+#[account]
+#[derive(InitSpace)]
+pub struct PoolEntry {
+    /// Config account of the pool.
+    pub config: Pubkey,
+
+    /// Token X of the pair, the smaller of the two mint addresses.
+    pub mint_x: Pubkey,
+
+    /// Token Y of the pair, the larger of the two mint addresses.
+    pub mint_y: Pubkey,
+
+    /// Fee tier the pool was created with (in basis points).
+    pub fee_tier: u16,
+
+    /// Bump used to derive the PDA for this entry.
+    pub bump: u8,
+}
+
+impl Factory {
+    /// Ensures `fee_tiers` is a valid set of fee tiers
+    ///
+    /// # Arguments
+    /// * `fee_tiers` - Candidate fee tiers in basis points
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if the tiers are non-empty, ascending, unique and below 100%
+    pub fn check_fee_tiers(fee_tiers: &[u16]) -> Result<()> {
+        require!(
+            !fee_tiers.is_empty() && fee_tiers.len() <= MAX_FEE_TIERS,
+            AmmError::InvalidFeeTiers
+        );
+        // Strictly ascending also rules out duplicates
+        require!(
+            fee_tiers.windows(2).all(|pair| pair[0] < pair[1]),
+            AmmError::InvalidFeeTiers
+        );
+        // A fee of 100% or more would swallow the whole trade
+        require!(fee_tiers[fee_tiers.len() - 1] < 10_000, AmmError::InvalidFee);
+        Ok(())
+    }
+
+    /// Ensures new pools may be created with `fee`
+    ///
+    /// # Arguments
+    /// * `fee` - Trading fee in basis points
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if `fee` is one of the allowed fee tiers, error otherwise
+    pub fn check_fee_tier(&self, fee: u16) -> Result<()> {
+        require!(self.fee_tiers.contains(&fee), AmmError::FeeTierNotAllowed);
+        Ok(())
+    }
+}
+
+impl Registry {
+    /// Account space of a registry listing `pools` pools, discriminator included
+    ///
+    /// # Arguments
+    /// * `pools` - Number of pools in the registry
+    pub fn space(pools: usize) -> usize {
+        8 + 4 + pools * 32 + 1
+    }
+}
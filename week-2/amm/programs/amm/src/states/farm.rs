@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::U256;
+use crate::errors::AmmError;
+
+/// Reward program paying out a reward token to a pool's staked LP tokens
+///
+/// Rewards are emitted at `reward_rate` per second between `start_ts` and `end_ts`
+/// and shared between stakers in proportion to their stake. Instead of crediting
+/// every staker on every update, the farm tracks the rewards earned per staked LP
+/// token over its lifetime, so a stake's rewards are one multiplication away.
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    /// Unique identifier used to differentiate between multiple farms of a pool.
+    pub seed: u64,
+
+    /// Config account of the pool whose LP tokens are staked.
+    pub config: Pubkey,
+
+    /// Account that created the farm, allowed to extend it and refund what is left.
+    pub authority: Pubkey,
+
+    /// LP token mint of the pool, the token being staked.
+    pub mint_lp: Pubkey,
+
+    /// Mint of the token paid out as rewards.
+    pub reward_mint: Pubkey,
+
+    /// Rewards emitted per second while the farm runs.
+    pub reward_rate: u64,
+
+    /// Unix timestamp rewards start accruing at.
+    pub start_ts: i64,
+
+    /// Unix timestamp rewards stop accruing at.
+    pub end_ts: i64,
+
+    /// LP tokens currently staked in the farm.
+    pub total_staked: u64,
+
+    /// Rewards earned per staked LP token over the farm's lifetime (Q64.64).
+    pub reward_per_share: u128,
+
+    /// Unix timestamp `reward_per_share` was last brought up to date, capped at `end_ts`.
+    pub last_update_ts: i64,
+
+    /// Rewards credited to stakers so far.
+    /// Time with nothing staked credits nobody, so those rewards stay refundable.
+    pub rewards_accrued: u64,
+
+    /// Rewards paid out to stakers so far.
+    pub rewards_claimed: u64,
+
+    /// Bump used to derive the PDA for this farm account.
+    pub bump: u8,
+}
+
+/// LP tokens one owner has staked in a farm
+#[account]
+#[derive(InitSpace)]
+pub struct Stake {
+    /// Farm the LP tokens are staked in
+    pub farm: Pubkey,
+
+    /// Owner allowed to unstake and claim
+    pub owner: Pubkey,
+
+    /// LP tokens staked
+    pub amount: u64,
+
+    /// The farm's `reward_per_share` when the stake was last credited (Q64.64)
+    pub reward_per_share_paid: u128,
+
+    /// Rewards earned and not yet claimed
+    pub rewards_owed: u64,
+
+    /// Bump used to derive the PDA for this stake account.
+    pub bump: u8,
+}
+
+impl Farm {
+    /// Returns the rewards emitted over `[from, to)`
+    ///
+    /// # Arguments
+    /// * `from` - Start of the interval (unix timestamp)
+    /// * `to` - End of the interval (unix timestamp)
+    pub fn rewards_between(&self, from: i64, to: i64) -> Result<u64> {
+        let duration = to.saturating_sub(from).max(0) as u64;
+        Ok(self
+            .reward_rate
+            .checked_mul(duration)
+            .ok_or(AmmError::Overflow)?)
+    }
+
+    /// Brings `reward_per_share` up to `now`
+    ///
+    /// Must be called before the staked amount or the reward rate changes.
+    ///
+    /// # Arguments
+    /// * `now` - Current unix timestamp
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        // Nothing accrues before the start or after the end
+        let until = now.min(self.end_ts);
+        if until <= self.last_update_ts {
+            return Ok(());
+        }
+
+        if self.total_staked > 0 {
+            let rewards = self.rewards_between(self.last_update_ts, until)?;
+            // Fits in a u128: rewards is a u64
+            let per_share = ((rewards as u128) << 64) / self.total_staked as u128;
+            self.reward_per_share = self
+                .reward_per_share
+                .checked_add(per_share)
+                .ok_or(AmmError::Overflow)?;
+            self.rewards_accrued = self
+                .rewards_accrued
+                .checked_add(rewards)
+                .ok_or(AmmError::Overflow)?;
+        }
+
+        self.last_update_ts = until;
+        Ok(())
+    }
+
+    /// Credits `stake` with the rewards it earned since it was last credited
+    ///
+    /// The farm must have been updated first.
+    ///
+    /// # Arguments
+    /// * `stake` - Stake to credit
+    pub fn settle(&self, stake: &mut Stake) -> Result<()> {
+        let growth = self.reward_per_share - stake.reward_per_share_paid;
+        // Rounded down, so stakers are never credited more than the farm accrued
+        let earned = (U256::from(stake.amount) * U256::from(growth)) >> 64;
+        let earned = u64::try_from(earned).map_err(|_| AmmError::Overflow)?;
+
+        stake.rewards_owed = stake
+            .rewards_owed
+            .checked_add(earned)
+            .ok_or(AmmError::Overflow)?;
+        stake.reward_per_share_paid = self.reward_per_share;
+        Ok(())
+    }
+
+    /// Returns the rewards in the reward vault no staker can ever claim
+    ///
+    /// Only meaningful once the farm has ended and been updated, when no more rewards accrue.
+    ///
+    /// # Arguments
+    /// * `reward_vault` - Balance of the farm's reward vault
+    pub fn unallocated_rewards(&self, reward_vault: u64) -> Result<u64> {
+        // Rewards credited to stakers but not yet claimed stay in the vault
+        let unclaimed = self
+            .rewards_accrued
+            .checked_sub(self.rewards_claimed)
+            .ok_or(AmmError::Underflow)?;
+        Ok(reward_vault
+            .checked_sub(unclaimed)
+            .ok_or(AmmError::Underflow)?)
+    }
+}
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::{invariant_per_lp, U256};
+use crate::errors::AmmError;
+use crate::states::Config;
+
+/// Liquidity one owner provided to a pool, tracked for fee and impermanent loss accounting
+///
+/// LP tokens alone do not remember what they cost. A position records what its owner
+/// deposited and the pool's invariant per LP token at the time. Proportional deposits
+/// and withdrawals leave that ratio unchanged while swap fees grow it, so comparing it
+/// with the current ratio splits the position's value into principal and earned fees.
+///
+/// Only LP tokens minted and burned with the position passed are tracked; LP tokens
+/// transferred in or out are not.
+#[account]
+#[derive(InitSpace)]
+pub struct LpPosition {
+    /// Config account of the pool the liquidity was provided to.
+    pub config: Pubkey,
+
+    /// Owner whose deposits and withdrawals update the position.
+    pub owner: Pubkey,
+
+    /// LP tokens minted into the position and not yet burned.
+    pub lp_amount: u64,
+
+    /// Token X deposited, transfer fee included, net of what withdrawals took out.
+    pub deposited_x: u64,
+
+    /// Token Y deposited, transfer fee included, net of what withdrawals took out.
+    pub deposited_y: u64,
+
+    /// Pool invariant per LP token at entry, averaged over deposits by LP amount (Q64.64).
+    pub entry_invariant_per_lp: u128,
+
+    /// Unix timestamp of the position's first deposit since it was last emptied.
+    pub opened_ts: i64,
+
+    /// Unix timestamp of the position's last deposit or withdrawal.
+    pub last_update_ts: i64,
+
+    /// Bump used to derive the PDA for this position account.
+    pub bump: u8,
+}
+
+/// Current worth of a position, returned by `lp_position_value`
+///
+/// Values are in base units of token Y, with token X priced at the pool's spot price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionValue {
+    /// Token X the position's LP tokens would withdraw
+    pub amount_x: u64,
+    /// Token Y the position's LP tokens would withdraw
+    pub amount_y: u64,
+    /// Part of `amount_x` earned in swap fees
+    pub fees_x: u64,
+    /// Part of `amount_y` earned in swap fees
+    pub fees_y: u64,
+    /// Value of `amount_x` and `amount_y`
+    pub value: u128,
+    /// Value of `fees_x` and `fees_y`
+    pub fees_value: u128,
+    /// Value of the deposited tokens, had they been held instead
+    pub hold_value: u128,
+    /// `hold_value` minus the position's value excluding fees; negative if the
+    /// position outperformed holding
+    pub impermanent_loss: i128,
+}
+
+impl LpPosition {
+    /// Records a deposit into the position
+    ///
+    /// # Arguments
+    /// * `config` - Pool configuration, for its curve
+    /// * `reserves` - LP reserves of token X and token Y after the deposit
+    /// * `supply` - LP token supply after the deposit
+    /// * `deposited` - Token X and token Y deposited, transfer fees included
+    /// * `lp_amount` - LP tokens minted to the owner
+    /// * `now` - Current unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn record_deposit(
+        &mut self,
+        config: &Config,
+        reserves: (u64, u64),
+        supply: u64,
+        deposited: (u64, u64),
+        lp_amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        // An emptied position starts over
+        if self.lp_amount == 0 {
+            self.opened_ts = now;
+        }
+
+        let invariant_per_lp = invariant_per_lp(config, reserves.0, reserves.1, supply)?;
+        let total_lp = self
+            .lp_amount
+            .checked_add(lp_amount)
+            .ok_or(AmmError::Overflow)?;
+
+        // Weight the entry snapshot by the LP tokens each deposit added
+        let weighted = U256::from(self.entry_invariant_per_lp) * U256::from(self.lp_amount)
+            + U256::from(invariant_per_lp) * U256::from(lp_amount);
+        self.entry_invariant_per_lp = (weighted / U256::from(total_lp)).as_u128();
+
+        self.lp_amount = total_lp;
+        self.deposited_x = self
+            .deposited_x
+            .checked_add(deposited.0)
+            .ok_or(AmmError::Overflow)?;
+        self.deposited_y = self
+            .deposited_y
+            .checked_add(deposited.1)
+            .ok_or(AmmError::Overflow)?;
+        self.last_update_ts = now;
+
+        Ok(())
+    }
+
+    /// Records a withdrawal from the position
+    ///
+    /// The deposited amounts shrink in proportion to the LP tokens burned, so the
+    /// rest of the position is still compared against what it cost. Burning more LP
+    /// tokens than the position holds empties it.
+    ///
+    /// # Arguments
+    /// * `lp_amount` - LP tokens burned by the owner
+    /// * `now` - Current unix timestamp
+    pub fn record_withdrawal(&mut self, lp_amount: u64, now: i64) {
+        let burned = lp_amount.min(self.lp_amount);
+        if burned > 0 {
+            let remaining = self.lp_amount - burned;
+            self.deposited_x = (self.deposited_x as u128 * remaining as u128 / self.lp_amount as u128) as u64;
+            self.deposited_y = (self.deposited_y as u128 * remaining as u128 / self.lp_amount as u128) as u64;
+            self.lp_amount = remaining;
+        }
+        self.last_update_ts = now;
+    }
+
+    /// Values the position against the pool's current state
+    ///
+    /// Token X is valued in token Y at `Config::pool_price`, the price the pool would
+    /// trade the next unit at. On StableSwap pools that is the slope of the invariant,
+    /// which stays near 1:1 however imbalanced the reserves are.
+    ///
+    /// # Arguments
+    /// * `config` - Pool configuration, for its curve and marginal price
+    /// * `reserve_x` - LP reserves of token X
+    /// * `reserve_y` - LP reserves of token Y
+    /// * `supply` - Current LP token supply
+    ///
+    /// # Returns
+    /// * `Result<PositionValue>` - The position's value, fees and impermanent loss
+    pub fn value(&self, config: &Config, reserve_x: u64, reserve_y: u64, supply: u64) -> Result<PositionValue> {
+        let amount_x = share(reserve_x, self.lp_amount, supply)?;
+        let amount_y = share(reserve_y, self.lp_amount, supply)?;
+
+        // The principal is the share of the position the entry invariant accounts for;
+        // the invariant's growth since then is fees. A shrinking invariant (weights of a
+        // weighted pool moving, or rounding) counts as no fees.
+        let current = invariant_per_lp(config, reserve_x, reserve_y, supply)?;
+        let (fees_x, fees_y) = match current > self.entry_invariant_per_lp {
+            true => (
+                amount_x - mul_div(amount_x, self.entry_invariant_per_lp, current),
+                amount_y - mul_div(amount_y, self.entry_invariant_per_lp, current),
+            ),
+            false => (0, 0),
+        };
+
+        let price = config.pool_price(reserve_x, reserve_y)?;
+        let value = value_in_y(amount_x, amount_y, price)?;
+        let fees_value = value_in_y(fees_x, fees_y, price)?;
+        let hold_value = value_in_y(self.deposited_x, self.deposited_y, price)?;
+        let impermanent_loss = to_i128(hold_value)? - to_i128(value - fees_value)?;
+
+        Ok(PositionValue {
+            amount_x,
+            amount_y,
+            fees_x,
+            fees_y,
+            value,
+            fees_value,
+            hold_value,
+            impermanent_loss,
+        })
+    }
+}
+
+/// Computes `reserve * lp_amount / supply`, rounded down
+fn share(reserve: u64, lp_amount: u64, supply: u64) -> Result<u64> {
+    require!(supply != 0, AmmError::ZeroBalance);
+    Ok((reserve as u128 * lp_amount as u128 / supply as u128) as u64)
+}
+
+/// Computes `amount * numerator / denominator` for `numerator <= denominator`, rounded down
+fn mul_div(amount: u64, numerator: u128, denominator: u128) -> u64 {
+    (U256::from(amount) * U256::from(numerator) / U256::from(denominator)).as_u64()
+}
+
+/// Values amounts of token X and token Y in token Y at `price`
+fn value_in_y(amount_x: u64, amount_y: u64, price: (U256, U256)) -> Result<u128> {
+    require!(!price.1.is_zero(), AmmError::ZeroBalance);
+    let value = U256::from(amount_x) * price.0 / price.1 + U256::from(amount_y);
+    require!(value <= U256::from(u128::MAX), AmmError::Overflow);
+    Ok(value.as_u128())
+}
+
+/// Narrows a value to i128, failing on overflow
+fn to_i128(value: u128) -> Result<i128> {
+    i128::try_from(value).map_err(|_| AmmError::Overflow.into())
+}
@@ -1,5 +1,35 @@
+pub mod cl_pool;
+pub use cl_pool::*;
+
+pub mod factory;
+pub use factory::*;
+
+pub mod dynamic_fee;
+pub use dynamic_fee::*;
+
+pub mod farm;
+pub use farm::*;
+
+pub mod lp_position;
+pub use lp_position::*;
+
+pub mod permission;
+pub use permission::*;
+
+pub mod price_guard;
+pub use price_guard::*;
+
+pub mod twamm;
+pub use twamm::*;
+
+pub mod weights;
+pub use weights::*;
+
 use anchor_lang::prelude::*;
 
+use crate::errors::AmmError;
+use crate::oracle::Observation;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
@@ -10,6 +40,7 @@ pub struct Config {
     /// Optional authority/maintainer of the AMM.
     /// Can be used to control parameters like fees or to pause the AMM.
     /// If set to `None`, the AMM is fully decentralized.
+    /// Starts out as the factory admin or `None`.
     pub authority: Option<Pubkey>,
 
     /// SPL token mint address for Token X (first asset in the pair).
@@ -18,14 +49,73 @@ pub struct Config {
     /// SPL token mint address for Token Y (second asset in the pair).
     pub mint_y: Pubkey,
 
+    /// Pricing curve used by this pool, fixed at creation.
+    pub curve_type: CurveType,
+
+    /// Amplification coefficient of the StableSwap curve (unused by constant product pools).
+    /// Higher values keep prices closer to 1:1 over a wider range of reserves.
+    pub amp: u64,
+
+    /// Token weights of a weighted pool, fixed at creation (`None` for other curves).
+    pub weights: Option<WeightSchedule>,
+
+    /// Weight of token X as a fraction of one (Q64.64), as of the last time the pool
+    /// was touched. Follows `weights` over time; unused by other curves.
+    pub weight_x: u128,
+
     /// Swap fee taken on each trade (in basis points, e.g., 30 = 0.3%).
     /// This fee typically goes to liquidity providers or protocol treasury.
+    /// Starts at the fee tier of the pool's `PoolEntry`; the authority may retune it
+    /// with `update_fee`, while the entry keeps the tier.
     pub fee: u16,
 
+    /// Share of every swap fee reserved for the protocol (in basis points of the fee,
+    /// e.g., 1667 ≈ 1/6). The remainder of the fee stays with liquidity providers.
+    pub protocol_fee: u16,
+
+    /// Token X protocol fees accrued in `vault_x` but not yet collected.
+    /// Excluded from the reserves LPs can deposit against or withdraw.
+    pub protocol_fees_x: u64,
+
+    /// Token Y protocol fees accrued in `vault_y` but not yet collected.
+    /// Excluded from the reserves LPs can deposit against or withdraw.
+    pub protocol_fees_y: u64,
+
+    /// Cumulative price of token X in units of token Y (Q64.64 fixed point * seconds).
+    /// Sampled at two points in time, it yields a manipulation-resistant TWAP.
+    pub price_x_cumulative: u128,
+
+    /// Cumulative price of token Y in units of token X (Q64.64 fixed point * seconds).
+    pub price_y_cumulative: u128,
+
+    /// Unix timestamp at which the cumulative prices were last updated.
+    pub last_update_ts: i64,
+
+    /// Outstanding flash loan, if any.
+    /// Set by `flash_borrow` and cleared by the matching `flash_repay` in the same transaction.
+    pub flash_loan: Option<FlashLoan>,
+
+    /// Volatility-driven fee settings, if the pool has opted into dynamic fees.
+    /// When `None`, swaps are always charged `fee`.
+    pub dynamic_fee: Option<DynamicFee>,
+
+    /// Oracle price band swaps must keep the pool within, if the pool has one.
+    /// When `None`, swaps may trade at any price.
+    pub price_guard: Option<PriceGuard>,
+
+    /// Long-term orders selling into the pool over time.
+    /// Their tokens are held in the vaults but excluded from the LP reserves.
+    pub twamm: Twamm,
+
     /// Boolean flag to lock the AMM.
     /// When `true`, operations like swap or deposit can be disabled.
     pub locked: bool,
 
+    /// Boolean flag restricting the pool to allowlisted users.
+    /// When `true`, deposits, withdrawals and trades require a `Permission` account
+    /// with the matching role, granted by the authority.
+    pub permissioned: bool,
+
     /// Bump used to derive the PDA for this config account.
     /// Ensures the correct address is derived on-chain.
     pub config_bump: u8,
@@ -33,4 +123,212 @@ pub struct Config {
     /// Bump used to derive the PDA for the LP token mint account.
     /// LP tokens represent a user’s share of the liquidity pool.
     pub lp_bump: u8,
+}
+
+/// Pricing curve of a pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    /// Uniswap v2 style x * y = k
+    ConstantProduct,
+    /// Curve style StableSwap invariant, for pegged pairs
+    StableSwap,
+    /// Balancer style weighted x^w_x * y^w_y = k, optionally with shifting weights (LBP)
+    Weighted,
+}
+
+/// Bookkeeping for a flash loan that is currently outstanding
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct FlashLoan {
+    /// Amount of token X lent out of `vault_x`
+    pub amount_x: u64,
+
+    /// Amount of token Y lent out of `vault_y`
+    pub amount_y: u64,
+
+    /// LP reserves of token X before the loan, used to check the invariant on repay
+    pub reserve_x: u64,
+
+    /// LP reserves of token Y before the loan, used to check the invariant on repay
+    pub reserve_y: u64,
+}
+
+impl Config {
+    /// Ensures `signer` is the update authority of this pool
+    ///
+    /// Pools initialized without an authority are immutable, so every
+    /// admin instruction fails for them with `NoAuthoritySet`.
+    ///
+    /// # Arguments
+    /// * `signer` - Public key of the account attempting the update
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if `signer` is the current authority, error otherwise
+    pub fn check_authority(&self, signer: &Pubkey) -> Result<()> {
+        // Immutable pools have no authority that could approve the change
+        let authority = self.authority.ok_or(AmmError::NoAuthoritySet)?;
+        // Only the stored authority may update the pool
+        require_keys_eq!(authority, *signer, AmmError::InvalidAuthority);
+        Ok(())
+    }
+
+    /// Returns the LP-owned reserves held by the pool vaults
+    ///
+    /// Vault balances also contain accrued protocol fees, which belong to the
+    /// protocol rather than to liquidity providers, so they are excluded here.
+    /// So are the tokens of long-term orders, unsold or bought and not yet withdrawn.
+    ///
+    /// # Arguments
+    /// * `vault_x` - Current balance of the token X vault
+    /// * `vault_y` - Current balance of the token Y vault
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64)>` - LP reserves of token X and token Y
+    pub fn lp_reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        let x = vault_x
+            .checked_sub(self.protocol_fees_x)
+            .and_then(|x| x.checked_sub(self.twamm.reserved_x))
+            .ok_or(AmmError::Underflow)?;
+        let y = vault_y
+            .checked_sub(self.protocol_fees_y)
+            .and_then(|y| y.checked_sub(self.twamm.reserved_y))
+            .ok_or(AmmError::Underflow)?;
+        Ok((x, y))
+    }
+
+    /// Returns the protocol's share of a swap fee
+    ///
+    /// # Arguments
+    /// * `fee` - Total swap fee charged on a trade
+    ///
+    /// # Returns
+    /// * `Result<u64>` - `fee * protocol_fee / 10_000`, rounded down in favor of LPs
+    pub fn protocol_cut(&self, fee: u64) -> Result<u64> {
+        let cut = (fee as u128)
+            .checked_mul(self.protocol_fee as u128)
+            .ok_or(AmmError::Overflow)?
+            / 10_000;
+        Ok(cut as u64)
+    }
+
+    /// Credits the protocol's share of a swap fee to the pool's protocol fee balance
+    /// The credited amount stays in the vault but is no longer counted as LP reserves
+    ///
+    /// # Arguments
+    /// * `is_x` - true if the fee was paid in token X, false for token Y
+    /// * `fee` - Total swap fee charged on the trade
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn accrue_protocol_fee(&mut self, is_x: bool, fee: u64) -> Result<()> {
+        let protocol_cut = self.protocol_cut(fee)?;
+
+        let accrued = match is_x {
+            true => &mut self.protocol_fees_x,
+            false => &mut self.protocol_fees_y,
+        };
+        *accrued = accrued
+            .checked_add(protocol_cut)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Returns the fee charged on swaps, in basis points
+    ///
+    /// Pools with dynamic fees add a surcharge for recent volatility on top of `fee`.
+    /// Call [`Config::decay_volatility`] first so the surcharge reflects the current time.
+    pub fn swap_fee(&self) -> u16 {
+        match &self.dynamic_fee {
+            Some(dynamic_fee) => dynamic_fee.fee(self.fee),
+            None => self.fee,
+        }
+    }
+
+    /// Decays the dynamic fee volatility up to the current time
+    /// Does nothing for pools without dynamic fees
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn decay_volatility(&mut self) -> Result<()> {
+        if self.dynamic_fee.is_some() {
+            self.decay_volatility_to(Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
+
+    /// Decays the dynamic fee volatility up to `now`
+    /// Does nothing for pools without dynamic fees
+    ///
+    /// # Arguments
+    /// * `now` - Unix timestamp to decay to
+    pub fn decay_volatility_to(&mut self, now: i64) {
+        if let Some(dynamic_fee) = self.dynamic_fee.as_mut() {
+            dynamic_fee.volatility = dynamic_fee.decayed_volatility(now);
+            dynamic_fee.last_update_ts = now;
+        }
+    }
+
+    /// Moves the weights of a weighted pool along their schedule up to the current time
+    /// Does nothing for other pools
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn update_weights(&mut self) -> Result<()> {
+        if self.weights.is_some() {
+            self.update_weights_to(Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
+
+    /// Moves the weights of a weighted pool along their schedule up to `now`
+    /// Does nothing for other pools
+    ///
+    /// # Arguments
+    /// * `now` - Unix timestamp to move to
+    pub fn update_weights_to(&mut self, now: i64) {
+        if let Some(weights) = &self.weights {
+            self.weight_x = weights.weight_x_at(now);
+        }
+    }
+
+    /// Adds the price move caused by a trade to the dynamic fee volatility
+    /// Does nothing for pools without dynamic fees
+    ///
+    /// # Arguments
+    /// * `reserves_before` - LP reserves of token X and token Y before the trade
+    /// * `reserves_after` - LP reserves of token X and token Y after the trade
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn record_price_move(&mut self, reserves_before: (u64, u64), reserves_after: (u64, u64)) -> Result<()> {
+        if self.dynamic_fee.is_some() {
+            let price_move = self.price_move_bps(reserves_before, reserves_after)?;
+            if let Some(dynamic_fee) = self.dynamic_fee.as_mut() {
+                dynamic_fee.volatility = dynamic_fee.volatility.saturating_add(price_move);
+            }
+        }
+        Ok(())
+    }
+
+    /// Accumulates the current marginal prices into the TWAP oracle
+    ///
+    /// Must be called before the reserves change, so that the time elapsed since
+    /// the last update is weighted with the prices that were in effect during it.
+    ///
+    /// # Arguments
+    /// * `reserve_x` - LP reserves of token X before the pending state change
+    /// * `reserve_y` - LP reserves of token Y before the pending state change
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn update_price_accumulators(&mut self, reserve_x: u64, reserve_y: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let observation = Observation::current(self, reserve_x, reserve_y, now)?;
+
+        self.price_x_cumulative = observation.price_x_cumulative;
+        self.price_y_cumulative = observation.price_y_cumulative;
+        self.last_update_ts = observation.timestamp;
+
+        Ok(())
+    }
 }
\ No newline at end of file
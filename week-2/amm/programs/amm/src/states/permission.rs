@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::states::Config;
+
+/// Roles a user holds in a permissioned pool
+///
+/// Granted and revoked by the pool authority. Pools that are not permissioned
+/// ignore these accounts entirely.
+#[account]
+#[derive(InitSpace)]
+pub struct Permission {
+    /// Config account of the pool the permission applies to.
+    pub config: Pubkey,
+
+    /// User the permission is granted to.
+    pub user: Pubkey,
+
+    /// Whether the user may deposit and withdraw liquidity.
+    pub lp: bool,
+
+    /// Whether the user may swap, take flash loans and place long-term orders.
+    pub trader: bool,
+
+    /// Bump used to derive the PDA for this permission account.
+    pub bump: u8,
+}
+
+/// Role an instruction requires in a permissioned pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Liquidity provider: deposits and withdrawals
+    Lp,
+    /// Trader: swaps, flash loans and long-term orders
+    Trader,
+}
+
+impl Config {
+    /// Ensures the user may perform an instruction requiring `role`
+    ///
+    /// Pools that are not permissioned accept everyone. Permissioned pools require
+    /// the user's permission account, derived from the pool and the user, holding
+    /// the role.
+    ///
+    /// # Arguments
+    /// * `permission` - The user's permission account, if one was passed
+    /// * `role` - Role the instruction requires
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if the user may proceed, error otherwise
+    pub fn check_permission(&self, permission: Option<&Permission>, role: Role) -> Result<()> {
+        if !self.permissioned {
+            return Ok(());
+        }
+
+        let permission = permission.ok_or(AmmError::PermissionDenied)?;
+        let granted = match role {
+            Role::Lp => permission.lp,
+            Role::Trader => permission.trader,
+        };
+        require!(granted, AmmError::PermissionDenied);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::{StableSwap, StableSwapError, U256, WEIGHT_ONE};
+use crate::errors::AmmError;
+use crate::oracle::{PriceUpdate, VerificationLevel};
+use crate::states::{Config, CurveType};
+
+/// Oracle price band a pool's swaps must stay within
+///
+/// Swaps on a guarded pool pass the `oracle` account, a Pyth price update. They
+/// fail if its price is older than `max_age`, or if they leave the pool price more
+/// than `max_deviation_bps` away from it. Swaps that bring the pool price closer to
+/// the oracle are always allowed, so arbitrageurs can pull a pool back into the band
+/// after the market moved.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct PriceGuard {
+    /// Price update account swaps are checked against
+    pub oracle: Pubkey,
+
+    /// Largest distance from the oracle price a swap may leave the pool price at
+    /// (in basis points of the oracle price)
+    pub max_deviation_bps: u16,
+
+    /// Oldest oracle price accepted, in seconds
+    pub max_age: u32,
+
+    /// Whether the feed quotes token Y in units of token X rather than X in Y
+    pub inverted: bool,
+}
+
+impl PriceGuard {
+    /// Ensures the band and the staleness limit are non-zero
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if the guard is valid, error otherwise
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_deviation_bps > 0 && self.max_age > 0,
+            AmmError::InvalidPriceGuard
+        );
+        Ok(())
+    }
+
+    /// Reads the oracle price of one base unit of token X in base units of token Y
+    ///
+    /// # Arguments
+    /// * `oracle` - The price update account, which must be `self.oracle`
+    /// * `decimals_x` - Decimals of token X
+    /// * `decimals_y` - Decimals of token Y
+    /// * `now` - Current unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<(U256, U256)>` - Numerator and denominator of the price
+    pub fn oracle_price(
+        &self,
+        oracle: &AccountInfo,
+        decimals_x: u8,
+        decimals_y: u8,
+        now: i64,
+    ) -> Result<(U256, U256)> {
+        require_keys_eq!(oracle.key(), self.oracle, AmmError::OracleMismatch);
+
+        let update = PriceUpdate::try_from_account(oracle)?;
+        // Partially verified updates are not backed by a guardian quorum
+        require!(
+            update.verification_level == VerificationLevel::Full,
+            AmmError::InvalidOracle
+        );
+        let message = update.price_message;
+        require!(message.price > 0, AmmError::InvalidOracle);
+        require!(
+            now.saturating_sub(message.publish_time) <= self.max_age as i64,
+            AmmError::StaleOraclePrice
+        );
+
+        // A whole token X is worth `price * 10^exponent` whole token Y, or the inverse
+        let price = U256::from(message.price as u64);
+        let (numerator, denominator, exponent) = match self.inverted {
+            false => (price, U256::one(), message.exponent as i64),
+            true => (U256::one(), price, -(message.exponent as i64)),
+        };
+
+        // Move from whole tokens to base units
+        let exponent = exponent + decimals_y as i64 - decimals_x as i64;
+        let scale = U256::from(10u64)
+            .checked_pow(U256::from(exponent.unsigned_abs()))
+            .ok_or(AmmError::Overflow)?;
+        let (numerator, denominator) = match exponent >= 0 {
+            true => (numerator.checked_mul(scale), Some(denominator)),
+            false => (Some(numerator), denominator.checked_mul(scale)),
+        };
+
+        Ok((
+            numerator.ok_or(AmmError::Overflow)?,
+            denominator.ok_or(AmmError::Overflow)?,
+        ))
+    }
+}
+
+impl Config {
+    /// Returns the pool's spot price of token X in token Y as a fraction
+    ///
+    /// Constant product pools price X at `y / x`. StableSwap pools use the slope of
+    /// their invariant, which stays near 1:1 around the balance point however the
+    /// reserves are split, and fall back to `y / x` when they are too imbalanced for
+    /// the solver to converge. Weighted pools price X at `(y / w_y) / (x / w_x)`; their
+    /// weights are cut to 32 fractional bits, which leaves room for the oracle's
+    /// decimal scaling.
+    ///
+    /// # Arguments
+    /// * `reserve_x` - LP reserves of token X
+    /// * `reserve_y` - LP reserves of token Y
+    ///
+    /// # Returns
+    /// * `Result<(U256, U256)>` - Numerator and denominator of the price
+    pub fn pool_price(&self, reserve_x: u64, reserve_y: u64) -> Result<(U256, U256)> {
+        Ok(match self.curve_type {
+            CurveType::ConstantProduct => (U256::from(reserve_y), U256::from(reserve_x)),
+            CurveType::StableSwap => match StableSwap::new(self.amp, self.fee)
+                .and_then(|curve| curve.marginal_price(reserve_x, reserve_y))
+            {
+                Ok(price) => price,
+                // Too far from balance for the solver, where the curve is constant product anyway
+                Err(StableSwapError::NotConverged) => (U256::from(reserve_y), U256::from(reserve_x)),
+                Err(error) => return Err(AmmError::from(error).into()),
+            },
+            CurveType::Weighted => (
+                U256::from(reserve_y) * U256::from(self.weight_x >> 32),
+                U256::from(reserve_x) * U256::from((WEIGHT_ONE - self.weight_x) >> 32),
+            ),
+        })
+    }
+
+    /// Returns how far a trade moved the pool price, as priced by [`Config::pool_price`]
+    ///
+    /// # Arguments
+    /// * `before` - LP reserves of token X and token Y before the trade
+    /// * `after` - LP reserves of token X and token Y after the trade
+    ///
+    /// # Returns
+    /// * `Result<u64>` - `|price_after - price_before| / price_before` in basis points,
+    ///   0 if either state leaves a side of the pool empty
+    pub fn price_move_bps(&self, before: (u64, u64), after: (u64, u64)) -> Result<u64> {
+        // Empty pools have no price to move
+        if before.0 == 0 || before.1 == 0 || after.0 == 0 || after.1 == 0 {
+            return Ok(0);
+        }
+        let price_move = deviation_bps(
+            self.pool_price(after.0, after.1)?,
+            self.pool_price(before.0, before.1)?,
+        )?;
+        // Moves beyond u64 basis points are capped by `max_fee` anyway
+        Ok(u64::try_from(price_move).unwrap_or(u64::MAX))
+    }
+
+    /// Ensures a swap does not push the pool price out of its price guard's band
+    ///
+    /// Pools without a guard accept every swap. Guarded pools require the oracle
+    /// account and a fresh oracle price, and reject swaps that end outside the band
+    /// unless they moved the pool price closer to the oracle.
+    ///
+    /// # Arguments
+    /// * `oracle` - The oracle account passed to the swap, if any
+    /// * `decimals_x` - Decimals of token X
+    /// * `decimals_y` - Decimals of token Y
+    /// * `before` - LP reserves of token X and token Y before the swap
+    /// * `after` - LP reserves of token X and token Y after the swap
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if the swap may proceed, error otherwise
+    pub fn check_price_guard(
+        &self,
+        oracle: Option<&AccountInfo>,
+        decimals_x: u8,
+        decimals_y: u8,
+        before: (u64, u64),
+        after: (u64, u64),
+    ) -> Result<()> {
+        let Some(guard) = self.price_guard else {
+            return Ok(());
+        };
+        let oracle = oracle.ok_or(AmmError::OracleMismatch)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let oracle_price = guard.oracle_price(oracle, decimals_x, decimals_y, now)?;
+
+        let deviation_after = deviation_bps(self.pool_price(after.0, after.1)?, oracle_price)?;
+        if deviation_after <= U256::from(guard.max_deviation_bps) {
+            return Ok(());
+        }
+        // Outside the band, only trades towards the oracle price are allowed
+        let deviation_before = deviation_bps(self.pool_price(before.0, before.1)?, oracle_price)?;
+        require!(
+            deviation_after < deviation_before,
+            AmmError::PriceDeviationExceeded
+        );
+
+        Ok(())
+    }
+}
+
+/// Returns how far a price is from a reference price, in basis points of the reference
+///
+/// # Arguments
+/// * `price` - Numerator and denominator of the price
+/// * `reference` - Numerator and denominator of the reference price
+fn deviation_bps(price: (U256, U256), reference: (U256, U256)) -> Result<U256> {
+    // price / reference = (price.0 * reference.1) / (reference.0 * price.1)
+    let scaled_price = price.0.checked_mul(reference.1).ok_or(AmmError::Overflow)?;
+    let scaled_reference = reference.0.checked_mul(price.1).ok_or(AmmError::Overflow)?;
+    // An emptied side leaves the pool with no meaningful price
+    require!(!scaled_reference.is_zero(), AmmError::PriceDeviationExceeded);
+
+    let delta = match scaled_price >= scaled_reference {
+        true => scaled_price - scaled_reference,
+        false => scaled_reference - scaled_price,
+    };
+    // Deviations beyond what fits are far outside any band anyway
+    Ok(delta
+        .checked_mul(U256::from(10_000u64))
+        .map_or(U256::MAX, |scaled| scaled / scaled_reference))
+}
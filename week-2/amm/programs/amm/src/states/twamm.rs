@@ -0,0 +1,559 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::{swap_exact_in, U256};
+use crate::errors::AmmError;
+use crate::events::ExecuteOrdersEvent;
+use crate::states::Config;
+
+/// Long-term orders expire on multiples of this interval (in seconds), so orders
+/// share expiry entries and the number of distinct expiries stays small
+#[constant]
+pub const ORDER_INTERVAL: i64 = 3_600;
+
+/// Maximum number of distinct expiries a pool can track at once
+pub const MAX_ORDER_EXPIRIES: usize = 16;
+
+/// Longest an order may sell for (in seconds)
+///
+/// Orders placed now expire on at most `MAX_ORDER_EXPIRIES` distinct interval
+/// boundaries, so running orders alone can never fill the expiry book; only
+/// expired orders nobody settled can, and anyone may settle those.
+#[constant]
+pub const MAX_ORDER_DURATION: u32 = (MAX_ORDER_EXPIRIES as u32 - 1) * ORDER_INTERVAL as u32;
+
+/// Smallest order, in basis points of the LP reserves of the token it sells
+/// Keeps dust orders from occupying expiry slots for free
+#[constant]
+pub const MIN_ORDER_BPS: u64 = 10;
+
+/// Long-term (TWAMM) order book of a pool
+///
+/// A long-term order sells its tokens into the pool at a constant rate until it
+/// expires. Rather than trading every second, the orders of each side are pooled
+/// into one sale rate and executed whenever the pool is touched, once for every
+/// stretch of time the sale rates stayed constant: opposing orders trade with each
+/// other at the pool price, and only the side left over trades against the LPs.
+///
+/// Tokens of long-term orders (not yet sold, or bought and not yet withdrawn) stay
+/// in the pool vaults but are excluded from the LP reserves, like protocol fees.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, InitSpace)]
+pub struct Twamm {
+    /// Token X sold per second by all active orders selling X
+    pub sell_rate_x: u64,
+
+    /// Token Y sold per second by all active orders selling Y
+    pub sell_rate_y: u64,
+
+    /// Token X in `vault_x` owned by long-term orders: unsold X plus X bought by Y sellers
+    pub reserved_x: u64,
+
+    /// Token Y in `vault_y` owned by long-term orders: unsold Y plus Y bought by X sellers
+    pub reserved_y: u64,
+
+    /// Token Y earned per unit of X sale rate over the pool's lifetime (Q64.64)
+    pub proceeds_per_rate_x: u128,
+
+    /// Token X earned per unit of Y sale rate over the pool's lifetime (Q64.64)
+    pub proceeds_per_rate_y: u128,
+
+    /// Unix timestamp virtual orders were last executed up to
+    pub last_execution_ts: i64,
+
+    /// Expiries of the pool's orders, ascending
+    #[max_len(MAX_ORDER_EXPIRIES)]
+    pub expiries: Vec<OrderExpiry>,
+}
+
+/// Orders of a pool expiring at the same time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, InitSpace)]
+pub struct OrderExpiry {
+    /// Unix timestamp the orders stop selling at
+    pub expiry_ts: i64,
+
+    /// Combined sale rate of the orders selling X that expire here
+    pub sell_rate_x: u64,
+
+    /// Combined sale rate of the orders selling Y that expire here
+    pub sell_rate_y: u64,
+
+    /// `proceeds_per_rate_x` at the expiry, set once virtual orders were executed past it
+    pub proceeds_per_rate_x: u128,
+
+    /// `proceeds_per_rate_y` at the expiry, set once virtual orders were executed past it
+    pub proceeds_per_rate_y: u128,
+
+    /// Orders expiring here that are still open; the entry is dropped when this reaches zero
+    pub orders: u32,
+}
+
+/// A long-term order selling one token of a pool for the other at a constant rate
+#[account]
+#[derive(InitSpace)]
+pub struct Order {
+    /// Config account of the pool the order trades against
+    pub config: Pubkey,
+
+    /// Owner allowed to withdraw the proceeds and cancel the order
+    pub owner: Pubkey,
+
+    /// Unique identifier among the owner's orders on the pool
+    pub id: u64,
+
+    /// true if the order sells token X for Y, false if it sells Y for X
+    pub is_x: bool,
+
+    /// Tokens sold per second
+    pub sale_rate: u64,
+
+    /// Unix timestamp the order was placed at
+    pub start_ts: i64,
+
+    /// Unix timestamp the order stops selling at, a multiple of `ORDER_INTERVAL`
+    pub expiry_ts: i64,
+
+    /// The pool's proceeds per unit of sale rate (of the sold side) when the order's
+    /// proceeds were last withdrawn (Q64.64)
+    pub proceeds_per_rate_last: u128,
+
+    /// Bump used to derive the PDA for this order account.
+    pub bump: u8,
+}
+
+/// Tokens the long-term orders of a pool traded in one execution of virtual orders
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OrderExecution {
+    /// Token X sold by the orders selling X
+    pub sold_x: u64,
+
+    /// Token Y sold by the orders selling Y
+    pub sold_y: u64,
+
+    /// Token X bought by the orders selling Y
+    pub bought_x: u64,
+
+    /// Token Y bought by the orders selling X
+    pub bought_y: u64,
+}
+
+impl Order {
+    /// Returns the proceeds earned since they were last withdrawn, rounded down
+    ///
+    /// # Arguments
+    /// * `proceeds_per_rate` - The pool's proceeds per unit of sale rate now, or at the
+    ///   order's expiry if it has expired
+    pub fn proceeds(&self, proceeds_per_rate: u128) -> Result<u64> {
+        let growth = proceeds_per_rate
+            .checked_sub(self.proceeds_per_rate_last)
+            .ok_or(AmmError::Underflow)?;
+        let proceeds = (U256::from(self.sale_rate) * U256::from(growth)) >> 64;
+        Ok(u64::try_from(proceeds).map_err(|_| AmmError::Overflow)?)
+    }
+}
+
+impl Config {
+    /// Executes the pool's long-term orders up to the current time
+    ///
+    /// Must be called before the LP reserves are read, by every instruction that
+    /// touches the pool. Emits an `ExecuteOrdersEvent` if the orders sold anything.
+    ///
+    /// # Arguments
+    /// * `vault_x` - Balance of the token X vault
+    /// * `vault_y` - Balance of the token Y vault
+    /// * `lp_supply` - Current LP token supply
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn execute_virtual_orders(&mut self, vault_x: u64, vault_y: u64, lp_supply: u64) -> Result<()> {
+        let from_ts = self.twamm.last_execution_ts;
+        let now = Clock::get()?.unix_timestamp;
+        let execution = self.execute_virtual_orders_to(vault_x, vault_y, lp_supply, now)?;
+        if execution.sold_x == 0 && execution.sold_y == 0 {
+            return Ok(());
+        }
+
+        // The config only knows its seeds, so derive its address from them
+        let pool = Pubkey::create_program_address(
+            &[b"config", &self.seed.to_le_bytes(), &[self.config_bump]],
+            &crate::ID,
+        )
+        .map_err(ProgramError::from)?;
+        emit!(ExecuteOrdersEvent {
+            pool,
+            from_ts,
+            sold_x: execution.sold_x,
+            sold_y: execution.sold_y,
+            bought_x: execution.bought_x,
+            bought_y: execution.bought_y,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Executes the pool's long-term orders up to `now`
+    ///
+    /// Time is split at every expiry in between, since the sale rates drop there.
+    /// Each stretch sells `sell_rate_x * duration` X and `sell_rate_y * duration` Y.
+    /// The two sides first trade with each other at the pool price at the start of
+    /// the stretch, without a fee; whatever one side has left is sold through the
+    /// pool's curve at the regular swap fee. Each side's proceeds are shared between
+    /// its orders in proportion to their sale rates.
+    ///
+    /// # Arguments
+    /// * `vault_x` - Balance of the token X vault
+    /// * `vault_y` - Balance of the token Y vault
+    /// * `lp_supply` - Current LP token supply
+    /// * `now` - Unix timestamp to execute up to
+    ///
+    /// # Returns
+    /// * `Result<OrderExecution>` - What the orders sold and bought along the way
+    pub fn execute_virtual_orders_to(
+        &mut self,
+        vault_x: u64,
+        vault_y: u64,
+        lp_supply: u64,
+        now: i64,
+    ) -> Result<OrderExecution> {
+        let mut execution = OrderExecution::default();
+        while self.twamm.last_execution_ts < now {
+            let last = self.twamm.last_execution_ts;
+            // Expiries are sorted, so the first one past `last` is the next rate change
+            let next_expiry = self
+                .twamm
+                .expiries
+                .iter()
+                .map(|expiry| expiry.expiry_ts)
+                .find(|&expiry_ts| expiry_ts > last);
+            let until = next_expiry.map_or(now, |expiry_ts| expiry_ts.min(now));
+
+            // Swaps along the way are charged the dynamic fee and weights of their own time
+            self.decay_volatility_to(until);
+            self.update_weights_to(until);
+            let duration = (until - last) as u64;
+            let sell_x = self
+                .twamm
+                .sell_rate_x
+                .checked_mul(duration)
+                .ok_or(AmmError::Overflow)?;
+            let sell_y = self
+                .twamm
+                .sell_rate_y
+                .checked_mul(duration)
+                .ok_or(AmmError::Overflow)?;
+
+            // Opposing orders trade with each other, only the rest goes through the curve
+            let (matched_x, matched_y) = self.match_virtual_orders(vault_x, vault_y, sell_x, sell_y)?;
+            let swapped_y =
+                self.execute_virtual_swap(vault_x, vault_y, lp_supply, true, sell_x - matched_x)?;
+            let swapped_x =
+                self.execute_virtual_swap(vault_x, vault_y, lp_supply, false, sell_y - matched_y)?;
+            let bought_y = matched_y.checked_add(swapped_y).ok_or(AmmError::Overflow)?;
+            let bought_x = matched_x.checked_add(swapped_x).ok_or(AmmError::Overflow)?;
+            self.credit_order_proceeds(true, bought_y)?;
+            self.credit_order_proceeds(false, bought_x)?;
+            execution = OrderExecution {
+                sold_x: execution.sold_x.checked_add(sell_x).ok_or(AmmError::Overflow)?,
+                sold_y: execution.sold_y.checked_add(sell_y).ok_or(AmmError::Overflow)?,
+                bought_x: execution.bought_x.checked_add(bought_x).ok_or(AmmError::Overflow)?,
+                bought_y: execution.bought_y.checked_add(bought_y).ok_or(AmmError::Overflow)?,
+            };
+            self.twamm.last_execution_ts = until;
+
+            // Orders expiring now stop selling; remember what they earned for their owners
+            if next_expiry == Some(until) {
+                let twamm = &mut self.twamm;
+                let expiry = twamm
+                    .expiries
+                    .iter_mut()
+                    .find(|expiry| expiry.expiry_ts == until)
+                    .ok_or(AmmError::InvalidOrder)?;
+                twamm.sell_rate_x = twamm
+                    .sell_rate_x
+                    .checked_sub(expiry.sell_rate_x)
+                    .ok_or(AmmError::Underflow)?;
+                twamm.sell_rate_y = twamm
+                    .sell_rate_y
+                    .checked_sub(expiry.sell_rate_y)
+                    .ok_or(AmmError::Underflow)?;
+                expiry.proceeds_per_rate_x = twamm.proceeds_per_rate_x;
+                expiry.proceeds_per_rate_y = twamm.proceeds_per_rate_y;
+            }
+        }
+
+        Ok(execution)
+    }
+
+    /// Matches the long-term orders of both sides against each other at the pool price
+    ///
+    /// The side worth less at the pool price is matched in full; the other side sells
+    /// it the equivalent amount, rounded down, and keeps the rest for the curve.
+    /// No tokens move, and the LP reserves are untouched: both amounts stay reserved
+    /// for the orders, which merely swap ownership of them.
+    ///
+    /// # Arguments
+    /// * `vault_x` - Balance of the token X vault
+    /// * `vault_y` - Balance of the token Y vault
+    /// * `sell_x` - Token X the orders selling X sell over the stretch
+    /// * `sell_y` - Token Y the orders selling Y sell over the stretch
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64)>` - Token X sold to the orders selling Y, and token Y sold to
+    ///   the orders selling X
+    fn match_virtual_orders(
+        &self,
+        vault_x: u64,
+        vault_y: u64,
+        sell_x: u64,
+        sell_y: u64,
+    ) -> Result<(u64, u64)> {
+        if sell_x == 0 || sell_y == 0 {
+            return Ok((0, 0));
+        }
+
+        let (reserve_x, reserve_y) = self.lp_reserves(vault_x, vault_y)?;
+        if reserve_x == 0 || reserve_y == 0 {
+            return Ok((0, 0));
+        }
+
+        // Price of X in Y
+        let (numerator, denominator) = self.pool_price(reserve_x, reserve_y)?;
+        if numerator.is_zero() || denominator.is_zero() {
+            return Ok((0, 0));
+        }
+        let value_x = U256::from(sell_x) * numerator;
+        let value_y = U256::from(sell_y) * denominator;
+
+        let matched = match value_x >= value_y {
+            // The orders selling Y are filled entirely with X
+            true => (value_y / numerator, U256::from(sell_y)),
+            // The orders selling X are filled entirely with Y
+            false => (U256::from(sell_x), value_x / denominator),
+        };
+        Ok((
+            u64::try_from(matched.0).map_err(|_| AmmError::Overflow)?,
+            u64::try_from(matched.1).map_err(|_| AmmError::Overflow)?,
+        ))
+    }
+
+    /// Sells `amount_in` tokens of the long-term orders of one side to the LPs
+    ///
+    /// No tokens move: the sold tokens join the LP reserves and the bought tokens
+    /// leave them, both within the same vaults.
+    ///
+    /// # Arguments
+    /// * `vault_x` - Balance of the token X vault
+    /// * `vault_y` - Balance of the token Y vault
+    /// * `lp_supply` - Current LP token supply
+    /// * `is_x` - true to sell the orders' token X, false to sell their token Y
+    /// * `amount_in` - Amount of tokens to sell
+    ///
+    /// # Returns
+    /// * `Result<u64>` - Amount of the other token the orders bought
+    fn execute_virtual_swap(
+        &mut self,
+        vault_x: u64,
+        vault_y: u64,
+        lp_supply: u64,
+        is_x: bool,
+        amount_in: u64,
+    ) -> Result<u64> {
+        if amount_in == 0 {
+            return Ok(0);
+        }
+
+        // Reserves exclude the orders' tokens, including those about to be sold
+        let (reserve_x, reserve_y) = self.lp_reserves(vault_x, vault_y)?;
+        let quote = swap_exact_in(self, reserve_x, reserve_y, lp_supply, is_x, amount_in)?;
+        self.accrue_protocol_fee(is_x, quote.fee)?;
+
+        let twamm = &mut self.twamm;
+        let (reserved_in, reserved_out) = match is_x {
+            true => (&mut twamm.reserved_x, &mut twamm.reserved_y),
+            false => (&mut twamm.reserved_y, &mut twamm.reserved_x),
+        };
+
+        *reserved_in = reserved_in
+            .checked_sub(amount_in)
+            .ok_or(AmmError::Underflow)?;
+        *reserved_out = reserved_out
+            .checked_add(quote.amount_out)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(quote.amount_out)
+    }
+
+    /// Shares what the long-term orders of one side bought between them, in
+    /// proportion to their sale rates
+    ///
+    /// # Arguments
+    /// * `is_x` - true for the orders selling X, false for the orders selling Y
+    /// * `amount_out` - Tokens the orders bought
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    fn credit_order_proceeds(&mut self, is_x: bool, amount_out: u64) -> Result<()> {
+        if amount_out == 0 {
+            return Ok(());
+        }
+
+        let twamm = &mut self.twamm;
+        let (sell_rate, proceeds_per_rate) = match is_x {
+            true => (twamm.sell_rate_x, &mut twamm.proceeds_per_rate_x),
+            false => (twamm.sell_rate_y, &mut twamm.proceeds_per_rate_y),
+        };
+
+        // Fits in a u128: amount_out is a u64
+        *proceeds_per_rate = proceeds_per_rate
+            .checked_add(((amount_out as u128) << 64) / sell_rate as u128)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Adds a long-term order to the pool's sale rate and expiries
+    ///
+    /// Virtual orders must have been executed up to now first.
+    ///
+    /// # Arguments
+    /// * `is_x` - true if the order sells token X, false if it sells token Y
+    /// * `sale_rate` - Tokens sold per second
+    /// * `expiry_ts` - Unix timestamp the order stops selling at
+    /// * `amount` - Tokens deposited for the order, `sale_rate` times its duration
+    pub fn add_order(&mut self, is_x: bool, sale_rate: u64, expiry_ts: i64, amount: u64) -> Result<()> {
+        let twamm = &mut self.twamm;
+        let index = match twamm
+            .expiries
+            .binary_search_by_key(&expiry_ts, |expiry| expiry.expiry_ts)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                require!(
+                    twamm.expiries.len() < MAX_ORDER_EXPIRIES,
+                    AmmError::TooManyOrderExpiries
+                );
+                twamm.expiries.insert(
+                    index,
+                    OrderExpiry {
+                        expiry_ts,
+                        ..OrderExpiry::default()
+                    },
+                );
+                index
+            }
+        };
+        let expiry = &mut twamm.expiries[index];
+
+        let (sell_rate, expiry_rate, reserved) = match is_x {
+            true => (&mut twamm.sell_rate_x, &mut expiry.sell_rate_x, &mut twamm.reserved_x),
+            false => (&mut twamm.sell_rate_y, &mut expiry.sell_rate_y, &mut twamm.reserved_y),
+        };
+        *sell_rate = sell_rate.checked_add(sale_rate).ok_or(AmmError::Overflow)?;
+        *expiry_rate = expiry_rate.checked_add(sale_rate).ok_or(AmmError::Overflow)?;
+        *reserved = reserved.checked_add(amount).ok_or(AmmError::Overflow)?;
+        expiry.orders = expiry.orders.checked_add(1).ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Returns the proceeds per unit of sale rate an order has access to:
+    /// the current value while it runs, the value at its expiry once it expired
+    ///
+    /// Virtual orders must have been executed up to now first.
+    ///
+    /// # Arguments
+    /// * `order` - The long-term order
+    pub fn order_proceeds_per_rate(&self, order: &Order) -> Result<u128> {
+        let twamm = &self.twamm;
+        if twamm.last_execution_ts < order.expiry_ts {
+            return Ok(match order.is_x {
+                true => twamm.proceeds_per_rate_x,
+                false => twamm.proceeds_per_rate_y,
+            });
+        }
+
+        let expiry = twamm
+            .expiries
+            .iter()
+            .find(|expiry| expiry.expiry_ts == order.expiry_ts)
+            .ok_or(AmmError::InvalidOrder)?;
+        Ok(match order.is_x {
+            true => expiry.proceeds_per_rate_x,
+            false => expiry.proceeds_per_rate_y,
+        })
+    }
+
+    /// Pays an order's proceeds out of the orders' reserved tokens
+    ///
+    /// Virtual orders must have been executed up to now first.
+    ///
+    /// # Arguments
+    /// * `order` - The long-term order, its proceeds checkpoint is moved up to now
+    ///
+    /// # Returns
+    /// * `Result<u64>` - Proceeds to transfer to the owner, in the token the order buys
+    pub fn take_order_proceeds(&mut self, order: &mut Order) -> Result<u64> {
+        let proceeds_per_rate = self.order_proceeds_per_rate(order)?;
+        let proceeds = order.proceeds(proceeds_per_rate)?;
+        order.proceeds_per_rate_last = proceeds_per_rate;
+
+        let reserved = match order.is_x {
+            true => &mut self.twamm.reserved_y,
+            false => &mut self.twamm.reserved_x,
+        };
+        *reserved = reserved.checked_sub(proceeds).ok_or(AmmError::Underflow)?;
+
+        Ok(proceeds)
+    }
+
+    /// Removes an order from the pool, stopping it if it has not expired yet
+    ///
+    /// Its proceeds must have been taken first.
+    ///
+    /// # Arguments
+    /// * `order` - The long-term order
+    ///
+    /// # Returns
+    /// * `Result<u64>` - Unsold tokens to refund to the owner, in the token the order sells
+    pub fn remove_order(&mut self, order: &Order) -> Result<u64> {
+        let twamm = &mut self.twamm;
+        let index = twamm
+            .expiries
+            .iter()
+            .position(|expiry| expiry.expiry_ts == order.expiry_ts)
+            .ok_or(AmmError::InvalidOrder)?;
+        let running = twamm.last_execution_ts < order.expiry_ts;
+        let expiry = &mut twamm.expiries[index];
+
+        let (sell_rate, expiry_rate, reserved) = match order.is_x {
+            true => (&mut twamm.sell_rate_x, &mut expiry.sell_rate_x, &mut twamm.reserved_x),
+            false => (&mut twamm.sell_rate_y, &mut expiry.sell_rate_y, &mut twamm.reserved_y),
+        };
+
+        // A running order stops selling and gets back what it has not sold yet
+        let mut unsold = 0;
+        if running {
+            let remaining = (order.expiry_ts - twamm.last_execution_ts) as u64;
+            unsold = order
+                .sale_rate
+                .checked_mul(remaining)
+                .ok_or(AmmError::Overflow)?;
+            *sell_rate = sell_rate
+                .checked_sub(order.sale_rate)
+                .ok_or(AmmError::Underflow)?;
+            *expiry_rate = expiry_rate
+                .checked_sub(order.sale_rate)
+                .ok_or(AmmError::Underflow)?;
+            *reserved = reserved.checked_sub(unsold).ok_or(AmmError::Underflow)?;
+        }
+
+        expiry.orders = expiry
+            .orders
+            .checked_sub(1)
+            .ok_or(AmmError::Underflow)?;
+        if expiry.orders == 0 {
+            twamm.expiries.remove(index);
+        }
+
+        Ok(unsold)
+    }
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::{bps_to_weight, MAX_WEIGHT, MIN_WEIGHT};
+use crate::errors::AmmError;
+
+/// Token weights of a weighted pool
+///
+/// Static pools (e.g. 80/20) use the same start and end weight. Liquidity
+/// bootstrapping pools (LBPs) shift the weight of token X linearly from
+/// `start_weight_x` to `end_weight_x` between `start_ts` and `end_ts`. Starting a
+/// launched token at a high weight and ending it at a low one walks its price down
+/// over time unless buyers step in, which makes for fair price discovery.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct WeightSchedule {
+    /// Weight of token X until `start_ts` (in basis points, token Y gets the rest)
+    pub start_weight_x: u16,
+
+    /// Weight of token X from `end_ts` on (in basis points)
+    pub end_weight_x: u16,
+
+    /// Unix timestamp the weights start shifting at
+    pub start_ts: i64,
+
+    /// Unix timestamp the weights stop shifting at
+    pub end_ts: i64,
+}
+
+impl WeightSchedule {
+    /// Ensures the weights are within bounds and the shift has a valid time range
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if the schedule is valid, error otherwise
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            (MIN_WEIGHT..=MAX_WEIGHT).contains(&self.start_weight_x)
+                && (MIN_WEIGHT..=MAX_WEIGHT).contains(&self.end_weight_x),
+            AmmError::InvalidWeights
+        );
+        // A shifting weight needs time to shift over
+        require!(
+            self.start_weight_x == self.end_weight_x || self.start_ts < self.end_ts,
+            AmmError::InvalidWeights
+        );
+        Ok(())
+    }
+
+    /// Returns the weight of token X at `now` as a fraction of one (Q64.64)
+    ///
+    /// # Arguments
+    /// * `now` - Unix timestamp
+    pub fn weight_x_at(&self, now: i64) -> u128 {
+        if now <= self.start_ts || self.start_weight_x == self.end_weight_x {
+            return bps_to_weight(self.start_weight_x);
+        }
+        if now >= self.end_ts {
+            return bps_to_weight(self.end_weight_x);
+        }
+
+        // Fits in a u128: both weights are below 2^64 and the elapsed time below 2^63
+        let start = bps_to_weight(self.start_weight_x);
+        let end = bps_to_weight(self.end_weight_x);
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        match end >= start {
+            true => start + (end - start) * elapsed / duration,
+            false => start - (start - end) * elapsed / duration,
+        }
+    }
+}
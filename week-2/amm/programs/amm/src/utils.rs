@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+            StateWithExtensions,
+        },
+    },
+    token_interface::Mint,
+};
+
+use crate::errors::AmmError;
+
+/// Token-2022 mint extensions the pool can safely hold in its vaults
+/// Anything else (transfer hooks, permanent delegates, confidential transfers, ...)
+/// could move or freeze pool funds behind the program's back
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+/// Ensures a mint only uses extensions the pool knows how to handle
+///
+/// Classic SPL Token mints have no extensions and are always supported.
+///
+/// # Arguments
+/// * `mint` - The mint to validate
+///
+/// # Returns
+/// * `Result<()>` - Ok if the mint is supported, error otherwise
+pub fn check_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    // Classic SPL Token mints cannot carry extensions
+    if *mint_info.owner == Token::id() {
+        return Ok(());
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            AmmError::UnsupportedMint
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the transfer fee withheld by the mint when `amount` is transferred
+///
+/// Mints without the transfer-fee extension (including every classic SPL Token
+/// mint) never withhold anything.
+///
+/// # Arguments
+/// * `mint` - Mint of the token being transferred
+/// * `amount` - Gross amount sent by the transfer
+///
+/// # Returns
+/// * `Result<u64>` - Amount withheld, so the recipient receives `amount - fee`
+pub fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    // Classic SPL Token mints never charge transfer fees
+    if *mint_info.owner == Token::id() {
+        return Ok(0);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    mint_transfer_fee(&data, Clock::get()?.epoch, amount)
+}
+
+/// Returns the gross amount that must be sent so that the recipient receives `amount`
+///
+/// # Arguments
+/// * `mint` - Mint of the token being transferred
+/// * `amount` - Net amount the recipient must end up with
+///
+/// # Returns
+/// * `Result<u64>` - Gross transfer amount, transfer fee included
+pub fn amount_with_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    // Classic SPL Token mints never charge transfer fees
+    if *mint_info.owner == Token::id() {
+        return Ok(amount);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    mint_amount_with_transfer_fee(&data, Clock::get()?.epoch, amount)
+}
+
+/// Returns the transfer fee withheld when `amount` is transferred, from raw mint data
+///
+/// Works for both SPL Token and Token-2022 mints, so off-chain quoting can reproduce
+/// [`transfer_fee`] exactly.
+///
+/// # Arguments
+/// * `mint_data` - Raw data of the mint account
+/// * `epoch` - Epoch the transfer happens in
+/// * `amount` - Gross amount sent by the transfer
+///
+/// # Returns
+/// * `Result<u64>` - Amount withheld, so the recipient receives `amount - fee`
+pub fn mint_transfer_fee(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(AmmError::Overflow)?,
+        // No transfer-fee extension on this mint
+        Err(_) => 0,
+    };
+
+    Ok(fee)
+}
+
+/// Returns the gross amount that must be sent so that the recipient receives `amount`,
+/// from raw mint data
+///
+/// # Arguments
+/// * `mint_data` - Raw data of the mint account
+/// * `epoch` - Epoch the transfer happens in
+/// * `amount` - Net amount the recipient must end up with
+///
+/// # Returns
+/// * `Result<u64>` - Gross transfer amount, transfer fee included
+pub fn mint_amount_with_transfer_fee(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64> {
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+    let gross = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .get_epoch_fee(epoch)
+            .calculate_pre_fee_amount(amount)
+            .ok_or(AmmError::Overflow)?,
+        // No transfer-fee extension on this mint
+        Err(_) => amount,
+    };
+
+    Ok(gross)
+}
+
+/// Ensures a transaction is executed before the user's deadline
+///
+/// # Arguments
+/// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+///
+/// # Returns
+/// * `Result<()>` - Ok if there is no deadline or it has not passed, `OfferExpired` otherwise
+pub fn check_deadline(expires_at: i64) -> Result<()> {
+    // 0 lets clients opt out of the deadline
+    if expires_at != 0 {
+        require!(Clock::get()?.unix_timestamp <= expires_at, AmmError::OfferExpired);
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Tests for the pool authority
+//!
+//! The authority can retune the fee within the dynamic fee caps but not the pool's
+//! fee tier, and cannot be renounced while protocol fees can accrue.
+
+mod common;
+
+use amm::curves::swap_exact_in;
+use amm::states::{CurveType, PoolEntry};
+use anchor_lang::{AccountDeserialize, InstructionData};
+
+use common::{pda, Pool, Setup, USERS};
+
+#[test]
+fn the_authority_can_retune_the_fee_but_not_the_tier() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let update_fee = |fee| amm::instruction::UpdateFee { fee }.data();
+
+    // Fees of 100% or more are refused
+    assert!(!pool.update(update_fee(10_000)));
+    assert!(pool.update(update_fee(100)));
+    assert_eq!(pool.read_config().fee, 100);
+
+    // Swaps are charged the new fee
+    let before = pool.snapshot();
+    let (reserve_x, reserve_y) = before.reserves();
+    let quote =
+        swap_exact_in(&pool.read_config(), reserve_x, reserve_y, before.lp_supply, true, 1_000_000_000).unwrap();
+    assert!(pool.swap(1, true, 1_000_000_000));
+    assert_eq!(pool.snapshot().users[1].1 - before.users[1].1, quote.amount_out);
+
+    // The pair's canonical pool is still the one of the tier it was created in
+    let entry = pda(&[b"pool", pool.mint_x.as_ref(), pool.mint_y.as_ref(), &setup.fee.to_le_bytes()]);
+    let entry = pool.svm.get_account(&entry).unwrap();
+    let entry = PoolEntry::try_deserialize(&mut entry.data.as_slice()).unwrap();
+    assert_eq!((entry.config, entry.fee_tier), (pool.config, setup.fee));
+
+    // With dynamic fees on, the base fee must stay within their caps
+    let dynamic_fee = amm::instruction::SetDynamicFee {
+        min_fee: 50,
+        max_fee: 200,
+        volatility_factor: 1_000,
+        decay_period: 600,
+    };
+    assert!(pool.update(dynamic_fee.data()));
+    assert!(!pool.update(update_fee(30)));
+    assert!(pool.update(update_fee(150)));
+}
+
+#[test]
+fn authority_cannot_be_renounced_while_protocol_fees_accrue() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 1_667,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let renounce = || amm::instruction::RenounceAuthority {}.data();
+    assert!(pool.swap(1, true, 1_000_000_000));
+
+    // Uncollected protocol fees would be stranded without an authority to collect them
+    assert!(pool.read_config().protocol_fees_x > 0);
+    assert!(!pool.update(renounce()));
+    assert!(pool.collect_protocol_fees());
+
+    // So would the fees of later swaps, as long as the protocol takes a share
+    assert!(!pool.update(renounce()));
+    assert!(pool.update(amm::instruction::UpdateProtocolFee { protocol_fee: 0 }.data()));
+    assert!(pool.update(renounce()));
+    assert_eq!(pool.read_config().authority, None);
+
+    // Swaps no longer accrue protocol fees, and nobody can switch them back on
+    assert!(pool.swap(1, true, 1_000_000_000));
+    let config = pool.read_config();
+    assert_eq!((config.protocol_fees_x, config.protocol_fees_y), (0, 0));
+    assert!(!pool.update(amm::instruction::UpdateProtocolFee { protocol_fee: 1_667 }.data()));
+}
@@ -0,0 +1,60 @@
+//! Tests for closing pools
+//!
+//! A pool every LP left can be closed: the authority receives what the locked
+//! liquidity is worth and the rent of the pool's accounts, and no LP tokens of the
+//! pool can be minted again.
+
+mod common;
+
+use amm::constants::MINIMUM_LIQUIDITY;
+use amm::states::CurveType;
+use anchor_lang::solana_program::{program_option::COption, program_pack::Pack};
+use anchor_spl::token::spl_token::state::Mint;
+use solana_sdk::signature::Signer;
+
+use common::{ata, set_token_account, Pool, Setup, USERS};
+
+#[test]
+fn pools_every_lp_left_can_be_closed() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 1_667,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    assert!(pool.swap(1, true, 1_000_000_000));
+
+    // LP tokens are still out, and protocol fees have not been collected
+    assert!(!pool.close_pool());
+    let lp = pool.snapshot().users[0].2;
+    assert!(pool.withdraw(0, lp));
+    assert!(!pool.close_pool());
+
+    assert!(pool.collect_protocol_fees());
+
+    // Only the locked minimum liquidity is left, backed by a little dust and by
+    // whatever is sent to the vaults directly
+    let dust_x = pool.snapshot().vault_x;
+    set_token_account(&mut pool.svm, pool.mint_x, &pool.config, dust_x + 1_000_000);
+    let state = pool.snapshot();
+    assert_eq!(state.lp_supply, MINIMUM_LIQUIDITY);
+    assert!(state.vault_x > 1_000_000 && state.vault_y > 0);
+    let admin = pool.admin.pubkey();
+    let admin_x = pool.token_balance(&ata(&admin, &pool.mint_x));
+    let admin_y = pool.token_balance(&ata(&admin, &pool.mint_y));
+
+    assert!(pool.close_pool());
+    assert_eq!(pool.token_balance(&ata(&admin, &pool.mint_x)), admin_x + state.vault_x);
+    assert_eq!(pool.token_balance(&ata(&admin, &pool.mint_y)), admin_y + state.vault_y);
+    for account in [pool.config, pool.locked_lp, pool.vault_x, pool.vault_y] {
+        assert!(pool.is_closed(&account));
+    }
+    let mint_lp = pool.svm.get_account(&pool.mint_lp).unwrap();
+    let mint_lp = Mint::unpack(&mint_lp.data).unwrap();
+    assert_eq!(mint_lp.supply, 0);
+    assert_eq!(mint_lp.mint_authority, COption::None);
+}
@@ -0,0 +1,1039 @@
+//! LiteSVM helpers shared by the program tests
+//!
+//! `Pool` deploys the compiled program, creates a pool through the factory, funds
+//! a few users and wraps every instruction the tests send. The free functions
+//! write mints, token accounts and program accounts directly into the SVM.
+//!
+//! The tests load `target/deploy/amm.so`, so build the program first:
+//! `anchor build && cargo test -p amm`
+
+// Every test file uses a different subset of these helpers
+#![allow(dead_code)]
+
+use amm::constants::MINIMUM_LIQUIDITY;
+use amm::curves::{bps_to_weight, StableSwap, Weighted, U256};
+use amm::states::{Config, CurveType, Factory, Farm, LpPosition, PositionValue, Registry, WeightSchedule};
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::{
+    instruction::Instruction, program_option::COption, program_pack::Pack, sysvar,
+};
+use anchor_lang::{
+    system_program, AccountDeserialize, AccountSerialize, AnchorDeserialize, InstructionData, Space,
+    ToAccountMetas,
+};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Number of users trading against each pool
+pub const USERS: usize = 3;
+
+/// Lamports every wallet starts with, for fees and account rent
+pub const LAMPORTS: u64 = 100_000_000_000;
+
+/// Parameters of a pool and its users, random in the invariant tests
+#[derive(Clone, Debug)]
+pub struct Setup {
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub weights: Option<WeightSchedule>,
+    pub fee: u16,
+    pub protocol_fee: u16,
+    /// Starting balances of token X and Y of every user
+    pub balances: Vec<(u64, u64)>,
+    /// Amounts of token X and Y user 0 seeds the pool with
+    pub initial_deposit: (u64, u64),
+}
+
+/// Balances of everything the invariants look at
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub vault_x: u64,
+    pub vault_y: u64,
+    pub protocol_fees_x: u64,
+    pub protocol_fees_y: u64,
+    pub lp_supply: u64,
+    pub locked_lp: u64,
+    /// Token X, token Y and LP balances of every user
+    pub users: Vec<(u64, u64, u64)>,
+}
+
+impl Snapshot {
+    /// Reserves owned by LPs, excluding accrued protocol fees
+    pub fn reserves(&self) -> (u64, u64) {
+        (
+            self.vault_x
+                .checked_sub(self.protocol_fees_x)
+                .expect("vault X holds less than the protocol fees"),
+            self.vault_y
+                .checked_sub(self.protocol_fees_y)
+                .expect("vault Y holds less than the protocol fees"),
+        )
+    }
+}
+
+/// A pool deployed in LiteSVM, with the addresses needed to trade against it
+pub struct Pool {
+    pub svm: LiteSVM,
+    pub admin: Keypair,
+    pub users: Vec<Keypair>,
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub weights: Option<WeightSchedule>,
+    pub fee: u16,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub config: Pubkey,
+    pub mint_lp: Pubkey,
+    pub locked_lp: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    /// Oracle passed to swaps, for pools with a price guard
+    pub oracle: Option<Pubkey>,
+    /// Whether users pass their permission account, for permissioned pools
+    pub permissioned: bool,
+    /// Whether LPs pass their position account, to track what they earn
+    pub lp_positions: bool,
+}
+
+impl Pool {
+    /// Deploys the program, creates the pool described by `setup` and funds its users
+    pub fn new(setup: &Setup) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(
+            amm::ID,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/amm.so"),
+        )
+        .expect("build the program with `anchor build` before running these tests");
+
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), LAMPORTS).unwrap();
+
+        // `initialize_factory` is restricted to the upgrade authority, which a program
+        // loaded from a file does not have, so the factory is written directly
+        let (factory, factory_bump) = Pubkey::find_program_address(&[b"factory"], &amm::ID);
+        let (registry, registry_bump) = Pubkey::find_program_address(&[b"registry"], &amm::ID);
+        let factory_data = Factory {
+            admin: admin.pubkey(),
+            fee_tiers: vec![setup.fee],
+            pool_count: 0,
+            bump: factory_bump,
+        };
+        set_program_account(&mut svm, factory, &factory_data, 8 + Factory::INIT_SPACE);
+        let registry_data = Registry {
+            pools: Vec::new(),
+            bump: registry_bump,
+        };
+        set_program_account(&mut svm, registry, &registry_data, Registry::space(0));
+
+        // The pool requires its mints in ascending order
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let [mint_x, mint_y] = mints;
+        let supply_x = setup.balances.iter().map(|(x, _)| x).sum();
+        let supply_y = setup.balances.iter().map(|(_, y)| y).sum();
+        set_mint(&mut svm, mint_x, &admin.pubkey(), supply_x);
+        set_mint(&mut svm, mint_y, &admin.pubkey(), supply_y);
+
+        let users: Vec<Keypair> = setup.balances.iter().map(|_| Keypair::new()).collect();
+        for (user, &(x, y)) in users.iter().zip(&setup.balances) {
+            svm.airdrop(&user.pubkey(), LAMPORTS).unwrap();
+            set_token_account(&mut svm, mint_x, &user.pubkey(), x);
+            set_token_account(&mut svm, mint_y, &user.pubkey(), y);
+        }
+
+        let config = pda(&[b"config", &0u64.to_le_bytes()]);
+        let mint_lp = pda(&[b"lp", config.as_ref()]);
+        let mut pool = Self {
+            svm,
+            admin: admin.insecure_clone(),
+            users,
+            curve_type: setup.curve_type,
+            amp: setup.amp,
+            weights: setup.weights,
+            fee: setup.fee,
+            mint_x,
+            mint_y,
+            config,
+            mint_lp,
+            locked_lp: pda(&[b"locked_lp", config.as_ref()]),
+            vault_x: ata(&config, &mint_x),
+            vault_y: ata(&config, &mint_y),
+            oracle: None,
+            permissioned: false,
+            lp_positions: false,
+        };
+
+        let initialize = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Initialize {
+                admin: admin.pubkey(),
+                mint_x,
+                mint_y,
+                factory,
+                registry,
+                pool: pda(&[b"pool", mint_x.as_ref(), mint_y.as_ref(), &setup.fee.to_le_bytes()]),
+                config,
+                mint_lp,
+                locked_lp: pool.locked_lp,
+                vault_x: pool.vault_x,
+                vault_y: pool.vault_y,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Initialize {
+                fee: setup.fee,
+                authority: Some(admin.pubkey()),
+                curve_type: setup.curve_type,
+                amp: setup.amp,
+                weights: setup.weights,
+            }
+            .data(),
+        };
+        assert!(pool.send(initialize, &admin), "initialize failed for {setup:?}");
+
+        if setup.protocol_fee > 0 {
+            let update = Instruction {
+                program_id: amm::ID,
+                accounts: amm::accounts::Update {
+                    authority: admin.pubkey(),
+                    config,
+                }
+                .to_account_metas(None),
+                data: amm::instruction::UpdateProtocolFee {
+                    protocol_fee: setup.protocol_fee,
+                }
+                .data(),
+            };
+            assert!(pool.send(update, &admin), "update_protocol_fee failed for {setup:?}");
+        }
+
+        let (amount_x, amount_y) = setup.initial_deposit;
+        assert!(
+            pool.deposit(0, 1, amount_x, amount_y),
+            "initial deposit failed for {setup:?}"
+        );
+
+        pool
+    }
+
+    /// Sends `ix` signed and paid for by `signer`
+    ///
+    /// # Returns
+    /// * `bool` - true if the transaction succeeded
+    pub fn send(&mut self, ix: Instruction, signer: &Keypair) -> bool {
+        self.send_all(vec![ix], signer)
+    }
+
+    /// Sends `ixs` in a single transaction signed and paid for by `signer`
+    ///
+    /// # Returns
+    /// * `bool` - true if the transaction succeeded
+    pub fn send_all(&mut self, ixs: Vec<Instruction>, signer: &Keypair) -> bool {
+        // The StableSwap solvers can take more than the default compute budget
+        let budget = ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+        let ixs: Vec<Instruction> = std::iter::once(budget).chain(ixs).collect();
+        let tx = Transaction::new_signed_with_payer(
+            &ixs,
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        let succeeded = self.svm.send_transaction(tx).is_ok();
+        // Identical transactions in a row would otherwise be rejected as duplicates
+        self.svm.expire_blockhash();
+        succeeded
+    }
+
+    /// Mints `amount` LP tokens to `user`, paying at most `max_x` and `max_y`
+    pub fn deposit(&mut self, user: usize, amount: u64, max_x: u64, max_y: u64) -> bool {
+        let data = amm::instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+            expires_at: 0,
+        };
+        self.send_deposit(user, data.data())
+    }
+
+    /// Zaps `amount_in` of `user`'s token X (or Y) into the pool, accepting any LP amount
+    pub fn deposit_single(&mut self, user: usize, is_x: bool, amount_in: u64) -> bool {
+        let data = amm::instruction::DepositSingle {
+            is_x,
+            amount_in,
+            min_lp_out: 0,
+            expires_at: 0,
+        };
+        self.send_deposit(user, data.data())
+    }
+
+    /// Sends `user` an instruction taking the `Deposit` accounts
+    pub fn send_deposit(&mut self, user: usize, data: Vec<u8>) -> bool {
+        let user = self.users[user].insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Deposit {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y),
+                user_ata_lp: ata(&user.pubkey(), &self.mint_lp),
+                permission: self.permission(&user.pubkey()),
+                lp_position: self.lp_position(&user.pubkey()),
+                oracle: self.oracle,
+                locked_lp: self.locked_lp,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &user)
+    }
+
+    /// Burns `amount` of `user`'s LP tokens, accepting any payout
+    pub fn withdraw(&mut self, user: usize, amount: u64) -> bool {
+        let user = self.users[user].insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Withdraw {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y),
+                user_ata_lp: ata(&user.pubkey(), &self.mint_lp),
+                permission: self.permission(&user.pubkey()),
+                lp_position: self.lp_position(&user.pubkey()),
+                oracle: self.oracle,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Withdraw {
+                amount,
+                min_x: 0,
+                min_y: 0,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Sells `amount_in` of `user`'s token X (or Y), accepting any output
+    pub fn swap(&mut self, user: usize, is_x: bool, amount_in: u64) -> bool {
+        let user = self.users[user].insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Swap {
+                user: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user.pubkey(), &self.mint_x),
+                user_ata_y: ata(&user.pubkey(), &self.mint_y),
+                permission: self.permission(&user.pubkey()),
+                oracle: self.oracle,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Swap {
+                is_x,
+                amount_in,
+                min_amount_out: 0,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Builds `user`'s `flash_borrow` (or, if `repay`, `flash_repay`) of the amounts given
+    pub fn flash(&self, user: usize, repay: bool, amount_x: u64, amount_y: u64) -> Instruction {
+        let user = self.users[user].pubkey();
+        let data = match repay {
+            true => amm::instruction::FlashRepay { amount_x, amount_y }.data(),
+            false => amm::instruction::FlashBorrow { amount_x, amount_y }.data(),
+        };
+        Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Flash {
+                user,
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_ata_x: ata(&user, &self.mint_x),
+                user_ata_y: ata(&user, &self.mint_y),
+                permission: self.permission(&user),
+                oracle: self.oracle,
+                instructions: sysvar::instructions::ID,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        }
+    }
+
+    /// Borrows `borrow` and repays `repay` (amounts of token X and Y) in one transaction
+    pub fn flash_swap(&mut self, user: usize, borrow: (u64, u64), repay: (u64, u64)) -> bool {
+        let signer = self.users[user].insecure_clone();
+        let ixs = vec![
+            self.flash(user, false, borrow.0, borrow.1),
+            self.flash(user, true, repay.0, repay.1),
+        ];
+        self.send_all(ixs, &signer)
+    }
+
+    /// Places `user`'s long-term order `id`, selling `amount_in` over `duration` seconds
+    pub fn place_order(&mut self, user: usize, id: u64, is_x: bool, amount_in: u64, duration: u32) -> bool {
+        let user = self.users[user].insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::PlaceOrder {
+                owner: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                owner_ata_x: ata(&user.pubkey(), &self.mint_x),
+                owner_ata_y: ata(&user.pubkey(), &self.mint_y),
+                order: self.order(&user.pubkey(), id),
+                permission: self.permission(&user.pubkey()),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::PlaceOrder {
+                id,
+                is_x,
+                amount_in,
+                duration,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        self.send(ix, &user)
+    }
+
+    /// Pays out what `user`'s order `id` has bought so far
+    pub fn withdraw_order_proceeds(&mut self, user: usize, id: u64) -> bool {
+        self.manage_order(user, id, amm::instruction::WithdrawOrderProceeds {}.data())
+    }
+
+    /// Cancels `user`'s order `id`, paying out its proceeds and refunding what it has not sold
+    pub fn cancel_order(&mut self, user: usize, id: u64) -> bool {
+        self.manage_order(user, id, amm::instruction::CancelOrder {}.data())
+    }
+
+    /// Sends an instruction managing `user`'s order `id`, signed by `user`
+    pub fn manage_order(&mut self, user: usize, id: u64, data: Vec<u8>) -> bool {
+        let user = self.users[user].insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::ManageOrder {
+                owner: user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                owner_ata_x: ata(&user.pubkey(), &self.mint_x),
+                owner_ata_y: ata(&user.pubkey(), &self.mint_y),
+                order: self.order(&user.pubkey(), id),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &user)
+    }
+
+    /// Settles `owner`'s expired order `id`, signed and paid for by `settler`
+    pub fn settle_order(&mut self, settler: usize, owner: usize, id: u64) -> bool {
+        let settler = self.users[settler].insecure_clone();
+        let owner = self.users[owner].pubkey();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::SettleOrder {
+                settler: settler.pubkey(),
+                owner,
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                owner_ata_x: ata(&owner, &self.mint_x),
+                owner_ata_y: ata(&owner, &self.mint_y),
+                order: self.order(&owner, id),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::SettleOrder {}.data(),
+        };
+        self.send(ix, &settler)
+    }
+
+    /// Collects the pool's protocol fees into the admin's token accounts
+    pub fn collect_protocol_fees(&mut self) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::CollectProtocolFees {
+                authority: admin.pubkey(),
+                treasury: admin.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                treasury_ata_x: ata(&admin.pubkey(), &self.mint_x),
+                treasury_ata_y: ata(&admin.pubkey(), &self.mint_y),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::CollectProtocolFees {}.data(),
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Closes the pool with the admin's signature, sweeping its dust to the admin
+    pub fn close_pool(&mut self) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::ClosePool {
+                authority: admin.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                registry: pda(&[b"registry"]),
+                pool: pda(&[b"pool", self.mint_x.as_ref(), self.mint_y.as_ref(), &self.fee.to_le_bytes()]),
+                config: self.config,
+                mint_lp: self.mint_lp,
+                locked_lp: self.locked_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                authority_ata_x: ata(&admin.pubkey(), &self.mint_x),
+                authority_ata_y: ata(&admin.pubkey(), &self.mint_y),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::ClosePool {}.data(),
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Creates farm `seed` over the pool's LP token, signed and funded by `creator`
+    pub fn create_farm(
+        &mut self,
+        creator: &Keypair,
+        reward_mint: Pubkey,
+        seed: u64,
+        reward_rate: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> bool {
+        let farm = self.farm(seed);
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::CreateFarm {
+                authority: creator.pubkey(),
+                config: self.config,
+                mint_lp: self.mint_lp,
+                reward_mint,
+                farm,
+                lp_vault: ata(&farm, &self.mint_lp),
+                reward_vault: ata(&farm, &reward_mint),
+                authority_reward_ata: ata(&creator.pubkey(), &reward_mint),
+                token_program: spl_token::ID,
+                reward_token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::CreateFarm {
+                seed,
+                reward_rate,
+                start_ts,
+                end_ts,
+            }
+            .data(),
+        };
+        self.send(ix, creator)
+    }
+
+    /// Stakes `amount` of `user`'s LP tokens in farm `seed`
+    pub fn stake(&mut self, user: usize, seed: u64, amount: u64) -> bool {
+        self.farm_stake(user, seed, amm::instruction::Stake { amount }.data())
+    }
+
+    /// Unstakes `amount` of `user`'s LP tokens from farm `seed`
+    pub fn unstake(&mut self, user: usize, seed: u64, amount: u64) -> bool {
+        self.farm_stake(user, seed, amm::instruction::Unstake { amount }.data())
+    }
+
+    /// Pays out the rewards `user` earned in farm `seed`
+    pub fn claim(&mut self, user: usize, seed: u64) -> bool {
+        self.farm_stake(user, seed, amm::instruction::Claim {}.data())
+    }
+
+    /// Sends an instruction on `user`'s stake in farm `seed`, signed by `user`
+    pub fn farm_stake(&mut self, user: usize, seed: u64, data: Vec<u8>) -> bool {
+        let user = self.users[user].insecure_clone();
+        let farm = self.farm(seed);
+        let reward_mint = self.read_farm(seed).reward_mint;
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::FarmStake {
+                user: user.pubkey(),
+                mint_lp: self.mint_lp,
+                reward_mint,
+                farm,
+                stake: pda(&[b"stake", farm.as_ref(), user.pubkey().as_ref()]),
+                lp_vault: ata(&farm, &self.mint_lp),
+                reward_vault: ata(&farm, &reward_mint),
+                user_ata_lp: ata(&user.pubkey(), &self.mint_lp),
+                user_ata_reward: ata(&user.pubkey(), &reward_mint),
+                token_program: spl_token::ID,
+                reward_token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &user)
+    }
+
+    /// Extends farm `seed` to `end_ts` at `reward_rate`, signed and funded by `signer`
+    pub fn extend_farm(&mut self, signer: &Keypair, seed: u64, end_ts: i64, reward_rate: u64) -> bool {
+        let data = amm::instruction::ExtendFarm { end_ts, reward_rate };
+        self.manage_farm(signer, seed, data.data())
+    }
+
+    /// Refunds the rewards of farm `seed` no staker can claim to `signer`
+    pub fn refund_farm(&mut self, signer: &Keypair, seed: u64) -> bool {
+        self.manage_farm(signer, seed, amm::instruction::RefundFarm {}.data())
+    }
+
+    /// Sends an instruction managing farm `seed`, signed by `signer`
+    pub fn manage_farm(&mut self, signer: &Keypair, seed: u64, data: Vec<u8>) -> bool {
+        let farm = self.farm(seed);
+        let reward_mint = self.read_farm(seed).reward_mint;
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::ManageFarm {
+                authority: signer.pubkey(),
+                reward_mint,
+                farm,
+                reward_vault: ata(&farm, &reward_mint),
+                authority_reward_ata: ata(&signer.pubkey(), &reward_mint),
+                reward_token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, signer)
+    }
+
+    /// Address of the pool's farm `seed`
+    pub fn farm(&self, seed: u64) -> Pubkey {
+        pda(&[b"farm", self.config.as_ref(), &seed.to_le_bytes()])
+    }
+
+    /// Reads the pool's farm `seed`
+    pub fn read_farm(&self, seed: u64) -> Farm {
+        let account = self.svm.get_account(&self.farm(seed)).unwrap();
+        Farm::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Whether `address` was closed (or never existed)
+    pub fn is_closed(&self, address: &Pubkey) -> bool {
+        self.svm
+            .get_account(address)
+            .map_or(true, |account| account.lamports == 0)
+    }
+
+    /// Address of `owner`'s long-term order `id`
+    pub fn order(&self, owner: &Pubkey, id: u64) -> Pubkey {
+        pda(&[b"order", self.config.as_ref(), owner.as_ref(), &id.to_le_bytes()])
+    }
+
+    /// Reads the pool configuration
+    pub fn read_config(&self) -> Config {
+        let account = self.svm.get_account(&self.config).unwrap();
+        Config::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Reserves owned by LPs, excluding protocol fees and the tokens of long-term orders
+    pub fn lp_reserves(&self) -> (u64, u64) {
+        let state = self.snapshot();
+        self.read_config()
+            .lp_reserves(state.vault_x, state.vault_y)
+            .unwrap()
+    }
+
+    /// Moves the clock to `unix_timestamp`
+    pub fn warp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Permission account `user` passes, for permissioned pools
+    pub fn permission(&self, user: &Pubkey) -> Option<Pubkey> {
+        self.permissioned
+            .then(|| pda(&[b"permission", self.config.as_ref(), user.as_ref()]))
+    }
+
+    /// Sends an authority-gated pool update, signed by the admin
+    pub fn update(&mut self, data: Vec<u8>) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Update {
+                authority: admin.pubkey(),
+                config: self.config,
+            }
+            .to_account_metas(None),
+            data,
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Restricts the pool to allowlisted users, passing permissions from now on
+    pub fn set_permissioned(&mut self) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Update {
+                authority: admin.pubkey(),
+                config: self.config,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::SetPermissioned { permissioned: true }.data(),
+        };
+        self.permissioned = true;
+        self.send(ix, &admin)
+    }
+
+    /// Grants `user` the LP and trader roles given
+    pub fn set_permission(&mut self, user: usize, lp: bool, trader: bool) -> bool {
+        let admin = self.admin.insecure_clone();
+        let user = self.users[user].pubkey();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::SetPermission {
+                authority: admin.pubkey(),
+                config: self.config,
+                permission: pda(&[b"permission", self.config.as_ref(), user.as_ref()]),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::SetPermission { user, lp, trader }.data(),
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Revokes every role of `user`
+    pub fn revoke_permission(&mut self, user: usize) -> bool {
+        let admin = self.admin.insecure_clone();
+        let user = self.users[user].pubkey();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::RevokePermission {
+                authority: admin.pubkey(),
+                config: self.config,
+                permission: pda(&[b"permission", self.config.as_ref(), user.as_ref()]),
+            }
+            .to_account_metas(None),
+            data: amm::instruction::RevokePermission {}.data(),
+        };
+        self.send(ix, &admin)
+    }
+
+    /// Guards the pool's swaps with `oracle`, passing it to every later swap
+    pub fn set_price_guard(&mut self, oracle: Pubkey, max_deviation_bps: u16, max_age: u32) -> bool {
+        let admin = self.admin.insecure_clone();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Update {
+                authority: admin.pubkey(),
+                config: self.config,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::SetPriceGuard {
+                oracle,
+                max_deviation_bps,
+                max_age,
+                inverted: false,
+            }
+            .data(),
+        };
+        self.oracle = Some(oracle);
+        self.send(ix, &admin)
+    }
+
+    /// Position account `user` passes, for pools tracking LP positions
+    pub fn lp_position(&self, user: &Pubkey) -> Option<Pubkey> {
+        self.lp_positions
+            .then(|| pda(&[b"lp_position", self.config.as_ref(), user.as_ref()]))
+    }
+
+    /// Reads the position of `user`
+    pub fn read_lp_position(&self, user: usize) -> LpPosition {
+        let address = pda(&[b"lp_position", self.config.as_ref(), self.users[user].pubkey().as_ref()]);
+        let account = self.svm.get_account(&address).expect("no LP position");
+        LpPosition::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Values the position of `user` with `lp_position_value`, decoding its return data
+    pub fn lp_position_value(&mut self, user: usize) -> PositionValue {
+        let user = self.users[user].pubkey();
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::LpPositionValue {
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                lp_position: pda(&[b"lp_position", self.config.as_ref(), user.as_ref()]),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::LpPositionValue {}.data(),
+        };
+        self.view(ix)
+    }
+
+    /// Calls a quote instruction with the pool's quote accounts, decoding its return data
+    pub fn quote<T: AnchorDeserialize>(&mut self, data: impl InstructionData) -> T {
+        let ix = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Quote {
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                config: self.config,
+                mint_lp: self.mint_lp,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        };
+        self.view(ix)
+    }
+
+    /// Sends a read-only instruction paid for by the admin and decodes its return data
+    pub fn view<T: AnchorDeserialize>(&mut self, ix: Instruction) -> T {
+        let admin = self.admin.insecure_clone();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&admin.pubkey()),
+            &[&admin],
+            self.svm.latest_blockhash(),
+        );
+        let meta = self.svm.send_transaction(tx).expect("view instruction failed");
+        self.svm.expire_blockhash();
+        T::deserialize(&mut meta.return_data.data.as_slice()).unwrap()
+    }
+
+    /// Balance of a token account, 0 if it does not exist yet
+    pub fn token_balance(&self, address: &Pubkey) -> u64 {
+        self.svm
+            .get_account(address)
+            .map(|account| TokenAccount::unpack(&account.data).unwrap().amount)
+            .unwrap_or(0)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let config = self.read_config();
+        let mint_lp = self.svm.get_account(&self.mint_lp).unwrap();
+
+        Snapshot {
+            vault_x: self.token_balance(&self.vault_x),
+            vault_y: self.token_balance(&self.vault_y),
+            protocol_fees_x: config.protocol_fees_x,
+            protocol_fees_y: config.protocol_fees_y,
+            lp_supply: Mint::unpack(&mint_lp.data).unwrap().supply,
+            locked_lp: self.token_balance(&self.locked_lp),
+            users: self
+                .users
+                .iter()
+                .map(|user| {
+                    (
+                        self.token_balance(&ata(&user.pubkey(), &self.mint_x)),
+                        self.token_balance(&ata(&user.pubkey(), &self.mint_y)),
+                        self.token_balance(&ata(&user.pubkey(), &self.mint_lp)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether the curve's invariant did not decrease between two reserve states:
+    /// `x * y` for constant product pools, `D` for StableSwap pools (solved to within 1)
+    /// and `x^w_x * y^w_y` for weighted pools
+    pub fn invariant_kept(&self, before: (u64, u64), after: (u64, u64)) -> bool {
+        match self.curve_type {
+            CurveType::ConstantProduct => {
+                U256::from(after.0) * U256::from(after.1) >= U256::from(before.0) * U256::from(before.1)
+            }
+            CurveType::StableSwap => {
+                let curve = StableSwap::new(self.amp, self.fee).unwrap();
+                curve.compute_d(after.0, after.1).unwrap() + U256::one()
+                    >= curve.compute_d(before.0, before.1).unwrap()
+            }
+            CurveType::Weighted => {
+                let weights = self.weights.expect("weighted pool without weights");
+                Weighted::new(bps_to_weight(weights.start_weight_x), self.fee)
+                    .unwrap()
+                    .invariant_holds(before, after)
+                    .unwrap()
+            }
+        }
+    }
+
+    /// Checks the invariants that hold in every state of a seeded pool
+    pub fn check_pool(&self, state: &Snapshot) {
+        // Every LP token in existence is held by a user or locked
+        let held: u64 = state.users.iter().map(|(_, _, lp)| lp).sum();
+        assert_eq!(
+            state.lp_supply,
+            state.locked_lp + held,
+            "LP supply does not match the LP balances"
+        );
+
+        // The minimum liquidity stays locked, and keeps both reserves from being drained
+        assert_eq!(state.locked_lp, MINIMUM_LIQUIDITY);
+        let (reserve_x, reserve_y) = state.reserves();
+        assert!(
+            reserve_x > 0 && reserve_y > 0,
+            "reserves drained to ({reserve_x}, {reserve_y}) with {} LP outstanding",
+            state.lp_supply
+        );
+    }
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &amm::ID).0
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token::ID)
+}
+
+/// Writes an Anchor account owned by the program, padded to `space` bytes
+pub fn set_program_account<T: AccountSerialize>(svm: &mut LiteSVM, address: Pubkey, account: &T, space: usize) {
+    let mut data = Vec::with_capacity(space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    set_account(svm, address, amm::ID, data);
+}
+
+pub fn set_mint(svm: &mut LiteSVM, address: Pubkey, authority: &Pubkey, supply: u64) {
+    let mut data = vec![0; Mint::LEN];
+    let mint = Mint {
+        mint_authority: COption::Some(*authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    Mint::pack(mint, &mut data).unwrap();
+    set_account(svm, address, spl_token::ID, data);
+}
+
+/// Creates `owner`'s associated token account for `mint` holding `amount`
+pub fn set_token_account(svm: &mut LiteSVM, mint: Pubkey, owner: &Pubkey, amount: u64) {
+    let mut data = vec![0; TokenAccount::LEN];
+    let account = TokenAccount {
+        mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    TokenAccount::pack(account, &mut data).unwrap();
+    set_account(svm, ata(owner, &mint), spl_token::ID, data);
+}
+
+pub fn set_account(svm: &mut LiteSVM, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(address, account).unwrap();
+}
@@ -0,0 +1,198 @@
+//! Tests for the concentrated liquidity pool's tick math and bookkeeping
+//!
+//! These drive `ClPool` and `TickBook` directly, without the program: the tick and
+//! sqrt price conversions round trip, swaps cross initialized ticks and move the
+//! active liquidity, fees only accrue to the ranges they were charged in, and dust
+//! positions cannot take tick book slots.
+
+use amm::curves::{sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK, MIN_TICK};
+use amm::errors::AmmError;
+use amm::states::{ClPool, ClPosition, TickBook, MIN_POSITION_LIQUIDITY};
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+
+/// Liquidity of the range around the starting price
+const INNER_LIQUIDITY: u128 = 1_000_000_000;
+
+/// Liquidity of the range just above it
+const OUTER_LIQUIDITY: u128 = 2_000_000_000;
+
+/// Enough input to reach any price limit used here
+const AMOUNT_IN: u64 = 1_000_000_000_000;
+
+/// A pool at price 1 (tick 0) with a 0.3% fee and no liquidity
+fn empty_pool() -> (ClPool, TickBook) {
+    let pool = ClPool {
+        seed: 0,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        fee: 30,
+        tick_spacing: 10,
+        sqrt_price: sqrt_price_at_tick(0).unwrap(),
+        tick_current: 0,
+        liquidity: 0,
+        fee_growth_global_x: 0,
+        fee_growth_global_y: 0,
+        bump: 0,
+    };
+    (pool, TickBook { ticks: Vec::new() })
+}
+
+/// An empty position over `[tick_lower, tick_upper)`
+fn position(tick_lower: i32, tick_upper: i32) -> ClPosition {
+    ClPosition {
+        cl_pool: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        tick_lower,
+        tick_upper,
+        liquidity: 0,
+        fee_growth_inside_x_last: 0,
+        fee_growth_inside_y_last: 0,
+        fees_owed_x: 0,
+        fees_owed_y: 0,
+        bump: 0,
+    }
+}
+
+/// Opens a position over `[tick_lower, tick_upper)` holding `liquidity`
+fn open(
+    pool: &mut ClPool,
+    book: &mut TickBook,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> ClPosition {
+    let mut position = position(tick_lower, tick_upper);
+    pool.modify_position(book, &mut position, liquidity as i128).unwrap();
+    position
+}
+
+/// Swaps until the price reaches `tick`, in whichever direction that is
+fn swap_to(pool: &mut ClPool, book: &mut TickBook, tick: i32) -> u64 {
+    let x_in = tick < pool.tick_current;
+    let limit = sqrt_price_at_tick(tick).unwrap();
+    let result = pool.swap(book, x_in, AMOUNT_IN, limit).unwrap();
+    assert_eq!(pool.sqrt_price, limit);
+    result.fee
+}
+
+/// Returns the AMM error code of a failed call
+fn error_code<T>(result: anchor_lang::Result<T>) -> u32 {
+    match result {
+        Err(Error::AnchorError(error)) => error.error_code_number,
+        _ => panic!("expected an AMM error"),
+    }
+}
+
+#[test]
+fn ticks_and_sqrt_prices_round_trip() {
+    let ticks = (MIN_TICK..=MAX_TICK)
+        .step_by(997)
+        .chain([MIN_TICK, -1, 0, 1, MAX_TICK]);
+
+    for tick in ticks {
+        let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+        // A tick's own sqrt price maps back to it
+        assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+
+        if tick > MIN_TICK {
+            // Anything below it belongs to the tick before
+            assert_eq!(tick_at_sqrt_price(sqrt_price - 1).unwrap(), tick - 1);
+            assert!(sqrt_price_at_tick(tick - 1).unwrap() < sqrt_price);
+        }
+    }
+
+    // Ticks outside the supported range have no price
+    assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+    assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+}
+
+#[test]
+fn swaps_cross_ticks_and_move_the_active_liquidity() {
+    let (mut pool, mut book) = empty_pool();
+    open(&mut pool, &mut book, -100, 100, INNER_LIQUIDITY);
+    open(&mut pool, &mut book, 100, 200, OUTER_LIQUIDITY);
+    assert_eq!(pool.liquidity, INNER_LIQUIDITY);
+
+    // Buying X moves the price up out of the inner range and into the outer one
+    swap_to(&mut pool, &mut book, 150);
+    assert_eq!(pool.tick_current, 150);
+    assert_eq!(pool.liquidity, OUTER_LIQUIDITY);
+
+    // Landing exactly on a tick from above crosses it, leaving the current tick just below
+    swap_to(&mut pool, &mut book, 100);
+    assert_eq!(pool.tick_current, 99);
+    assert_eq!(pool.liquidity, INNER_LIQUIDITY);
+    swap_to(&mut pool, &mut book, 50);
+    assert_eq!(pool.tick_current, 50);
+    assert_eq!(pool.liquidity, INNER_LIQUIDITY);
+
+    // Past every position there is no liquidity left, and crossing back restores it
+    swap_to(&mut pool, &mut book, -150);
+    assert_eq!(pool.tick_current, -150);
+    assert_eq!(pool.liquidity, 0);
+    swap_to(&mut pool, &mut book, 0);
+    assert_eq!(pool.liquidity, INNER_LIQUIDITY);
+}
+
+#[test]
+fn fees_accrue_only_to_the_ranges_they_were_charged_in() {
+    let (mut pool, mut book) = empty_pool();
+    let mut inner = open(&mut pool, &mut book, -100, 100, INNER_LIQUIDITY);
+    let mut outer = open(&mut pool, &mut book, 100, 200, OUTER_LIQUIDITY);
+
+    // A trade within the inner range pays the inner range only
+    let fee_inner = swap_to(&mut pool, &mut book, 50);
+    let inside_inner = book.fee_growth_inside(-100, 100, &pool);
+    let inside_outer = book.fee_growth_inside(100, 200, &pool);
+    assert_eq!(inside_inner.1, pool.fee_growth_global_y);
+    assert_eq!(inside_outer, (0, 0));
+
+    // A trade across both ranges splits its fee between them
+    let fee_both = swap_to(&mut pool, &mut book, 150);
+    let inside_inner = book.fee_growth_inside(-100, 100, &pool);
+    let inside_outer = book.fee_growth_inside(100, 200, &pool);
+    assert!(inside_outer.1 > 0);
+    assert_eq!(inside_inner.1.wrapping_add(inside_outer.1), pool.fee_growth_global_y);
+    // Ranges the price never reached earn nothing
+    assert_eq!(book.fee_growth_inside(-200, -100, &pool), (0, 0));
+
+    // Collecting credits each position its share, never more than was charged
+    pool.modify_position(&mut book, &mut inner, 0).unwrap();
+    pool.modify_position(&mut book, &mut outer, 0).unwrap();
+    assert!(inner.fees_owed_y > 0 && outer.fees_owed_y > 0);
+    assert_eq!((inner.fees_owed_x, outer.fees_owed_x), (0, 0));
+    assert!(inner.fees_owed_y + outer.fees_owed_y <= fee_inner + fee_both);
+
+    // Selling X back charges the fee in X, to the outer range and then the inner one
+    swap_to(&mut pool, &mut book, 0);
+    pool.modify_position(&mut book, &mut inner, 0).unwrap();
+    pool.modify_position(&mut book, &mut outer, 0).unwrap();
+    assert!(inner.fees_owed_x > 0 && outer.fees_owed_x > 0);
+}
+
+#[test]
+fn dust_positions_cannot_take_tick_book_slots() {
+    let (mut pool, mut book) = empty_pool();
+    let too_small = u32::from(AmmError::PositionTooSmall);
+
+    // Positions below the minimum are refused
+    let mut dust = position(-10, 10);
+    let result = pool.modify_position(&mut book, &mut dust, MIN_POSITION_LIQUIDITY as i128 - 1);
+    assert_eq!(error_code(result), too_small);
+
+    // Positions at the minimum may be opened, but not shrunk below it
+    let (mut pool, mut book) = empty_pool();
+    let mut position = open(&mut pool, &mut book, -10, 10, MIN_POSITION_LIQUIDITY);
+    assert_eq!(book.ticks.len(), 2);
+    let result = pool.modify_position(&mut book, &mut position, -1);
+    assert_eq!(error_code(result), too_small);
+
+    // Emptying the position entirely frees its ticks
+    let (mut pool, mut book) = empty_pool();
+    let mut position = open(&mut pool, &mut book, -10, 10, MIN_POSITION_LIQUIDITY);
+    pool.modify_position(&mut book, &mut position, -(MIN_POSITION_LIQUIDITY as i128))
+        .unwrap();
+    assert!(book.ticks.is_empty());
+    assert_eq!(pool.liquidity, 0);
+}
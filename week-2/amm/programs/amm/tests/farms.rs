@@ -0,0 +1,107 @@
+//! Tests for LP farms
+//!
+//! Claims, extensions and refunds never pay out more than the farm was funded with.
+
+mod common;
+
+use amm::states::CurveType;
+use anchor_lang::prelude::Pubkey;
+use solana_sdk::signature::Signer;
+
+use common::{ata, set_mint, set_token_account, Pool, Setup, USERS};
+
+#[test]
+fn farms_never_pay_out_more_than_they_were_funded() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let stake = 1_000_000_000;
+    assert!(pool.deposit(1, stake, u64::MAX, u64::MAX));
+    assert!(pool.deposit(2, stake, u64::MAX, u64::MAX));
+
+    // 1_000 reward tokens per second for 1_000 seconds, and as much again for the extension
+    let (rate, funding) = (1_000, 1_000_000);
+    let admin = pool.admin.insecure_clone();
+    let user = pool.users[1].insecure_clone();
+    let reward_mint = Pubkey::new_unique();
+    set_mint(&mut pool.svm, reward_mint, &admin.pubkey(), 3 * funding);
+    set_token_account(&mut pool.svm, reward_mint, &admin.pubkey(), 2 * funding);
+    set_token_account(&mut pool.svm, reward_mint, &user.pubkey(), funding);
+    let rewards_of = |pool: &Pool, owner: &Pubkey| pool.token_balance(&ata(owner, &reward_mint));
+    let start = 1_700_000_000;
+    pool.warp(start - 100);
+
+    // Only the pool authority may create farms, and it funds the whole schedule
+    assert!(!pool.create_farm(&user, reward_mint, 1, rate, start, start + 1_000));
+    assert!(pool.create_farm(&admin, reward_mint, 0, rate, start, start + 1_000));
+    assert_eq!(rewards_of(&pool, &admin.pubkey()), funding);
+    assert_eq!(rewards_of(&pool, &pool.farm(0)), funding);
+
+    // Nothing is staked for the first 100 seconds, then user 1 and later user 2 stake
+    pool.warp(start + 100);
+    assert!(pool.stake(1, 0, stake));
+    pool.warp(start + 300);
+    assert!(pool.stake(2, 0, stake));
+    assert!(!pool.unstake(2, 0, stake + 1));
+
+    // User 1 earned everything for 200 seconds, then half of it for 200 seconds
+    pool.warp(start + 500);
+    let claimed = rewards_of(&pool, &user.pubkey());
+    assert!(pool.claim(1, 0));
+    let claimed = rewards_of(&pool, &user.pubkey()) - claimed;
+    assert!(claimed <= 300_000 && claimed + 1 >= 300_000);
+    assert!(!pool.claim(1, 0));
+
+    // Only the farm's creator may extend it, and never to a shorter or poorer schedule
+    assert!(!pool.extend_farm(&user, 0, start + 2_000, rate));
+    assert!(!pool.extend_farm(&admin, 0, start + 900, rate));
+    assert!(!pool.extend_farm(&admin, 0, start + 2_000, rate - 1));
+    assert!(pool.extend_farm(&admin, 0, start + 2_000, rate));
+    assert_eq!(rewards_of(&pool, &admin.pubkey()), 0);
+    let funded = 2 * funding;
+
+    // Refunds wait for the end, while unstaking does not
+    assert!(!pool.refund_farm(&admin, 0));
+    pool.warp(start + 1_500);
+    let lp = pool.snapshot().users[2].2;
+    assert!(pool.unstake(2, 0, stake));
+    assert_eq!(pool.snapshot().users[2].2, lp + stake);
+
+    // Once the farm has ended nobody can stake in or extend it
+    pool.warp(start + 2_000);
+    assert!(!pool.stake(2, 0, stake));
+    assert!(!pool.extend_farm(&admin, 0, start + 3_000, rate));
+
+    // The creator gets back exactly the rewards of the 100 seconds nobody was staked
+    assert!(!pool.refund_farm(&user, 0));
+    assert!(pool.refund_farm(&admin, 0));
+    let refunded = rewards_of(&pool, &admin.pubkey());
+    assert_eq!(refunded, 100 * rate);
+    assert!(!pool.refund_farm(&admin, 0));
+
+    // Rewards credited to stakers stay claimable after the refund
+    let before = (rewards_of(&pool, &user.pubkey()), rewards_of(&pool, &pool.users[2].pubkey()));
+    assert!(pool.claim(1, 0));
+    assert!(pool.claim(2, 0));
+    let claimed_1 = claimed + rewards_of(&pool, &user.pubkey()) - before.0;
+    let claimed_2 = rewards_of(&pool, &pool.users[2].pubkey()) - before.1;
+    assert!(claimed_1 <= 1_300_000 && claimed_1 + 3 >= 1_300_000);
+    assert!(claimed_2 <= 600_000 && claimed_2 + 2 >= 600_000);
+    assert!(pool.unstake(1, 0, stake));
+
+    // Claims and the refund never add up to more than was funded, the rest is dust
+    let farm = pool.read_farm(0);
+    assert_eq!(farm.rewards_claimed, claimed_1 + claimed_2);
+    assert!(claimed_1 + claimed_2 + refunded <= funded);
+    let dust = rewards_of(&pool, &pool.farm(0));
+    assert_eq!(claimed_1 + claimed_2 + refunded + dust, funded);
+    assert!(dust <= 4);
+    assert_eq!(farm.total_staked, 0);
+}
@@ -0,0 +1,101 @@
+//! Tests for flash loans and flash swaps
+//!
+//! Flash swaps cannot take value out of a StableSwap pool, and loans are refused
+//! without a matching `flash_repay`, while another loan is outstanding or when
+//! underpaid.
+
+mod common;
+
+use amm::curves::StableSwap;
+use amm::states::CurveType;
+
+use common::{Pool, Setup, USERS};
+
+#[test]
+fn flash_swaps_keep_the_stable_swap_invariant() {
+    let setup = Setup {
+        curve_type: CurveType::StableSwap,
+        amp: 10,
+        weights: None,
+        fee: 4,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 10_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let (reserve_x, reserve_y) = pool.snapshot().reserves();
+    let borrowed = reserve_x / 100;
+
+    // Repaying enough Y to keep the fee-adjusted x * y of the imbalanced pool...
+    let k = reserve_x as u128 * reserve_y as u128;
+    let net = (k.div_ceil((reserve_x - borrowed) as u128) - reserve_y as u128) as u64;
+    let product_repay = (net as u128 * 10_000).div_ceil(10_000 - setup.fee as u128) as u64 + 1;
+    // ...is far less than the curve prices the borrowed X at
+    let curve = StableSwap::new(setup.amp, setup.fee).unwrap();
+    let stable_repay = curve.swap_exact_out(reserve_y, reserve_x, borrowed).unwrap().amount_in;
+    assert!(product_repay < stable_repay, "{product_repay} >= {stable_repay}");
+
+    // and would lower D, so it is refused
+    let before = pool.snapshot();
+    assert!(!pool.flash_swap(1, (borrowed, 0), (0, product_repay)));
+    assert_eq!(before, pool.snapshot());
+
+    // Paying what a swap would cost goes through and keeps D
+    assert!(pool.flash_swap(1, (borrowed, 0), (0, stable_repay + stable_repay / 100)));
+    let after = pool.snapshot();
+    assert!(pool.invariant_kept(before.reserves(), after.reserves()));
+    pool.check_pool(&after);
+}
+
+#[test]
+fn flash_loans_must_be_repaid_in_the_same_transaction() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let user = pool.users[1].insecure_clone();
+    let borrowed = 1_000_000_000;
+    // The borrowed X plus the swap fee on what is paid back, rounded up
+    let repay = (borrowed as u128 * 10_000).div_ceil(10_000 - setup.fee as u128) as u64 + 1;
+    let before = pool.snapshot();
+
+    // A borrow with no `flash_repay` after it is refused up front
+    assert!(!pool.send(pool.flash(1, false, borrowed, 0), &user));
+    // So is a repay placed before the borrow, which has no loan to settle yet
+    let ixs = vec![pool.flash(1, true, repay, 0), pool.flash(1, false, borrowed, 0)];
+    assert!(!pool.send_all(ixs, &user));
+    assert_eq!(pool.snapshot(), before);
+
+    // A second borrow before the first is repaid is refused, even if a repay follows both
+    let ixs = vec![
+        pool.flash(1, false, borrowed, 0),
+        pool.flash(1, false, borrowed, 0),
+        pool.flash(1, true, 2 * repay, 0),
+    ];
+    assert!(!pool.send_all(ixs, &user));
+    assert_eq!(pool.snapshot(), before);
+
+    // Paying back the principal without the fee, or a token short of it, is refused
+    assert!(!pool.flash_swap(1, (borrowed, 0), (borrowed, 0)));
+    assert!(!pool.flash_swap(1, (borrowed, 0), (repay - 2, 0)));
+    assert_eq!(pool.snapshot(), before);
+
+    // Loans repaid in full may follow each other in one transaction
+    let ixs = vec![
+        pool.flash(1, false, borrowed, 0),
+        pool.flash(1, true, repay, 0),
+        pool.flash(1, false, borrowed, 0),
+        pool.flash(1, true, repay, 0),
+    ];
+    assert!(pool.send_all(ixs, &user));
+    let after = pool.snapshot();
+    assert_eq!(after.vault_x, before.vault_x + 2 * (repay - borrowed));
+    assert!(pool.read_config().flash_loan.is_none());
+    pool.check_pool(&after);
+}
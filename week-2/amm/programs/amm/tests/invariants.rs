@@ -0,0 +1,197 @@
+//! Property-based invariant tests for the pool
+//!
+//! Every case creates a pool with a random curve, fee and protocol fee, funds a few
+//! users with random balances, then runs a random sequence of deposits, withdrawals
+//! and swaps against the compiled program in LiteSVM. The pool's invariants are
+//! checked after every step, whether the step succeeded or was rejected.
+//!
+//! The tests load `target/deploy/amm.so`, so build the program first:
+//! `anchor build && cargo test -p amm`
+
+mod common;
+
+use amm::constants::MINIMUM_LIQUIDITY;
+use amm::curves::{MAX_WEIGHT, MIN_WEIGHT};
+use amm::states::{CurveType, WeightSchedule};
+use proptest::prelude::*;
+
+use common::{Pool, Setup, USERS};
+
+/// A random operation on the pool
+/// Amounts are fractions (in basis points) of what the user or pool holds, so most
+/// generated operations are feasible whatever the balances are
+#[derive(Clone, Debug)]
+enum Op {
+    /// Mint up to 10% of the LP supply, paying at most the user's whole balances
+    Deposit { user: usize, share_bps: u16 },
+    /// Burn a share of the user's LP tokens
+    Withdraw { user: usize, share_bps: u16 },
+    /// Sell a share of the user's balance of the input token
+    Swap { user: usize, is_x: bool, share_bps: u16 },
+}
+
+impl Pool {
+    /// Runs `op`, then checks every invariant against the state before it
+    fn apply(&mut self, op: &Op) {
+        let before = self.snapshot();
+
+        let succeeded = match *op {
+            Op::Deposit { user, share_bps } => {
+                let amount = mul_bps(before.lp_supply, share_bps) / 10;
+                let (max_x, max_y, _) = before.users[user];
+                amount > 0 && self.deposit(user, amount, max_x, max_y)
+            }
+            Op::Withdraw { user, share_bps } => {
+                let amount = mul_bps(before.users[user].2, share_bps);
+                amount > 0 && self.withdraw(user, amount)
+            }
+            Op::Swap { user, is_x, share_bps } => {
+                let (x, y, _) = before.users[user];
+                let amount_in = mul_bps(if is_x { x } else { y }, share_bps);
+                amount_in > 0 && self.swap(user, is_x, amount_in)
+            }
+        };
+
+        let after = self.snapshot();
+        self.check_pool(&after);
+
+        if !succeeded {
+            assert_eq!(before, after, "rejected {op:?} changed the pool");
+            return;
+        }
+
+        let (reserve_x, reserve_y) = before.reserves();
+        let (reserve_x_after, reserve_y_after) = after.reserves();
+
+        match *op {
+            Op::Deposit { user, .. } => {
+                // The depositor pays at least what the LP tokens they get are worth,
+                // so existing LPs are never diluted
+                let paid_x = before.users[user].0 - after.users[user].0;
+                let paid_y = before.users[user].1 - after.users[user].1;
+                let minted = after.lp_supply - before.lp_supply;
+                assert!(
+                    worth(reserve_x, minted, before.lp_supply) <= paid_x as u128
+                        && worth(reserve_y, minted, before.lp_supply) <= paid_y as u128,
+                    "{op:?} paid ({paid_x}, {paid_y}) for {minted} LP of {} backed by ({reserve_x}, {reserve_y})",
+                    before.lp_supply,
+                );
+            }
+            Op::Withdraw { user, .. } => {
+                // Nobody gets more than the reserves backing the LP tokens they burn
+                let received_x = after.users[user].0 - before.users[user].0;
+                let received_y = after.users[user].1 - before.users[user].1;
+                let burned = before.lp_supply - after.lp_supply;
+                assert!(
+                    received_x as u128 <= worth(reserve_x, burned, before.lp_supply)
+                        && received_y as u128 <= worth(reserve_y, burned, before.lp_supply),
+                    "{op:?} received ({received_x}, {received_y}) for {burned} LP of {} backed by ({reserve_x}, {reserve_y})",
+                    before.lp_supply,
+                );
+            }
+            Op::Swap { .. } => {
+                // Fees only ever grow the invariant
+                assert!(
+                    self.invariant_kept((reserve_x, reserve_y), (reserve_x_after, reserve_y_after)),
+                    "{op:?} decreased the invariant: ({reserve_x}, {reserve_y}) -> ({reserve_x_after}, {reserve_y_after})"
+                );
+                assert_eq!(before.lp_supply, after.lp_supply, "{op:?} changed the LP supply");
+            }
+        }
+    }
+}
+
+/// Returns how much of `reserve` `lp` out of `lp_supply` LP tokens are worth, rounded down
+fn worth(reserve: u64, lp: u64, lp_supply: u64) -> u128 {
+    reserve as u128 * lp as u128 / lp_supply as u128
+}
+
+/// Returns `amount * bps / 10_000`
+fn mul_bps(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / 10_000) as u64
+}
+
+fn setup() -> impl Strategy<Value = Setup> {
+    let curve = prop_oneof![
+        Just((CurveType::ConstantProduct, 0, None)),
+        (1..=1_000u64).prop_map(|amp| (CurveType::StableSwap, amp, None)),
+        (MIN_WEIGHT..=MAX_WEIGHT).prop_map(|weight_x| {
+            let weights = WeightSchedule {
+                start_weight_x: weight_x,
+                end_weight_x: weight_x,
+                start_ts: 0,
+                end_ts: 0,
+            };
+            (CurveType::Weighted, 0, Some(weights))
+        }),
+    ];
+    let balance = 1_000_000..1_000_000_000_000u64;
+    (
+        curve,
+        prop::sample::select(vec![1u16, 5, 30, 100, 300, 1_000]),
+        prop_oneof![Just(0u16), 1..=10_000u16],
+        prop::collection::vec((balance.clone(), balance), USERS),
+        // Seed with enough of each token to mint more than the minimum liquidity
+        (1..=10_000u16, 1..=10_000u16),
+    )
+        .prop_map(|((curve_type, amp, weights), fee, protocol_fee, balances, (share_x, share_y))| {
+            let (x, y) = balances[0];
+            let initial_deposit = (mul_bps(x, share_x).max(1_000_000), mul_bps(y, share_y).max(1_000_000));
+            Setup {
+                curve_type,
+                amp,
+                weights,
+                fee,
+                protocol_fee,
+                balances,
+                initial_deposit,
+            }
+        })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let share_bps = 1..=10_000u16;
+    prop_oneof![
+        (0..USERS, share_bps.clone()).prop_map(|(user, share_bps)| Op::Deposit { user, share_bps }),
+        (0..USERS, share_bps.clone()).prop_map(|(user, share_bps)| Op::Withdraw { user, share_bps }),
+        (0..USERS, any::<bool>(), share_bps)
+            .prop_map(|(user, is_x, share_bps)| Op::Swap { user, is_x, share_bps }),
+    ]
+}
+
+proptest! {
+    // Every case deploys a fresh program, so keep the number of cases moderate
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn invariants_hold_after_every_step(setup in setup(), ops in prop::collection::vec(op(), 1..50)) {
+        let mut pool = Pool::new(&setup);
+        pool.check_pool(&pool.snapshot());
+
+        for op in &ops {
+            pool.apply(op);
+        }
+    }
+
+    #[test]
+    fn pool_is_never_drained_by_rounding(setup in setup(), ops in prop::collection::vec(op(), 1..50)) {
+        let mut pool = Pool::new(&setup);
+        for op in &ops {
+            pool.apply(op);
+        }
+
+        // Everyone exits, one by one; the locked minimum liquidity still holds reserves
+        for user in 0..pool.users.len() {
+            let lp = pool.snapshot().users[user].2;
+            if lp > 0 {
+                pool.apply(&Op::Withdraw { user, share_bps: 10_000 });
+                prop_assert_eq!(pool.snapshot().users[user].2, 0, "user {} could not exit", user);
+            }
+        }
+
+        let state = pool.snapshot();
+        prop_assert_eq!(state.lp_supply, MINIMUM_LIQUIDITY);
+        let (reserve_x, reserve_y) = state.reserves();
+        prop_assert!(reserve_x > 0 && reserve_y > 0);
+    }
+}
@@ -0,0 +1,61 @@
+//! Tests for LP position tracking
+//!
+//! Positions record what their LP deposited, and value what it holds now as fees
+//! earned and impermanent loss against holding the deposited tokens.
+
+mod common;
+
+use amm::states::CurveType;
+use solana_sdk::signature::Signer;
+
+use common::{Pool, Setup, USERS};
+
+#[test]
+fn lp_positions_split_value_into_fees_and_impermanent_loss() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let lp_supply = pool.snapshot().lp_supply;
+    pool.lp_positions = true;
+
+    // User 1 doubles the pool, opening a position worth what it cost
+    assert!(pool.deposit(1, lp_supply, u64::MAX, u64::MAX));
+    let position = pool.read_lp_position(1);
+    assert_eq!(position.owner, pool.users[1].pubkey());
+    assert_eq!(position.lp_amount, lp_supply);
+    let value = pool.lp_position_value(1);
+    assert_eq!((value.fees_x, value.fees_y), (0, 0));
+    assert!(value.impermanent_loss.abs() <= 2, "{value:?}");
+
+    // Trading back and forth earns fees without moving the price much
+    for _ in 0..10 {
+        assert!(pool.swap(2, true, 1_000_000_000));
+        assert!(pool.swap(2, false, 1_000_000_000));
+    }
+    let value = pool.lp_position_value(1);
+    assert!(value.fees_x > 0 && value.fees_y > 0, "{value:?}");
+    assert!(value.value > value.hold_value, "{value:?}");
+
+    // A large trade moves the price, and the position falls behind holding
+    assert!(pool.swap(2, true, 50_000_000_000));
+    let moved = pool.lp_position_value(1);
+    assert!(moved.impermanent_loss > 0, "{moved:?}");
+    assert!(moved.value < moved.hold_value, "{moved:?}");
+    assert!(moved.fees_x > value.fees_x, "{moved:?}");
+
+    // Withdrawing half keeps comparing the rest against half the cost
+    let before = pool.read_lp_position(1);
+    assert!(pool.withdraw(1, lp_supply / 2));
+    let after = pool.read_lp_position(1);
+    assert_eq!(after.lp_amount, lp_supply - lp_supply / 2);
+    assert!(after.deposited_x.abs_diff(before.deposited_x / 2) <= 1);
+    assert!(after.deposited_y.abs_diff(before.deposited_y / 2) <= 1);
+    assert_eq!(after.entry_invariant_per_lp, before.entry_invariant_per_lp);
+}
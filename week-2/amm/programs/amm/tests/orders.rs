@@ -0,0 +1,122 @@
+//! Tests for long-term orders placed through the program
+//!
+//! Dust and abandoned orders cannot fill the expiry book, and opposing orders trade
+//! with each other before the pool and pay out their proceeds when withdrawn or
+//! cancelled. `twamm.rs` covers the execution math without the program.
+
+mod common;
+
+use amm::curves::swap_exact_in;
+use amm::states::{CurveType, MAX_ORDER_DURATION, MAX_ORDER_EXPIRIES, ORDER_INTERVAL};
+use solana_sdk::signature::Signer;
+
+use common::{Pool, Setup, USERS};
+
+#[test]
+fn abandoned_orders_cannot_fill_the_expiry_book() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    // Halfway through an interval, so the next boundary is the first expiry
+    let start = 1_700_000_000 / ORDER_INTERVAL * ORDER_INTERVAL + ORDER_INTERVAL / 2;
+    pool.warp(start);
+
+    // Dust orders and orders running past the maximum duration are refused
+    assert!(!pool.place_order(1, 0, true, 3_600, ORDER_INTERVAL as u32));
+    assert!(!pool.place_order(1, 0, true, 1_000_000_000, MAX_ORDER_DURATION + 1));
+
+    // Orders of every allowed duration land on at most MAX_ORDER_EXPIRIES expiries...
+    for id in 0..MAX_ORDER_EXPIRIES as u64 {
+        assert!(pool.place_order(1, id, true, 1_000_000_000, (id * ORDER_INTERVAL as u64) as u32));
+    }
+    assert_eq!(pool.read_config().twamm.expiries.len(), MAX_ORDER_EXPIRIES);
+    // ...so a new one can always join one of them
+    assert!(pool.place_order(2, 0, false, 1_000_000_000, MAX_ORDER_DURATION));
+
+    // Once the first expiry passes, a later expiry needs its slot
+    let first_expiry = pool.read_config().twamm.expiries[0].expiry_ts;
+    pool.warp(first_expiry + 1);
+    assert!(!pool.place_order(2, 1, false, 1_000_000_000, MAX_ORDER_DURATION));
+
+    // Running orders cannot be settled by others
+    assert!(!pool.settle_order(2, 1, 1));
+
+    // Anyone can settle the expired order, paying its owner the proceeds
+    let before = pool.snapshot().users[1];
+    assert!(pool.settle_order(2, 1, 0));
+    assert!(pool.snapshot().users[1].1 > before.1);
+    assert!(pool.is_closed(&pool.order(&pool.users[1].pubkey(), 0)));
+    assert!(pool.place_order(2, 1, false, 1_000_000_000, MAX_ORDER_DURATION));
+}
+
+#[test]
+fn opposing_orders_trade_with_each_other_before_the_pool() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    // On an interval boundary, so one-interval orders sell for exactly an interval
+    let start = 1_700_000_000 / ORDER_INTERVAL * ORDER_INTERVAL;
+    pool.warp(start);
+    let half = ORDER_INTERVAL / 2;
+
+    // 100_000 tokens per second on each side, at a pool price of 1
+    let amount = 100_000 * ORDER_INTERVAL as u64;
+    let sold_by_half = 100_000 * half as u64;
+    assert!(pool.place_order(1, 0, true, amount, ORDER_INTERVAL as u32));
+    assert!(pool.place_order(2, 0, false, amount, ORDER_INTERVAL as u32));
+    let reserves = pool.lp_reserves();
+    assert_eq!(reserves, (100_000_000_000, 100_000_000_000));
+
+    // Nothing has been bought yet
+    assert!(!pool.withdraw_order_proceeds(1, 0));
+
+    // Halfway through, each side bought what the other sold, without touching the LPs
+    pool.warp(start + half);
+    let before = pool.snapshot();
+    assert!(pool.withdraw_order_proceeds(1, 0));
+    let after = pool.snapshot();
+    assert_eq!(after.users[1].1 - before.users[1].1, sold_by_half);
+    assert_eq!(pool.lp_reserves(), reserves);
+
+    // Cancelling pays out the X bought and refunds the Y not sold yet
+    assert!(pool.cancel_order(2, 0));
+    let cancelled = pool.snapshot();
+    assert_eq!(cancelled.users[2].0 - after.users[2].0, sold_by_half);
+    assert_eq!(cancelled.users[2].1 - after.users[2].1, amount - sold_by_half);
+    assert!(pool.is_closed(&pool.order(&pool.users[2].pubkey(), 0)));
+    assert!(!pool.withdraw_order_proceeds(2, 0));
+
+    // Alone, the rest of the X order trades against the pool at the curve's price
+    let config = pool.read_config();
+    let quote =
+        swap_exact_in(&config, reserves.0, reserves.1, after.lp_supply, true, sold_by_half).unwrap();
+    pool.warp(start + ORDER_INTERVAL);
+    assert!(pool.withdraw_order_proceeds(1, 0));
+    let proceeds = pool.snapshot().users[1].1 - cancelled.users[1].1;
+    assert!(proceeds <= quote.amount_out && proceeds + 1 >= quote.amount_out);
+    assert_eq!(
+        pool.lp_reserves(),
+        (reserves.0 + sold_by_half, reserves.1 - quote.amount_out)
+    );
+
+    // The expired order has nothing left to pay out, and closing it frees its expiry
+    assert!(!pool.withdraw_order_proceeds(1, 0));
+    assert!(pool.cancel_order(1, 0));
+    assert!(pool.is_closed(&pool.order(&pool.users[1].pubkey(), 0)));
+    assert!(pool.read_config().twamm.expiries.is_empty());
+    pool.check_pool(&pool.snapshot());
+}
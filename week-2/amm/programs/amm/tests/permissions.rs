@@ -0,0 +1,46 @@
+//! Tests for permissioned pools
+//!
+//! Only users on the pool's allowlist may provide liquidity or trade, each within
+//! the roles the authority granted them.
+
+mod common;
+
+use amm::states::CurveType;
+
+use common::{Pool, Setup, USERS};
+
+#[test]
+fn permissioned_pools_admit_only_allowlisted_users() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let lp_supply = pool.snapshot().lp_supply;
+    assert!(pool.set_permissioned());
+
+    // Without a permission account nobody gets in, not even the first LP
+    assert!(!pool.swap(1, true, 1_000_000));
+    assert!(!pool.deposit(1, lp_supply / 100, u64::MAX, u64::MAX));
+    assert!(!pool.withdraw(0, 1_000));
+
+    // Traders may swap but not provide liquidity
+    assert!(pool.set_permission(1, false, true));
+    assert!(pool.swap(1, true, 1_000_000));
+    assert!(!pool.deposit(1, lp_supply / 100, u64::MAX, u64::MAX));
+
+    // LPs may provide liquidity but not swap
+    assert!(pool.set_permission(0, true, false));
+    assert!(pool.withdraw(0, 1_000));
+    assert!(pool.deposit(0, 1_000, u64::MAX, u64::MAX));
+    assert!(!pool.swap(0, true, 1_000_000));
+
+    // Revoked users are locked out again
+    assert!(pool.revoke_permission(1));
+    assert!(!pool.swap(1, true, 1_000_000));
+}
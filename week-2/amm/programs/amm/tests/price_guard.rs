@@ -0,0 +1,133 @@
+//! Tests for the price guard
+//!
+//! Swaps, zaps and flash swaps of a pool with a price guard, on constant product
+//! and StableSwap curves, checked against a locally written Pyth price update.
+
+mod common;
+
+use amm::curves::{StableSwap, U256};
+use amm::oracle::{PriceFeedMessage, PriceUpdate, VerificationLevel, PYTH_RECEIVER_ID};
+use amm::states::CurveType;
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::AnchorSerialize;
+use litesvm::LiteSVM;
+
+use common::{set_account, Pool, Setup, USERS};
+
+/// Writes a fully verified Pyth price update pricing a whole token X at
+/// `price * 10^exponent` whole token Y
+fn set_price_update(svm: &mut LiteSVM, address: Pubkey, price: i64, exponent: i32, publish_time: i64) {
+    let update = PriceUpdate {
+        write_authority: Pubkey::new_unique(),
+        verification_level: VerificationLevel::Full,
+        price_message: PriceFeedMessage {
+            feed_id: [7; 32],
+            price,
+            conf: 0,
+            exponent,
+            publish_time,
+            prev_publish_time: publish_time,
+            ema_price: price,
+            ema_conf: 0,
+        },
+        posted_slot: 0,
+    };
+    let mut data = PriceUpdate::DISCRIMINATOR.to_vec();
+    update.serialize(&mut data).unwrap();
+    set_account(svm, address, PYTH_RECEIVER_ID, data);
+}
+
+#[test]
+fn price_guard_keeps_swaps_near_the_oracle_price() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let mut clock = pool.svm.get_sysvar::<Clock>();
+
+    // Both mints have the same decimals and the oracle prices X at 1.00000000 Y
+    let oracle = Pubkey::new_unique();
+    set_price_update(&mut pool.svm, oracle, 100_000_000, -8, clock.unix_timestamp);
+    assert!(pool.set_price_guard(oracle, 100, 60));
+
+    // Selling 0.1% of the reserves moves the price by about 0.2%, well within the band
+    assert!(pool.swap(1, true, 100_000_000));
+    // Selling 1% moves it by about 2%
+    assert!(!pool.swap(1, true, 1_000_000_000));
+
+    // The market moves 5% away, and the pool is left outside the band
+    set_price_update(&mut pool.svm, oracle, 105_000_000, -8, clock.unix_timestamp);
+    // Trades pushing the pool further away are refused
+    assert!(!pool.swap(1, true, 100_000_000));
+    // Trades towards the oracle price go through, even if they end outside the band
+    assert!(pool.swap(1, false, 1_000_000_000));
+
+    // Stale prices halt swaps until the oracle is updated
+    clock.unix_timestamp += 61;
+    pool.svm.set_sysvar(&clock);
+    assert!(!pool.swap(1, false, 100_000_000));
+    set_price_update(&mut pool.svm, oracle, 105_000_000, -8, clock.unix_timestamp);
+    assert!(pool.swap(1, false, 100_000_000));
+
+    // Guarded pools cannot be traded without their oracle
+    pool.oracle = None;
+    assert!(!pool.swap(1, false, 100_000_000));
+    pool.oracle = Some(Pubkey::new_unique());
+    assert!(!pool.swap(1, false, 100_000_000));
+}
+
+#[test]
+fn price_guard_covers_zaps_flash_swaps_and_stable_pools() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 100_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let clock = pool.svm.get_sysvar::<Clock>();
+    let oracle = Pubkey::new_unique();
+    set_price_update(&mut pool.svm, oracle, 100_000_000, -8, clock.unix_timestamp);
+    assert!(pool.set_price_guard(oracle, 100, 60));
+
+    // Zaps swap part of the deposit internally, which moves the price like a swap
+    assert!(pool.deposit_single(1, true, 100_000_000));
+    assert!(!pool.deposit_single(1, true, 5_000_000_000));
+
+    // Flash swaps are held to the band too, however much they pay
+    assert!(!pool.flash_swap(1, (0, 2_000_000_000), (3_000_000_000, 0)));
+    assert!(pool.flash_swap(1, (0, 10_000_000), (10_100_000, 0)));
+
+    // A StableSwap pool 10:1 out of balance still prices X near 1 Y, not at 0.1 Y
+    let setup = Setup {
+        curve_type: CurveType::StableSwap,
+        amp: 100,
+        weights: None,
+        fee: 4,
+        protocol_fee: 0,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 10_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let (numerator, denominator) = StableSwap::new(100, 4)
+        .unwrap()
+        .marginal_price(100_000_000_000, 10_000_000_000)
+        .unwrap();
+    let price = (numerator * U256::from(100_000_000u64) / denominator).as_u64();
+    assert!(price > 50_000_000 && price < 100_000_000);
+    set_price_update(&mut pool.svm, oracle, price as i64, -8, clock.unix_timestamp);
+    assert!(pool.set_price_guard(oracle, 100, 60));
+
+    assert!(pool.swap(1, true, 100_000_000));
+    assert!(pool.swap(1, false, 100_000_000));
+    assert!(!pool.swap(1, true, 30_000_000_000));
+}
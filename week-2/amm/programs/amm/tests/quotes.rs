@@ -0,0 +1,55 @@
+//! Tests for the read-only quote instructions
+//!
+//! Quotes are checked against what the `swap`, `deposit` and `withdraw`
+//! instructions they quote then do.
+
+mod common;
+
+use amm::states::CurveType;
+use amm::{QuotedDeposit, QuotedSwap, QuotedWithdraw};
+
+use common::{Pool, Setup, USERS};
+
+#[test]
+fn quotes_match_what_the_instructions_do() {
+    let setup = Setup {
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        fee: 30,
+        protocol_fee: 1_667,
+        balances: vec![(1_000_000_000_000, 1_000_000_000_000); USERS],
+        initial_deposit: (100_000_000_000, 300_000_000_000),
+    };
+    let mut pool = Pool::new(&setup);
+    let virtual_price: u128 = pool.quote(amm::instruction::LpVirtualPrice {});
+
+    let quote: QuotedSwap = pool.quote(amm::instruction::QuoteSwap {
+        is_x: true,
+        amount_in: 1_000_000_000,
+    });
+    let before = pool.snapshot().users[1];
+    assert!(pool.swap(1, true, 1_000_000_000));
+    let after = pool.snapshot().users[1];
+    assert_eq!(before.0 - after.0, quote.amount_in);
+    assert_eq!(after.1 - before.1, quote.amount_out);
+
+    let quote: QuotedDeposit = pool.quote(amm::instruction::QuoteDeposit { lp_amount: 1_000_000 });
+    let before = pool.snapshot().users[1];
+    assert!(pool.deposit(1, 1_000_000, u64::MAX, u64::MAX));
+    let after = pool.snapshot().users[1];
+    assert_eq!(before.0 - after.0, quote.amount_x);
+    assert_eq!(before.1 - after.1, quote.amount_y);
+    assert_eq!(after.2 - before.2, quote.lp_amount);
+
+    let quote: QuotedWithdraw = pool.quote(amm::instruction::QuoteWithdraw { lp_amount: 500_000 });
+    let before = pool.snapshot().users[1];
+    assert!(pool.withdraw(1, 500_000));
+    let after = pool.snapshot().users[1];
+    assert_eq!(after.0 - before.0, quote.amount_x);
+    assert_eq!(after.1 - before.1, quote.amount_y);
+
+    // The swap fee left in the pool raised the LP token's virtual price
+    let grown: u128 = pool.quote(amm::instruction::LpVirtualPrice {});
+    assert!(grown > virtual_price);
+}
@@ -0,0 +1,147 @@
+//! Tests for the execution of long-term (TWAMM) orders
+//!
+//! These drive `Config` directly, without the program: opposing orders trade with
+//! each other at the pool price, only the side left over trades against the LPs,
+//! and each side's proceeds are shared between its orders by sale rate.
+
+use amm::curves::swap_exact_in;
+use amm::states::{Config, CurveType, Order, OrderExecution, Twamm};
+use anchor_lang::prelude::Pubkey;
+
+/// LP reserves of each token
+const RESERVE: u64 = 10_000_000;
+
+/// LP token supply
+const LP_SUPPLY: u64 = 1_000_000;
+
+/// Expiry of every order, a single interval after the pool's creation
+const EXPIRY: i64 = 3_600;
+
+/// A constant product pool with a 0.3% fee and no orders
+fn pool_config() -> Config {
+    Config {
+        seed: 0,
+        authority: None,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        curve_type: CurveType::ConstantProduct,
+        amp: 0,
+        weights: None,
+        weight_x: 0,
+        fee: 30,
+        protocol_fee: 0,
+        protocol_fees_x: 0,
+        protocol_fees_y: 0,
+        price_x_cumulative: 0,
+        price_y_cumulative: 0,
+        last_update_ts: 0,
+        flash_loan: None,
+        dynamic_fee: None,
+        price_guard: None,
+        twamm: Twamm::default(),
+        locked: false,
+        permissioned: false,
+        config_bump: 255,
+        lp_bump: 255,
+    }
+}
+
+/// Places an order selling `sale_rate` tokens per second until `EXPIRY`, and
+/// returns it along with the tokens it deposited
+fn place(config: &mut Config, is_x: bool, sale_rate: u64) -> (Order, u64) {
+    let amount = sale_rate * EXPIRY as u64;
+    config.add_order(is_x, sale_rate, EXPIRY, amount).unwrap();
+    let order = Order {
+        config: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        id: 0,
+        is_x,
+        sale_rate,
+        start_ts: 0,
+        expiry_ts: EXPIRY,
+        proceeds_per_rate_last: 0,
+        bump: 0,
+    };
+    (order, amount)
+}
+
+#[test]
+fn opposing_orders_of_equal_value_fill_each_other() {
+    let mut config = pool_config();
+    let (mut sell_x, deposit_x) = place(&mut config, true, 100);
+    let (mut sell_y, deposit_y) = place(&mut config, false, 100);
+    let (vault_x, vault_y) = (RESERVE + deposit_x, RESERVE + deposit_y);
+
+    let execution = config
+        .execute_virtual_orders_to(vault_x, vault_y, LP_SUPPLY, EXPIRY)
+        .unwrap();
+
+    // Both sides sold everything to each other at the pool price of 1
+    assert_eq!(
+        execution,
+        OrderExecution {
+            sold_x: deposit_x,
+            sold_y: deposit_y,
+            bought_x: deposit_y,
+            bought_y: deposit_x,
+        }
+    );
+    // Nothing went through the curve: the LPs neither traded nor earned a fee
+    assert_eq!(config.lp_reserves(vault_x, vault_y).unwrap(), (RESERVE, RESERVE));
+
+    // Each order is paid what the other one sold
+    assert_eq!(config.take_order_proceeds(&mut sell_x).unwrap(), deposit_y);
+    assert_eq!(config.take_order_proceeds(&mut sell_y).unwrap(), deposit_x);
+}
+
+#[test]
+fn only_the_excess_of_one_side_trades_against_the_pool() {
+    let mut config = pool_config();
+    let (mut sell_x, deposit_x) = place(&mut config, true, 200);
+    let (mut sell_y, deposit_y) = place(&mut config, false, 100);
+    let (vault_x, vault_y) = (RESERVE + deposit_x, RESERVE + deposit_y);
+
+    // The X left over once the Y sellers are filled is sold through the curve
+    let excess_x = deposit_x - deposit_y;
+    let quote = swap_exact_in(&config, RESERVE, RESERVE, LP_SUPPLY, true, excess_x).unwrap();
+
+    let execution = config
+        .execute_virtual_orders_to(vault_x, vault_y, LP_SUPPLY, EXPIRY)
+        .unwrap();
+    assert_eq!(execution.bought_x, deposit_y);
+    assert_eq!(execution.bought_y, deposit_y + quote.amount_out);
+    assert_eq!(
+        config.lp_reserves(vault_x, vault_y).unwrap(),
+        (RESERVE + excess_x, RESERVE - quote.amount_out)
+    );
+
+    // Selling all the X through the curve first would have paid the X sellers less
+    let sequential =
+        swap_exact_in(&pool_config(), RESERVE, RESERVE, LP_SUPPLY, true, deposit_x).unwrap();
+    assert!(execution.bought_y > sequential.amount_out);
+
+    // Proceeds are rounded down, never more than the orders bought
+    let proceeds_x = config.take_order_proceeds(&mut sell_x).unwrap();
+    let proceeds_y = config.take_order_proceeds(&mut sell_y).unwrap();
+    assert!(proceeds_x <= execution.bought_y && proceeds_x + 1 >= execution.bought_y);
+    assert_eq!(proceeds_y, deposit_y);
+}
+
+#[test]
+fn one_sided_orders_trade_against_the_pool() {
+    let mut config = pool_config();
+    let (_, deposit_x) = place(&mut config, true, 100);
+    let (vault_x, vault_y) = (RESERVE + deposit_x, RESERVE);
+
+    let quote = swap_exact_in(&config, RESERVE, RESERVE, LP_SUPPLY, true, deposit_x).unwrap();
+    let execution = config
+        .execute_virtual_orders_to(vault_x, vault_y, LP_SUPPLY, EXPIRY)
+        .unwrap();
+
+    assert_eq!(execution.bought_y, quote.amount_out);
+    assert_eq!(execution.bought_x, 0);
+    assert_eq!(
+        config.lp_reserves(vault_x, vault_y).unwrap(),
+        (RESERVE + deposit_x, RESERVE - quote.amount_out)
+    );
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Maximum number of pools a single route may pass through
+#[constant]
+pub const MAX_HOPS: u8 = 4;
+
+/// Number of `remaining_accounts` each hop of a route takes, in `amm::Swap` order:
+/// mint_x, mint_y, config, mint_lp, vault_x, vault_y, user_ata_x, user_ata_y,
+/// permission, oracle, token_program_x, token_program_y
+/// Pools that are not permissioned or have no price guard take the AMM program id
+/// in place of the permission or the oracle
+#[constant]
+pub const HOP_ACCOUNTS: u8 = 12;
\ No newline at end of file
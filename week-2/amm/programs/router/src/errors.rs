@@ -0,0 +1,19 @@
+use anchor_lang::error_code;
+
+#[error_code]
+pub enum RouterError {
+    #[msg("Route must contain at least one hop.")]
+    EmptyRoute,
+    #[msg("Route has too many hops.")]
+    TooManyHops,
+    #[msg("Route accounts must come in groups of one hop each.")]
+    InvalidRouteAccounts,
+    #[msg("Hop does not trade the token produced by the previous hop.")]
+    InvalidRoute,
+    #[msg("Invalid amount.")]
+    InvalidAmount,
+    #[msg("Slippage exceeded.")]
+    SlippageExceeded,
+    #[msg("Offer expired.")]
+    OfferExpired,
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub mod route;
+pub use route::*;
\ No newline at end of file
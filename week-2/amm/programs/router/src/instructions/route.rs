@@ -0,0 +1,177 @@
+use amm::cpi::accounts::Swap;
+use amm::program::Amm;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::constants::{HOP_ACCOUNTS, MAX_HOPS};
+use crate::errors::RouterError;
+
+/// Accounts required for a multi-hop swap through several AMM pools
+///
+/// The pools themselves are passed through `remaining_accounts`, `HOP_ACCOUNTS`
+/// per hop in the order the route visits them. Each group holds the accounts of
+/// `amm::Swap` that differ between pools, in the same order as that struct:
+/// mint_x, mint_y, config, mint_lp, vault_x, vault_y, user_ata_x, user_ata_y,
+/// permission, oracle, token_program_x, token_program_y. Pools that are not
+/// permissioned or have no price guard take the AMM program id in place of the
+/// permission or the oracle, as Anchor does for absent accounts.
+#[derive(Accounts)]
+pub struct Route<'info> {
+    /// The user who is performing the swap (must sign the transaction)
+    /// Mutable because they may need to pay for ATA creation if accounts don't exist
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The token the route starts from
+    pub mint_in: InterfaceAccount<'info, Mint>,
+
+    /// The token the route must end in
+    pub mint_out: InterfaceAccount<'info, Mint>,
+
+    /// Token program for the LP mints of the pools
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Associated Token program for ATA operations
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+    /// The AMM program every hop is executed by
+    pub amm_program: Program<'info, Amm>,
+}
+
+impl<'info> Route<'info> {
+    /// Swaps `amount_in` of `mint_in` into `mint_out` through a path of pools
+    ///
+    /// Each hop is a CPI into `amm::swap`. The direction of a hop is picked from the
+    /// token the previous hop produced, and its input is exactly what the user received
+    /// from that hop, so Token-2022 transfer fees on intermediate tokens are accounted for.
+    /// Hops are executed without their own slippage limit; only the final output is
+    /// checked against `min_amount_out`. Pools may use any curve type.
+    ///
+    /// # Arguments
+    /// * `hops` - Remaining accounts, `HOP_ACCOUNTS` per pool in route order
+    /// * `amount_in` - Amount of `mint_in` to swap
+    /// * `min_amount_out` - Minimum amount of `mint_out` expected (slippage protection)
+    /// * `expires_at` - Unix timestamp after which the transaction is rejected, 0 for no deadline
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn route(
+        &mut self,
+        hops: &'info [AccountInfo<'info>],
+        amount_in: u64,
+        min_amount_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        // Reject transactions that sat in a queue past the user's deadline
+        if expires_at != 0 {
+            require!(Clock::get()?.unix_timestamp <= expires_at, RouterError::OfferExpired);
+        }
+        require!(amount_in > 0, RouterError::InvalidAmount);
+        require!(!hops.is_empty(), RouterError::EmptyRoute);
+        require!(
+            hops.len() % HOP_ACCOUNTS as usize == 0,
+            RouterError::InvalidRouteAccounts
+        );
+        require!(
+            hops.len() / HOP_ACCOUNTS as usize <= MAX_HOPS as usize,
+            RouterError::TooManyHops
+        );
+
+        let mut mint = self.mint_in.key();
+        let mut amount = amount_in;
+
+        for hop in hops.chunks(HOP_ACCOUNTS as usize) {
+            // The hop must trade the token we currently hold
+            let is_x = if hop[0].key() == mint {
+                true
+            } else if hop[1].key() == mint {
+                false
+            } else {
+                return err!(RouterError::InvalidRoute);
+            };
+
+            let (mint_next, user_ata_out) = match is_x {
+                true => (hop[1].key(), &hop[7]),
+                false => (hop[0].key(), &hop[6]),
+            };
+
+            // Measure what the user actually receives, net of any transfer fee
+            let balance_before = token_balance(user_ata_out)?;
+            self.swap_hop(hop, is_x, amount, expires_at)?;
+            let balance_after = token_balance(user_ata_out)?;
+
+            amount = balance_after
+                .checked_sub(balance_before)
+                .ok_or(RouterError::InvalidAmount)?;
+            require!(amount > 0, RouterError::InvalidAmount);
+            mint = mint_next;
+        }
+
+        // The route must end in the requested token
+        require_keys_eq!(mint, self.mint_out.key(), RouterError::InvalidRoute);
+        // Slippage protection applies to the route as a whole
+        require!(amount >= min_amount_out, RouterError::SlippageExceeded);
+
+        Ok(())
+    }
+
+    /// Executes one hop of the route by CPI into `amm::swap`
+    ///
+    /// # Arguments
+    /// * `hop` - The hop's `HOP_ACCOUNTS` remaining accounts
+    /// * `is_x` - true if swapping token X for Y, false if swapping Y for X
+    /// * `amount_in` - Amount of input tokens to swap
+    /// * `expires_at` - Deadline of the route, forwarded to the pool
+    ///
+    /// # Returns
+    /// * `Result<()>` - Ok if successful, error otherwise
+    pub fn swap_hop(
+        &self,
+        hop: &'info [AccountInfo<'info>],
+        is_x: bool,
+        amount_in: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let cpi_accounts = Swap {
+            user: self.user.to_account_info(),
+            mint_x: hop[0].clone(),
+            mint_y: hop[1].clone(),
+            config: hop[2].clone(),
+            mint_lp: hop[3].clone(),
+            vault_x: hop[4].clone(),
+            vault_y: hop[5].clone(),
+            user_ata_x: hop[6].clone(),
+            user_ata_y: hop[7].clone(),
+            permission: Some(hop[8].clone()),
+            oracle: Some(hop[9].clone()),
+            token_program_x: hop[10].clone(),
+            token_program_y: hop[11].clone(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.amm_program.to_account_info(), cpi_accounts);
+
+        // Per-hop slippage is left open; the route checks its final output instead
+        amm::cpi::swap(cpi_context, is_x, amount_in, 0, expires_at)
+    }
+}
+
+/// Reads the balance of a user token account, treating a not yet created ATA as empty
+///
+/// # Arguments
+/// * `account` - The token account to read
+///
+/// # Returns
+/// * `Result<u64>` - The account's token balance
+fn token_balance(account: &AccountInfo) -> Result<u64> {
+    if account.data_is_empty() {
+        return Ok(0);
+    }
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    Ok(token_account.amount)
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+declare_id!("8tdce5ktJewzZwo1Sg6uVLtZukrJpY8Aeo2F6bX8L1Gq");
+
+pub mod constants;
+pub mod errors;
+pub mod instructions;
+
+pub use instructions::*;
+
+#[program]
+pub mod router {
+    use super::*;
+
+    pub fn route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Route<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .route(ctx.remaining_accounts, amount_in, min_amount_out, expires_at)
+    }
+}
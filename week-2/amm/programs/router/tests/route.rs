@@ -0,0 +1,437 @@
+//! Tests for multi-hop routes through AMM pools
+//!
+//! Every test deploys the AMM and the router in LiteSVM, creates a constant product
+//! A/B pool and a StableSwap B/C pool, and routes a user's tokens through them. A
+//! route pays out what swapping hop by hop would have, its slippage limit applies to
+//! the final output only, and routes that do not end in `mint_out` or skip a token
+//! are rejected without moving any funds.
+//!
+//! The tests load `target/deploy/amm.so` and `target/deploy/router.so`, so build the
+//! programs first: `anchor build && cargo test -p router`
+
+use amm::curves::swap_exact_in;
+use amm::states::{Config, CurveType, Factory, Registry};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+};
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize, InstructionData, Space, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use litesvm::LiteSVM;
+use router::constants::HOP_ACCOUNTS;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Lamports every wallet starts with, for fees and account rent
+const LAMPORTS: u64 = 100_000_000_000;
+
+/// Fee tier of every pool, 0.3%
+const FEE: u16 = 30;
+
+/// Tokens of each side the admin seeds every pool with
+const LIQUIDITY: u64 = 100_000_000_000;
+
+/// Token A the user starts with
+const BALANCE: u64 = 10_000_000_000;
+
+/// Amount of token A every route sells
+const AMOUNT_IN: u64 = 1_000_000_000;
+
+/// Accounts of a pool that a hop passes to `amm::swap`
+#[derive(Clone, Copy)]
+struct Pool {
+    mint_x: Pubkey,
+    mint_y: Pubkey,
+    config: Pubkey,
+    mint_lp: Pubkey,
+    vault_x: Pubkey,
+    vault_y: Pubkey,
+}
+
+/// The AMM and the router deployed in LiteSVM, with an A/B and a B/C pool
+struct Env {
+    svm: LiteSVM,
+    user: Keypair,
+    /// Mints A, B and C, in ascending order
+    mints: [Pubkey; 3],
+    /// Constant product pool of A and B
+    pool_ab: Pool,
+    /// StableSwap pool of B and C
+    pool_bc: Pool,
+}
+
+impl Env {
+    /// Deploys both programs, creates the pools and funds the user with token A
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        for (id, name) in [(amm::ID, "amm"), (router::ID, "router")] {
+            let path = format!("{}/../../target/deploy/{name}.so", env!("CARGO_MANIFEST_DIR"));
+            svm.add_program_from_file(id, path)
+                .expect("build the programs with `anchor build` before running these tests");
+        }
+
+        let admin = Keypair::new();
+        let user = Keypair::new();
+        svm.airdrop(&admin.pubkey(), LAMPORTS).unwrap();
+        svm.airdrop(&user.pubkey(), LAMPORTS).unwrap();
+
+        // `initialize_factory` is restricted to the upgrade authority, which a program
+        // loaded from a file does not have, so the factory is written directly
+        let (factory, factory_bump) = Pubkey::find_program_address(&[b"factory"], &amm::ID);
+        let (registry, registry_bump) = Pubkey::find_program_address(&[b"registry"], &amm::ID);
+        let factory_data = Factory {
+            admin: admin.pubkey(),
+            fee_tiers: vec![FEE],
+            pool_count: 0,
+            bump: factory_bump,
+        };
+        set_program_account(&mut svm, factory, &factory_data, 8 + Factory::INIT_SPACE);
+        let registry_data = Registry {
+            pools: Vec::new(),
+            bump: registry_bump,
+        };
+        set_program_account(&mut svm, registry, &registry_data, Registry::space(0));
+
+        let mut mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        mints.sort();
+        let [mint_a, mint_b, mint_c] = mints;
+        set_mint(&mut svm, mint_a, &admin.pubkey(), LIQUIDITY + BALANCE);
+        set_mint(&mut svm, mint_b, &admin.pubkey(), 2 * LIQUIDITY);
+        set_mint(&mut svm, mint_c, &admin.pubkey(), LIQUIDITY);
+        set_token_account(&mut svm, mint_a, &admin.pubkey(), LIQUIDITY);
+        set_token_account(&mut svm, mint_b, &admin.pubkey(), 2 * LIQUIDITY);
+        set_token_account(&mut svm, mint_c, &admin.pubkey(), LIQUIDITY);
+        set_token_account(&mut svm, mint_a, &user.pubkey(), BALANCE);
+
+        let mut env = Self {
+            svm,
+            user,
+            mints,
+            pool_ab: Pool::new(0, mint_a, mint_b),
+            pool_bc: Pool::new(1, mint_b, mint_c),
+        };
+        let (pool_ab, pool_bc) = (env.pool_ab, env.pool_bc);
+        env.create_pool(&admin, &pool_ab, CurveType::ConstantProduct, 0);
+        env.create_pool(&admin, &pool_bc, CurveType::StableSwap, 100);
+        env
+    }
+
+    /// Initializes `pool` and seeds it with `LIQUIDITY` of each token from `admin`
+    fn create_pool(&mut self, admin: &Keypair, pool: &Pool, curve_type: CurveType, amp: u64) {
+        let initialize = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Initialize {
+                admin: admin.pubkey(),
+                mint_x: pool.mint_x,
+                mint_y: pool.mint_y,
+                factory: pda(&[b"factory"]),
+                registry: pda(&[b"registry"]),
+                pool: pda(&[b"pool", pool.mint_x.as_ref(), pool.mint_y.as_ref(), &FEE.to_le_bytes()]),
+                config: pool.config,
+                mint_lp: pool.mint_lp,
+                locked_lp: pda(&[b"locked_lp", pool.config.as_ref()]),
+                vault_x: pool.vault_x,
+                vault_y: pool.vault_y,
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Initialize {
+                fee: FEE,
+                authority: Some(admin.pubkey()),
+                curve_type,
+                amp,
+                weights: None,
+            }
+            .data(),
+        };
+        assert!(self.send(initialize, admin), "initialize failed");
+
+        let deposit = Instruction {
+            program_id: amm::ID,
+            accounts: amm::accounts::Deposit {
+                user: admin.pubkey(),
+                mint_x: pool.mint_x,
+                mint_y: pool.mint_y,
+                config: pool.config,
+                mint_lp: pool.mint_lp,
+                vault_x: pool.vault_x,
+                vault_y: pool.vault_y,
+                user_ata_x: ata(&admin.pubkey(), &pool.mint_x),
+                user_ata_y: ata(&admin.pubkey(), &pool.mint_y),
+                user_ata_lp: ata(&admin.pubkey(), &pool.mint_lp),
+                permission: None,
+                lp_position: None,
+                oracle: None,
+                locked_lp: pda(&[b"locked_lp", pool.config.as_ref()]),
+                token_program_x: spl_token::ID,
+                token_program_y: spl_token::ID,
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: amm::instruction::Deposit {
+                amount: 1,
+                max_x: LIQUIDITY,
+                max_y: LIQUIDITY,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        assert!(self.send(deposit, admin), "initial deposit failed");
+    }
+
+    /// Routes `AMOUNT_IN` of `mint_in` through `hops` into `mint_out`
+    ///
+    /// # Returns
+    /// * `bool` - true if the transaction succeeded
+    fn route(&mut self, mint_in: Pubkey, mint_out: Pubkey, hops: &[Pool], min_amount_out: u64) -> bool {
+        let user = self.user.pubkey();
+        let mut accounts = router::accounts::Route {
+            user,
+            mint_in,
+            mint_out,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            amm_program: amm::ID,
+        }
+        .to_account_metas(None);
+        for hop in hops {
+            let hop_accounts = [
+                AccountMeta::new_readonly(hop.mint_x, false),
+                AccountMeta::new_readonly(hop.mint_y, false),
+                AccountMeta::new(hop.config, false),
+                AccountMeta::new_readonly(hop.mint_lp, false),
+                AccountMeta::new(hop.vault_x, false),
+                AccountMeta::new(hop.vault_y, false),
+                AccountMeta::new(ata(&user, &hop.mint_x), false),
+                AccountMeta::new(ata(&user, &hop.mint_y), false),
+                // Neither pool is permissioned or has a price guard
+                AccountMeta::new_readonly(amm::ID, false),
+                AccountMeta::new_readonly(amm::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ];
+            assert_eq!(hop_accounts.len(), HOP_ACCOUNTS as usize);
+            accounts.extend(hop_accounts);
+        }
+
+        let ix = Instruction {
+            program_id: router::ID,
+            accounts,
+            data: router::instruction::Route {
+                amount_in: AMOUNT_IN,
+                min_amount_out,
+                expires_at: 0,
+            }
+            .data(),
+        };
+        let user = self.user.insecure_clone();
+        self.send(ix, &user)
+    }
+
+    /// Sends `ix` signed and paid for by `signer`
+    ///
+    /// # Returns
+    /// * `bool` - true if the transaction succeeded
+    fn send(&mut self, ix: Instruction, signer: &Keypair) -> bool {
+        // The StableSwap solvers can take more than the default compute budget
+        let budget = ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+        let tx = Transaction::new_signed_with_payer(
+            &[budget, ix],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        let succeeded = self.svm.send_transaction(tx).is_ok();
+        // Identical transactions in a row would otherwise be rejected as duplicates
+        self.svm.expire_blockhash();
+        succeeded
+    }
+
+    /// Output of selling `amount_in` of token X (or Y) into `pool` at its current state
+    fn quote(&self, pool: &Pool, is_x: bool, amount_in: u64) -> u64 {
+        let account = self.svm.get_account(&pool.config).unwrap();
+        let config = Config::try_deserialize(&mut account.data.as_slice()).unwrap();
+        let (reserve_x, reserve_y) = config
+            .lp_reserves(self.token_balance(&pool.vault_x), self.token_balance(&pool.vault_y))
+            .unwrap();
+        let mint_lp = self.svm.get_account(&pool.mint_lp).unwrap();
+        let supply = Mint::unpack(&mint_lp.data).unwrap().supply;
+        swap_exact_in(&config, reserve_x, reserve_y, supply, is_x, amount_in)
+            .unwrap()
+            .amount_out
+    }
+
+    /// The user's balance of `mint`
+    fn balance(&self, mint: &Pubkey) -> u64 {
+        self.token_balance(&ata(&self.user.pubkey(), mint))
+    }
+
+    /// Every balance a route could move: the user's and both pools' vaults
+    fn balances(&self) -> Vec<u64> {
+        let user = self.mints.iter().map(|mint| self.balance(mint));
+        let vaults = [self.pool_ab, self.pool_bc]
+            .into_iter()
+            .flat_map(|pool| [pool.vault_x, pool.vault_y])
+            .map(|vault| self.token_balance(&vault));
+        user.chain(vaults).collect()
+    }
+
+    fn token_balance(&self, address: &Pubkey) -> u64 {
+        self.svm
+            .get_account(address)
+            .map(|account| TokenAccount::unpack(&account.data).unwrap().amount)
+            .unwrap_or(0)
+    }
+}
+
+impl Pool {
+    /// Addresses of the `seed`th pool created by the factory, trading `mint_x` for `mint_y`
+    fn new(seed: u64, mint_x: Pubkey, mint_y: Pubkey) -> Self {
+        let config = pda(&[b"config", &seed.to_le_bytes()]);
+        Self {
+            mint_x,
+            mint_y,
+            config,
+            mint_lp: pda(&[b"lp", config.as_ref()]),
+            vault_x: ata(&config, &mint_x),
+            vault_y: ata(&config, &mint_y),
+        }
+    }
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &amm::ID).0
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token::ID)
+}
+
+/// Writes an Anchor account owned by the AMM, padded to `space` bytes
+fn set_program_account<T: AccountSerialize>(svm: &mut LiteSVM, address: Pubkey, account: &T, space: usize) {
+    let mut data = Vec::with_capacity(space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    set_account(svm, address, amm::ID, data);
+}
+
+fn set_mint(svm: &mut LiteSVM, address: Pubkey, authority: &Pubkey, supply: u64) {
+    let mut data = vec![0; Mint::LEN];
+    let mint = Mint {
+        mint_authority: COption::Some(*authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    Mint::pack(mint, &mut data).unwrap();
+    set_account(svm, address, spl_token::ID, data);
+}
+
+/// Creates `owner`'s associated token account for `mint` holding `amount`
+fn set_token_account(svm: &mut LiteSVM, mint: Pubkey, owner: &Pubkey, amount: u64) {
+    let mut data = vec![0; TokenAccount::LEN];
+    let account = TokenAccount {
+        mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    TokenAccount::pack(account, &mut data).unwrap();
+    set_account(svm, ata(owner, &mint), spl_token::ID, data);
+}
+
+fn set_account(svm: &mut LiteSVM, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(address, account).unwrap();
+}
+
+#[test]
+fn routes_pay_out_what_swapping_hop_by_hop_would() {
+    let mut env = Env::new();
+    let [mint_a, mint_b, mint_c] = env.mints;
+    let (pool_ab, pool_bc) = (env.pool_ab, env.pool_bc);
+
+    // A → B through the constant product pool, then B → C through the StableSwap pool
+    let amount_b = env.quote(&pool_ab, true, AMOUNT_IN);
+    let amount_c = env.quote(&pool_bc, true, amount_b);
+    assert!(env.route(mint_a, mint_c, &[pool_ab, pool_bc], amount_c));
+    assert_eq!(env.balance(&mint_a), BALANCE - AMOUNT_IN);
+    assert_eq!(env.balance(&mint_b), 0);
+    assert_eq!(env.balance(&mint_c), amount_c);
+    assert_eq!(env.token_balance(&pool_bc.vault_x), LIQUIDITY + amount_b);
+
+    // Back through the same pools, selling token Y at every hop
+    let amount_b = env.quote(&pool_bc, false, AMOUNT_IN);
+    let amount_a = env.quote(&pool_ab, false, amount_b);
+    assert!(env.route(mint_c, mint_a, &[pool_bc, pool_ab], amount_a));
+    assert_eq!(env.balance(&mint_c), amount_c - AMOUNT_IN);
+    assert_eq!(env.balance(&mint_a), BALANCE - AMOUNT_IN + amount_a);
+}
+
+#[test]
+fn slippage_is_checked_on_the_final_output_only() {
+    let mut env = Env::new();
+    let [mint_a, _, mint_c] = env.mints;
+    let (pool_ab, pool_bc) = (env.pool_ab, env.pool_bc);
+    let amount_b = env.quote(&pool_ab, true, AMOUNT_IN);
+    let amount_c = env.quote(&pool_bc, true, amount_b);
+
+    // A single token short of the limit rolls back every hop
+    let before = env.balances();
+    assert!(!env.route(mint_a, mint_c, &[pool_ab, pool_bc], amount_c + 1));
+    assert_eq!(env.balances(), before);
+
+    // Hops have no limit of their own, exactly the quoted output of the route passes
+    assert!(env.route(mint_a, mint_c, &[pool_ab, pool_bc], amount_c));
+    assert_eq!(env.balance(&mint_c), amount_c);
+}
+
+#[test]
+fn routes_must_connect_and_end_in_mint_out() {
+    let mut env = Env::new();
+    let [mint_a, mint_b, mint_c] = env.mints;
+    let (pool_ab, pool_bc) = (env.pool_ab, env.pool_bc);
+    let before = env.balances();
+
+    // A route ending in B does not deliver the C the user asked for
+    assert!(!env.route(mint_a, mint_c, &[pool_ab], 0));
+    // The first hop does not trade token A
+    assert!(!env.route(mint_a, mint_c, &[pool_bc], 0));
+    // A round trip through the A/B pool ends in A, not C
+    assert!(!env.route(mint_a, mint_c, &[pool_ab, pool_ab], 0));
+    // No hops at all
+    assert!(!env.route(mint_a, mint_c, &[], 0));
+    assert_eq!(env.balances(), before);
+
+    // A single hop is a valid route when it ends in `mint_out`
+    assert!(env.route(mint_a, mint_b, &[pool_ab], 0));
+    assert!(env.balance(&mint_b) > 0);
+}
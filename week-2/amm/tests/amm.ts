@@ -20,7 +20,11 @@ describe("amm initialize", () => {
   let mintY: anchor.web3.PublicKey;
   let lpMint: anchor.web3.PublicKey;
 
+  let factoryPda: anchor.web3.PublicKey;
+  let registryPda: anchor.web3.PublicKey;
+  let poolPda: anchor.web3.PublicKey;
   let configPda: anchor.web3.PublicKey;
+  let lockedLp: anchor.web3.PublicKey;
   let vaultX: anchor.web3.PublicKey;
   let vaultY: anchor.web3.PublicKey;
 
@@ -28,36 +32,72 @@ describe("amm initialize", () => {
   let userAtaY: anchor.web3.PublicKey;
   let userLpAta: anchor.web3.PublicKey;
 
-  const seed = new anchor.BN(42);
-  const fee = 30;
+  let fee: number;
 
-  it("Initializes the AMM pool", async () => {
-    mintX = await createMint(connection, admin.payer, admin.publicKey, null, 6);
-    mintY = await createMint(connection, admin.payer, admin.publicKey, null, 6);
-
-    [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
-      program.programId
-    );
+  const findPda = (seeds: Buffer[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(seeds, program.programId)[0];
 
-    [lpMint] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("lp"), configPda.toBuffer()],
-      program.programId
-    );
+  it("Initializes the AMM pool", async () => {
+    factoryPda = findPda([Buffer.from("factory")]);
+    registryPda = findPda([Buffer.from("registry")]);
+
+    // The factory is created once per deployment, by the program's upgrade authority
+    let factory = await program.account.factory.fetchNullable(factoryPda);
+    if (factory === null) {
+      const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      await program.methods
+        .initializeFactory([30])
+        .accountsPartial({
+          admin: admin.publicKey,
+          factory: factoryPda,
+          registry: registryPda,
+          program: program.programId,
+          programData,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      factory = await program.account.factory.fetch(factoryPda);
+    }
+    fee = factory.feeTiers[0];
+
+    // Pools take their mints in ascending order
+    const mintA = await createMint(connection, admin.payer, admin.publicKey, null, 6);
+    const mintB = await createMint(connection, admin.payer, admin.publicKey, null, 6);
+    [mintX, mintY] = [mintA, mintB].sort((a, b) => a.toBuffer().compare(b.toBuffer()));
+
+    // The factory's pool counter is the new pool's seed
+    configPda = findPda([Buffer.from("config"), factory.poolCount.toArrayLike(Buffer, "le", 8)]);
+    poolPda = findPda([
+      Buffer.from("pool"),
+      mintX.toBuffer(),
+      mintY.toBuffer(),
+      new anchor.BN(fee).toArrayLike(Buffer, "le", 2),
+    ]);
+    lpMint = findPda([Buffer.from("lp"), configPda.toBuffer()]);
+    lockedLp = findPda([Buffer.from("locked_lp"), configPda.toBuffer()]);
 
     vaultX = await getAssociatedTokenAddress(mintX, configPda, true);
     vaultY = await getAssociatedTokenAddress(mintY, configPda, true);
 
     const tx = await program.methods
-      .initialize(seed, fee, null)
-      .accounts({
+      .initialize(fee, null, { constantProduct: {} }, new anchor.BN(0), null)
+      .accountsPartial({
         admin: admin.publicKey,
         mintX,
         mintY,
+        factory: factoryPda,
+        registry: registryPda,
+        pool: poolPda,
         config: configPda,
         mintLp: lpMint,
+        lockedLp,
         vaultX,
         vaultY,
+        tokenProgramX: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenProgramY: anchor.utils.token.TOKEN_PROGRAM_ID,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
         associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
@@ -80,23 +120,43 @@ describe("amm initialize", () => {
     userLpAta = ataLp.address;
   });
 
+  // Accounts shared by deposits, withdrawals and swaps on the pool; it is neither
+  // permissioned nor price guarded, and LP positions are not tracked
+  const poolAccounts = () => ({
+    user: admin.publicKey,
+    mintX,
+    mintY,
+    config: configPda,
+    mintLp: lpMint,
+    vaultX,
+    vaultY,
+    userAtaX,
+    userAtaY,
+    permission: null,
+    oracle: null,
+    tokenProgramX: anchor.utils.token.TOKEN_PROGRAM_ID,
+    tokenProgramY: anchor.utils.token.TOKEN_PROGRAM_ID,
+    tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+    associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  // Transactions are only accepted within a minute of being built
+  const deadline = () => new anchor.BN(Math.floor(Date.now() / 1000) + 60);
+
   it("Deposits liquidity into the pool", async () => {
-    const depositAmount = new anchor.BN(1_000_000);
+    // The first deposit pays exactly maxX and maxY, and mints at least depositAmount
+    const depositAmount = new anchor.BN(100_000);
     const maxX = new anchor.BN(500_000);
     const maxY = new anchor.BN(500_000);
 
     const tx = await program.methods
-      .deposit(depositAmount, maxX, maxY)
-      .accounts({
-        user: admin.publicKey,
-        userTokenX: userAtaX,
-        userTokenY: userAtaY,
-        userLp: userLpAta,
-        config: configPda,
-        vaultX,
-        vaultY,
-        lpMint,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+      .deposit(depositAmount, maxX, maxY, deadline())
+      .accountsPartial({
+        ...poolAccounts(),
+        userAtaLp: userLpAta,
+        lockedLp,
+        lpPosition: null,
       })
       .rpc();
 
@@ -105,24 +165,17 @@ describe("amm initialize", () => {
   });
 
   it("Withdraws liquidity from the pool", async () => {
-    const withdrawAmount = new anchor.BN(500_000);
-    const minX = new anchor.BN(100_000);
-    const minY = new anchor.BN(100_000);
+    const withdrawAmount = new anchor.BN(100_000);
+    const minX = new anchor.BN(90_000);
+    const minY = new anchor.BN(90_000);
 
     const tx = await program.methods
-      .withdraw(withdrawAmount, minX, minY)
-      .accounts({
-        user: admin.publicKey,
-        userTokenX: userAtaX,
-        userTokenY: userAtaY,
-        userLp: userLpAta,
-        config: configPda,
-        vaultX,
-        vaultY,
-        lpMint,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+      .withdraw(withdrawAmount, minX, minY, deadline())
+      .accountsPartial({
+        ...poolAccounts(),
+        userAtaLp: userLpAta,
+        lpPosition: null,
       })
-      .signers([admin.payer])
       .rpc();
 
     console.log(`https://explorer.solana.com/tx/${tx}?cluster=devnet`);
@@ -134,16 +187,8 @@ describe("amm initialize", () => {
     const minOut = new anchor.BN(50_000);
 
     const tx = await program.methods
-      .swap(true, amountIn, minOut)
-      .accounts({
-        user: admin.publicKey,
-        userTokenIn: userAtaX,
-        userTokenOut: userAtaY,
-        config: configPda,
-        vaultIn: vaultX,
-        vaultOut: vaultY,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-      })
+      .swap(true, amountIn, minOut, deadline())
+      .accountsPartial(poolAccounts())
       .rpc();
 
     console.log(`https://explorer.solana.com/tx/${tx}?cluster=devnet`);